@@ -0,0 +1,161 @@
+use crate::database::AlbumRating;
+
+// The two webview windows the sync flow drives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Window {
+    Music,
+    Rym,
+}
+
+impl Window {
+    // Webview label from tauri.conf.json
+    pub fn label(self) -> &'static str {
+        match self {
+            Window::Music => "music",
+            Window::Rym => "rym",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Window::Music => "Music",
+            Window::Rym => "RYM",
+        }
+    }
+}
+
+// What the sync flow does to the windows. The app implements it over its Tauri
+// webviews; tests use FakeBrowser.
+pub trait BrowserSurface: Send + Sync {
+    fn has_window(&self, window: Window) -> bool;
+    fn navigate(&self, window: Window, url: &str) -> Result<(), String>;
+    fn eval(&self, window: Window, js: &str);
+    // Shows and focuses
+    fn show(&self, window: Window);
+    fn hide(&self, window: Window);
+    fn is_visible(&self, window: Window) -> bool;
+    // Sends "rym-rating-updated" to both windows
+    fn emit_rating(&self, rating: &AlbumRating);
+
+    fn toast(&self, window: Window, message: &str) {
+        self.eval(window, &toast_js(message));
+    }
+}
+
+// The injected script defines window.showSyncToast
+pub fn toast_js(message: &str) -> String {
+    format!(
+        "if (window.showSyncToast) window.showSyncToast({})",
+        serde_json::to_string(message).unwrap_or_default()
+    )
+}
+
+#[cfg(test)]
+pub use fake::{BrowserEvent, FakeBrowser};
+
+#[cfg(test)]
+mod fake {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tokio::time::Instant;
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum BrowserEvent {
+        Navigate(Window, String),
+        // Toasts come through here too, via the trait's default `toast`
+        Eval(Window, String),
+        Show(Window),
+        Hide(Window),
+        // rym_url and status of the broadcast rating
+        Rating(String, Option<String>),
+    }
+
+    // Windows in memory: visibility, where each was sent and everything done to them.
+    // Clones share the same windows.
+    #[derive(Clone)]
+    pub struct FakeBrowser {
+        visible: Arc<Mutex<HashMap<Window, bool>>>,
+        events: Arc<Mutex<Vec<BrowserEvent>>>,
+        navigations: Arc<Mutex<Vec<(Window, String, Instant)>>>,
+    }
+
+    impl FakeBrowser {
+        // Both windows, with Apple Music in front like at startup
+        pub fn new() -> Self {
+            FakeBrowser {
+                visible: Arc::new(Mutex::new(HashMap::from([(Window::Music, true), (Window::Rym, false)]))),
+                events: Arc::new(Mutex::new(Vec::new())),
+                navigations: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+
+        pub fn without(window: Window) -> Self {
+            let browser = FakeBrowser::new();
+            browser.visible.lock().unwrap().remove(&window);
+            browser
+        }
+
+        // Everything done so far, emptying the log
+        pub fn take_events(&self) -> Vec<BrowserEvent> {
+            std::mem::take(&mut *self.events.lock().unwrap())
+        }
+
+        pub fn navigations(&self, window: Window) -> Vec<(String, Instant)> {
+            self.navigations
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(w, _, _)| *w == window)
+                .map(|(_, url, at)| (url.clone(), *at))
+                .collect()
+        }
+
+        fn record(&self, event: BrowserEvent) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    impl BrowserSurface for FakeBrowser {
+        fn has_window(&self, window: Window) -> bool {
+            self.visible.lock().unwrap().contains_key(&window)
+        }
+
+        fn navigate(&self, window: Window, url: &str) -> Result<(), String> {
+            if !self.has_window(window) {
+                return Err(format!("{} window not found", window.name()));
+            }
+            self.navigations.lock().unwrap().push((window, url.to_string(), Instant::now()));
+            self.record(BrowserEvent::Navigate(window, url.to_string()));
+            Ok(())
+        }
+
+        fn eval(&self, window: Window, js: &str) {
+            if self.has_window(window) {
+                self.record(BrowserEvent::Eval(window, js.to_string()));
+            }
+        }
+
+        fn show(&self, window: Window) {
+            if let Some(visible) = self.visible.lock().unwrap().get_mut(&window) {
+                *visible = true;
+            }
+            self.record(BrowserEvent::Show(window));
+        }
+
+        fn hide(&self, window: Window) {
+            if let Some(visible) = self.visible.lock().unwrap().get_mut(&window) {
+                *visible = false;
+            }
+            self.record(BrowserEvent::Hide(window));
+        }
+
+        fn is_visible(&self, window: Window) -> bool {
+            self.visible.lock().unwrap().get(&window).copied().unwrap_or(false)
+        }
+
+        fn emit_rating(&self, rating: &AlbumRating) {
+            self.record(BrowserEvent::Rating(rating.rym_url.clone(), rating.status.clone()));
+        }
+    }
+}
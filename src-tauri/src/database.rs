@@ -1,7 +1,339 @@
-use rusqlite::{Connection, Result};
-use serde::{Deserialize, Serialize};
+use crate::matcher;
+use crate::migrations;
+use crate::release_date::ReleaseDate;
+use rusqlite::{Connection, OptionalExtension, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::PathBuf;
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackRating {
+    pub number: String, // "1", "A2", "" for unnumbered sub-tracks
+    pub title: String,
+    #[serde(default)]
+    pub duration: String,
+    pub rating: Option<f32>,
+    pub count: Option<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Review {
+    #[serde(default)]
+    pub id: String, // RYM review id, empty if the page didn't expose one
+    pub reviewer: String,
+    #[serde(default)]
+    pub date: String,
+    pub rating: Option<f32>,
+    #[serde(default, alias = "text")]
+    pub body: String,
+}
+
+impl Review {
+    // RYM allows one review per user per release, so the reviewer is a safe fallback key
+    pub fn stable_id(&self) -> String {
+        if !self.id.is_empty() {
+            self.id.clone()
+        } else {
+            format!("user:{}", self.reviewer.trim().to_lowercase())
+        }
+    }
+}
+
+// Older scraper payloads (and Supabase rows) carry tracks/reviews as a JSON string
+fn deserialize_json_list<'de, D, T>(deserializer: D) -> std::result::Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum JsonList<T> {
+        List(Vec<T>),
+        Json(String),
+    }
+
+    Ok(match Option::<JsonList<T>>::deserialize(deserializer)? {
+        Some(JsonList::List(items)) => items,
+        Some(JsonList::Json(json)) => parse_json_list(&json),
+        None => Vec::new(),
+    })
+}
+
+pub fn parse_json_list<T: DeserializeOwned>(json: &str) -> Vec<T> {
+    serde_json::from_str(json).unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RatingSnapshot {
+    pub rym_rating: f32,
+    pub rating_count: i32,
+    pub timestamp: i64,
+}
+
+// An album whose score moved between its two most recent snapshots
+#[derive(Debug, Clone, Serialize)]
+pub struct RatingMove {
+    pub artist_name: String,
+    pub album_name: String,
+    pub rym_url: String,
+    pub previous: RatingSnapshot,
+    pub current: RatingSnapshot,
+}
+
+// A manual match from an Apple Music artist/album to a RYM release page
+#[derive(Debug, Clone, Serialize)]
+pub struct Alias {
+    pub id: i64,
+    pub artist_name: String,
+    pub album_name: String,
+    pub rym_url: String,
+    pub created_at: i64,
+}
+
+// One entry of an artist's discography as listed on their RYM page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArtistRelease {
+    pub release_type: String, // "album", "ep", "single", "compilation", "live", ...
+    pub title: String,
+    pub rym_url: String,
+    pub year: Option<i32>,
+    pub rating: Option<f32>,
+    pub count: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Artist {
+    pub name: String,
+    pub rym_url: String,
+    pub formed: Option<String>,
+    pub born: Option<String>,
+    pub genres: String,
+    #[serde(default)]
+    pub releases: Vec<ArtistRelease>,
+    pub timestamp: i64,
+}
+
+// One ranked release on a RYM chart page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChartEntry {
+    pub position: u32,
+    pub artist_name: String,
+    pub release_name: String,
+    pub rym_url: String,
+    pub year: Option<i32>,
+    pub release_date: String,
+    pub genres: String,
+    pub secondary_genres: Option<String>,
+    pub rating: Option<f32>,
+    pub count: Option<i32>,
+    pub reviews: Option<i32>,
+}
+
+// A chart as published on one date; `rym_url` is the chart's first page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chart {
+    pub title: String,
+    pub rym_url: String,
+    pub chart_date: String, // "2025-12-16"
+    #[serde(default)]
+    pub entries: Vec<ChartEntry>,
+    pub timestamp: i64,
+}
+
+// A chart entry compared with the previous snapshot of the same chart
+#[derive(Debug, Clone, Serialize)]
+pub struct ChartMove {
+    pub entry: ChartEntry,
+    pub previous_position: Option<u32>, // None for new entries
+    pub change: Option<i64>,            // positive when the release climbed
+}
+
+// One item of an imported RYM list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CollectionEntry {
+    pub position: u32,
+    pub entry_type: String, // "album", "ep", "song", ... taken from the RYM URL
+    pub artist_name: String,
+    pub title: String,
+    pub rym_url: String,
+    pub year: Option<i32>,
+    pub description: Option<String>,
+}
+
+// A RYM user list imported for walking through in Apple Music.
+// `cursor` is the position of the entry last stepped to (0 before the first).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collection {
+    #[serde(default)]
+    pub id: i64,
+    pub title: String,
+    pub author: String,
+    pub rym_url: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub entries: Vec<CollectionEntry>,
+    #[serde(default)]
+    pub entry_count: u32,
+    #[serde(default)]
+    pub cursor: u32,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenreLink {
+    pub name: String,
+    pub rym_url: String,
+}
+
+// One of the top-ranked releases shown on a genre page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenreRelease {
+    pub position: u32,
+    pub title: String,
+    pub artist_name: String,
+    pub rym_url: String,
+}
+
+// A node of the RYM genre tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Genre {
+    pub name: String,
+    pub rym_url: String,
+    #[serde(default)]
+    pub parents: Vec<GenreLink>, // outermost first, direct parent last
+    pub summary: Option<String>,     // one-line description
+    pub description: Option<String>, // full description from the genre page
+    pub akas: String,
+    pub release_count: Option<i32>,
+    pub top_level: bool,
+    #[serde(default)]
+    pub top_releases: Vec<GenreRelease>,
+    pub timestamp: i64,
+}
+
+// Cached albums whose primary genres fall under one top-level genre
+#[derive(Debug, Clone, Serialize)]
+pub struct GenreGroup {
+    pub genre: GenreLink,
+    pub albums: Vec<GenreAlbum>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GenreAlbum {
+    pub artist_name: String,
+    pub album_name: String,
+    pub rym_url: String,
+    pub rym_rating: f32,
+    pub genres: String,
+}
+
+// A release a song appears on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SongAppearance {
+    pub title: String,
+    pub rym_url: String,
+    pub track_number: Option<String>, // "1", "A2"; only known for the release in the song header
+}
+
+// A song with its own RYM page, rated separately from the releases it appears on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SongRating {
+    pub title: String,
+    pub artist_name: String,
+    pub rym_url: String,
+    pub rating: Option<f32>, // None until the song has ratings
+    pub rating_count: i32,
+    pub genres: String,
+    pub secondary_genres: Option<String>,
+    pub release_date: String,
+    #[serde(default)]
+    pub appearances: Vec<SongAppearance>,
+    pub timestamp: i64,
+}
+
+// A streaming link RYM lists for a release
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MediaLink {
+    pub service: String, // RYM's key: "applemusic", "spotify", "bandcamp", "youtube", ...
+    pub url: String,
+    pub default: bool, // RYM's pick when a service has several links
+}
+
+// A new or upcoming release from RYM's new music page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpcomingRelease {
+    pub release_type: String, // "album", "ep", "additional", ... taken from the URL
+    pub title: String,
+    pub artist_name: String, // "TOBi & Real Bad Man"
+    #[serde(default)]
+    pub artist_urls: Vec<String>,
+    pub rym_url: String,
+    pub release_date: String, // "21 November 2025", or just "December 2025"
+    pub genres: String,
+    pub rating: Option<f32>,
+    pub count: Option<i32>,
+    pub wants: Option<i32>,
+    pub timestamp: i64,
+}
+
+// Narrows the new releases feed to genres the cache rates highly: a genre qualifies
+// when at least `min_albums` cached albums list it and they average `min_rating`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct NewReleaseFilter {
+    pub min_rating: f32,
+    pub min_albums: u32,
+    pub upcoming_only: bool,
+    pub limit: Option<u32>,
+}
+
+impl Default for NewReleaseFilter {
+    fn default() -> Self {
+        NewReleaseFilter { min_rating: 3.5, min_albums: 2, upcoming_only: false, limit: None }
+    }
+}
+
+// How urgently a RYM page is wanted; higher goes first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FetchPriority {
+    Prefetch = 0,
+    Refresh = 1,
+    Foreground = 2,
+}
+
+impl FetchPriority {
+    fn from_i64(n: i64) -> Self {
+        match n {
+            2 => FetchPriority::Foreground,
+            1 => FetchPriority::Refresh,
+            _ => FetchPriority::Prefetch,
+        }
+    }
+}
+
+// A RYM page waiting in the fetch queue
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueuedFetch {
+    pub url: String,
+    pub priority: FetchPriority,
+    pub enqueued_at: i64,
+}
+
+// What the background refresher needs to know about a cached album
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedAlbum {
+    pub album_name: String,
+    pub artist_name: String,
+    pub rym_url: String,
+    pub release_date: String,
+    pub rating_count: i32,
+    pub timestamp: i64,
+    // Score change between the album's last two snapshots, if it has two
+    pub score_change: Option<f32>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AlbumRating {
     pub album_name: String,
@@ -14,8 +346,10 @@ pub struct AlbumRating {
     pub descriptors: Option<String>,
     pub language: Option<String>,
     pub rank: Option<String>,
-    pub track_ratings: Option<String>, // JSON
-    pub reviews: Option<String>,       // JSON
+    #[serde(default, deserialize_with = "deserialize_json_list")]
+    pub track_ratings: Vec<TrackRating>,
+    #[serde(default, deserialize_with = "deserialize_json_list")]
+    pub reviews: Vec<Review>,
     pub release_date: String,
     pub timestamp: i64,
     #[serde(skip_deserializing, default)]
@@ -24,165 +358,475 @@ pub struct AlbumRating {
 
 pub struct Database {
     conn: Connection,
+    match_threshold: f32,
 }
 
-impl Database {
-    pub fn new(db_path: PathBuf) -> Result<Self> {
-        let conn = Connection::open(db_path)?;
-        
-        // Create table if it doesn't exist
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS album_ratings (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                album_name TEXT NOT NULL,
-                artist_name TEXT NOT NULL,
-                rym_rating REAL NOT NULL,
-                rating_count INTEGER NOT NULL,
-                rym_url TEXT NOT NULL,
-                genres TEXT NOT NULL DEFAULT '',
-                secondary_genres TEXT DEFAULT '',
-                descriptors TEXT DEFAULT '',
-                language TEXT DEFAULT '',
-                rank TEXT DEFAULT '',
-                track_ratings TEXT DEFAULT '',
-                reviews TEXT DEFAULT '',
-                release_date TEXT NOT NULL DEFAULT '',
-                timestamp INTEGER NOT NULL,
-                UNIQUE(album_name, artist_name)
-            )",
-            [],
-        )?;
-
-        // COMPREHENSIVE MIGRATIONS
-        let columns = [
-            ("genres", "TEXT NOT NULL DEFAULT ''"),
-            ("secondary_genres", "TEXT DEFAULT ''"),
-            ("descriptors", "TEXT DEFAULT ''"),
-            ("language", "TEXT DEFAULT ''"),
-            ("rank", "TEXT DEFAULT ''"),
-            ("track_ratings", "TEXT DEFAULT ''"),
-            ("reviews", "TEXT DEFAULT ''"),
-            ("release_date", "TEXT NOT NULL DEFAULT ''"),
-        ];
+// Lookup key for fuzzy matching: diacritics and full-width forms folded, lowercase,
+// bracketed parts removed, alphanumerics only.
+pub fn normalize_key(s: &str) -> String {
+    let s = matcher::fold_diacritics(s).to_lowercase();
+    // Remove everything in brackets or parentheses
+    let mut result = String::new();
+    let mut depth = 0;
+    for c in s.chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = (depth as i32 - 1).max(0) as u32,
+            _ if depth == 0 => result.push(c),
+            _ => {}
+        }
+    }
+    // Keep only alphanumeric
+    result.chars().filter(|c| c.is_alphanumeric()).collect::<String>()
+}
+
+// Every key a name is indexed under: the native form plus, for non-Latin names, the
+// romanization RYM shows in brackets ("أحمد [Ahmed]" -> "أحمد", "ahmed").
+pub fn key_variants(s: &str) -> Vec<String> {
+    let mut keys = vec![normalize_key(s)];
+    if let Some(romanized) = matcher::romanization(s) {
+        keys.push(normalize_key(romanized));
+    }
+    keys.retain(|k| !k.is_empty());
+    keys.dedup();
+    keys
+}
 
-        for (col, def) in columns {
-            let query = format!("ALTER TABLE album_ratings ADD COLUMN {} {}", col, def);
-            let _ = conn.execute(&query, []);
+// Writes the album_keys rows for one release: every album variant paired with every artist variant
+pub fn index_album_keys(conn: &Connection, album_id: i64, album_name: &str, artist_name: &str) -> Result<()> {
+    conn.execute("DELETE FROM album_keys WHERE album_id = ?1", [album_id])?;
+    let mut stmt = conn.prepare("INSERT OR IGNORE INTO album_keys (album_id, album_key, artist_key) VALUES (?1, ?2, ?3)")?;
+    for album_key in key_variants(album_name) {
+        for artist_key in key_variants(artist_name) {
+            stmt.execute((album_id, &album_key, &artist_key))?;
         }
-        
-        Ok(Database { conn })
+    }
+    Ok(())
+}
+
+const KEY_LOOKUP: &str = "SELECT album_id FROM album_keys WHERE album_key = ?1 AND artist_key = ?2 LIMIT 1";
+
+// Releases worth ranking against a lookup that missed: same artist key, or an album key
+// starting with the same few characters. Keys are alphanumeric, so GLOB needs no escaping.
+const FUZZY_CANDIDATES: &str = "SELECT DISTINCT r.id, r.album_name, r.artist_name
+    FROM album_keys k JOIN album_ratings r ON r.id = k.album_id
+    WHERE k.artist_key = ?1 OR k.album_key GLOB ?2";
+const CANDIDATE_PREFIX_CHARS: usize = 4;
+
+// GLOB pattern for FUZZY_CANDIDATES. An empty key would become "*" and match every row,
+// so it gets NULL, which matches nothing and leaves the artist key to find candidates.
+fn candidate_pattern(album_key: &str) -> Option<String> {
+    let prefix: String = album_key.chars().take(CANDIDATE_PREFIX_CHARS).collect();
+    (!prefix.is_empty()).then(|| format!("{}*", prefix))
+}
+
+const ALBUM_COLUMNS: &str = "id, album_name, artist_name, rym_rating, rating_count, rym_url, genres,
+    secondary_genres, descriptors, language, rank, track_ratings, reviews,
+    release_date, timestamp";
+
+// Row mapper for "SELECT position, entry_type, artist_name, title, rym_url, year, description"
+fn map_collection_entry(row: &rusqlite::Row) -> rusqlite::Result<CollectionEntry> {
+    Ok(CollectionEntry {
+        position: row.get(0)?,
+        entry_type: row.get(1)?,
+        artist_name: row.get(2)?,
+        title: row.get(3)?,
+        rym_url: row.get(4)?,
+        year: row.get(5)?,
+        description: row.get(6)?,
+    })
+}
+
+const UPCOMING_COLUMNS: &str = "release_type, title, artist_name, artist_urls, rym_url, release_date,
+    genres, rating, count, wants, timestamp";
+
+fn map_upcoming_release(row: &rusqlite::Row) -> rusqlite::Result<UpcomingRelease> {
+    Ok(UpcomingRelease {
+        release_type: row.get(0)?,
+        title: row.get(1)?,
+        artist_name: row.get(2)?,
+        artist_urls: row.get::<_, String>(3)?.lines().map(str::to_string).collect(),
+        rym_url: row.get(4)?,
+        release_date: row.get(5)?,
+        genres: row.get(6)?,
+        rating: row.get(7)?,
+        count: row.get(8)?,
+        wants: row.get(9)?,
+        timestamp: row.get(10)?,
+    })
+}
+
+fn map_queued_fetch(row: &rusqlite::Row) -> rusqlite::Result<QueuedFetch> {
+    Ok(QueuedFetch {
+        url: row.get(0)?,
+        priority: FetchPriority::from_i64(row.get(1)?),
+        enqueued_at: row.get(2)?,
+    })
+}
+
+// Guards walks up the genre tree against a parent loop in scraped data
+const MAX_GENRE_DEPTH: i64 = 32;
+
+// Upserts one genre row. A page only knows part of a genre (the index has no parents,
+// a breadcrumb only names its parents), so missing fields keep their stored values.
+fn upsert_genre(conn: &Connection, genre: &Genre, parent_id: Option<i64>) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO genres (name, name_key, rym_url, parent_id, top_level, summary, description, akas, release_count, timestamp)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+         ON CONFLICT(rym_url) DO UPDATE SET
+            name = excluded.name,
+            name_key = excluded.name_key,
+            parent_id = COALESCE(excluded.parent_id, genres.parent_id),
+            top_level = MAX(excluded.top_level, genres.top_level),
+            summary = COALESCE(excluded.summary, genres.summary),
+            description = COALESCE(excluded.description, genres.description),
+            akas = CASE WHEN excluded.akas = '' THEN genres.akas ELSE excluded.akas END,
+            release_count = COALESCE(excluded.release_count, genres.release_count),
+            timestamp = MAX(excluded.timestamp, genres.timestamp)",
+        rusqlite::params![
+            &genre.name,
+            normalize_key(&genre.name),
+            &genre.rym_url,
+            parent_id,
+            genre.top_level,
+            &genre.summary,
+            &genre.description,
+            &genre.akas,
+            genre.release_count,
+            genre.timestamp,
+        ],
+    )?;
+    conn.query_row("SELECT id FROM genres WHERE rym_url = ?1", [&genre.rym_url], |row| row.get(0))
+}
+
+// Writes a genre with its parent chain (creating bare rows for parents not seen yet)
+// and, if the page listed any, its top releases
+fn write_genre(conn: &Connection, genre: &Genre) -> Result<()> {
+    let mut parent_id = None;
+    for parent in &genre.parents {
+        let node = Genre {
+            name: parent.name.clone(),
+            rym_url: parent.rym_url.clone(),
+            parents: Vec::new(),
+            summary: None,
+            description: None,
+            akas: String::new(),
+            release_count: None,
+            top_level: false,
+            top_releases: Vec::new(),
+            timestamp: 0,
+        };
+        parent_id = Some(upsert_genre(conn, &node, parent_id)?);
+    }
+    let genre_id = upsert_genre(conn, genre, parent_id)?;
+
+    if !genre.top_releases.is_empty() {
+        conn.execute("DELETE FROM genre_releases WHERE genre_id = ?1", [genre_id])?;
+        for release in &genre.top_releases {
+            conn.execute(
+                "INSERT INTO genre_releases (genre_id, position, title, artist_name, rym_url) VALUES (?1, ?2, ?3, ?4, ?5)",
+                (genre_id, release.position, &release.title, &release.artist_name, &release.rym_url),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+impl Database {
+    pub fn new(db_path: PathBuf) -> std::result::Result<Self, String> {
+        let mut conn = Connection::open(db_path).map_err(|e| format!("Failed to open database: {}", e))?;
+        conn.execute_batch("PRAGMA foreign_keys = ON;")
+            .map_err(|e| format!("Failed to enable foreign keys: {}", e))?;
+
+        migrations::run_migrations(&mut conn)?;
+
+        Ok(Database { conn, match_threshold: matcher::DEFAULT_THRESHOLD })
     }
     
     pub fn get_rating(&self, album_name: &str, artist_name: &str) -> Result<Option<AlbumRating>> {
-        // Robust normalization helper
-        let normalize = |s: &str| {
-            let s = s.to_lowercase();
-            // Remove everything in brackets or parentheses
-            let mut result = String::new();
-            let mut depth = 0;
-            for c in s.chars() {
-                match c {
-                    '(' | '[' => depth += 1,
-                    ')' | ']' => depth = (depth as i32 - 1).max(0) as u32,
-                    _ if depth == 0 => result.push(c),
-                    _ => {}
-                }
-            }
-            // Keep only alphanumeric
-            result.chars().filter(|c| c.is_alphanumeric()).collect::<String>()
-        };
+        Ok(self.find_album(album_name, artist_name)?.map(|(_, rating)| rating))
+    }
+
+    pub fn match_threshold(&self) -> f32 {
+        self.match_threshold
+    }
 
-        let norm_album = normalize(album_name);
-        let norm_artist = normalize(artist_name);
+    pub fn set_match_threshold(&mut self, threshold: f32) {
+        self.match_threshold = threshold;
+    }
 
+    // Same lookup as `get_rating`, but also returns the row id for child tables
+    fn find_album(&self, album_name: &str, artist_name: &str) -> Result<Option<(i64, AlbumRating)>> {
         println!("RYM-DATABASE: Querying \"{}\" by \"{}\"", album_name, artist_name);
-        println!("RYM-DATABASE: Normalized search key: \"{}\" | \"{}\"", norm_album, norm_artist);
+        println!(
+            "RYM-DATABASE: Normalized search keys: {:?} | {:?}",
+            key_variants(album_name),
+            key_variants(artist_name)
+        );
 
-        // 1. Try exact match (LOWER to handle case-insensitivity)
-        let mut stmt = self.conn.prepare(
-            "SELECT album_name, artist_name, rym_rating, rating_count, rym_url, genres, 
-                    secondary_genres, descriptors, language, rank, track_ratings, reviews,
-                    release_date, timestamp 
-             FROM album_ratings 
-             WHERE LOWER(album_name) = LOWER(?1) AND LOWER(artist_name) = LOWER(?2)"
-        )?;
+        // 0. Manual matches win over anything name-based
+        if let Some(url) = self.get_alias_url(album_name, artist_name)? {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT {} FROM album_ratings WHERE rym_url = ?1 ORDER BY timestamp DESC LIMIT 1",
+                ALBUM_COLUMNS
+            ))?;
+            if let Some((id, mut r)) = stmt.query_row([&url], |row| self.map_row(row)).optional()? {
+                println!("RYM-DATABASE: ✓ Found via manual alias: {}", url);
+                self.attach_children(id, &mut r)?;
+                // Report it under the Apple Music names so the UI recognises it as the current album
+                r.album_name = album_name.to_string();
+                r.artist_name = artist_name.to_string();
+                return Ok(Some((id, r)));
+            }
+            println!("RYM-DATABASE: Alias points to {} but it isn't cached yet.", url);
+        }
+
+        // 1. Try exact match (NOCASE to handle case-insensitivity)
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM album_ratings 
+             WHERE album_name = ?1 COLLATE NOCASE AND artist_name = ?2 COLLATE NOCASE",
+            ALBUM_COLUMNS
+        ))?;
         
-        let row = stmt.query_row([album_name, artist_name], |row| self.map_row(row));
+        let row = stmt.query_row([album_name, artist_name], |row| self.map_row(row)).optional()?;
         
-        if let Ok(r) = row {
+        if let Some((id, mut r)) = row {
             println!("RYM-DATABASE: ✓ Found exact match (case-insensitive).");
-            return Ok(Some(r));
+            self.attach_children(id, &mut r)?;
+            return Ok(Some((id, r)));
+        }
+
+        // 2. Fuzzy match on the indexed normalized keys (native and romanized)
+        let mut stmt = self.conn.prepare(&format!("SELECT {} FROM album_ratings WHERE id = ({})", ALBUM_COLUMNS, KEY_LOOKUP))?;
+        for album_key in key_variants(album_name) {
+            for artist_key in key_variants(artist_name) {
+                let row = stmt.query_row([&album_key, &artist_key], |row| self.map_row(row)).optional()?;
+
+                if let Some((id, mut r)) = row {
+                    println!("RYM-DATABASE: ✓ Found match via fuzzy normalization: \"{}\"", r.album_name);
+                    self.attach_children(id, &mut r)?;
+                    return Ok(Some((id, r)));
+                }
+            }
         }
 
-        // 2. Fuzzy match
-        let mut stmt = self.conn.prepare("SELECT * FROM album_ratings")?;
-        let mut rows = stmt.query([])?;
-        while let Some(row) = rows.next()? {
-            let db_album: String = row.get(1)?;
-            let db_artist: String = row.get(2)?;
-            
-            if normalize(&db_album) == norm_album && normalize(&db_artist) == norm_artist {
-                println!("RYM-DATABASE: ✓ Found match via fuzzy normalization: \"{}\"", db_album);
-                return Ok(Some(self.map_row(row)?));
+        // 3. Ranked similarity match over releases by the same artist key or with the same
+        // album key prefix, both served by album_keys indexes
+        let candidates = {
+            let mut stmt = self.conn.prepare(FUZZY_CANDIDATES)?;
+            let mut rows = BTreeMap::new();
+            for album_key in key_variants(album_name) {
+                let pattern = candidate_pattern(&album_key);
+                for artist_key in key_variants(artist_name) {
+                    let found = stmt.query_map((&artist_key, &pattern), |row| {
+                        Ok((row.get::<_, i64>(0)?, (row.get::<_, String>(1)?, row.get::<_, String>(2)?)))
+                    })?;
+                    for row in found {
+                        let (id, names) = row?;
+                        rows.insert(id, names);
+                    }
+                }
             }
+            println!("RYM-DATABASE: Ranking {} similar candidates", rows.len());
+            matcher::rank(album_name, artist_name, rows.into_iter().map(|(id, (album, artist))| (id, album, artist)))
+        };
+        for c in candidates.iter().take(3) {
+            println!("RYM-DATABASE:   candidate {:.3} \"{}\" by \"{}\"", c.score, c.album_name, c.artist_name);
+        }
+
+        if let Some(best) = candidates.first().filter(|c| c.score >= self.match_threshold) {
+            println!("RYM-DATABASE: ✓ Found similar match (score {:.3}): \"{}\"", best.score, best.album_name);
+            return self.load_album(best.item);
         }
 
         println!("RYM-DATABASE: ❌ No match found.");
         Ok(None)
     }
 
-    fn map_row(&self, row: &rusqlite::Row) -> rusqlite::Result<AlbumRating> {
-        Ok(AlbumRating {
-            album_name: row.get(0)?,
-            artist_name: row.get(1)?,
-            rym_rating: row.get(2)?,
-            rating_count: row.get(3)?,
-            rym_url: row.get(4)?,
-            genres: row.get(5)?,
-            secondary_genres: row.get(6)?,
-            descriptors: row.get(7)?,
-            language: row.get(8)?,
-            rank: row.get(9)?,
-            track_ratings: row.get(10)?,
-            reviews: row.get(11)?,
-            release_date: row.get(12)?,
-            timestamp: row.get(13)?,
+    fn load_album(&self, id: i64) -> Result<Option<(i64, AlbumRating)>> {
+        let mut stmt = self.conn.prepare(&format!("SELECT {} FROM album_ratings WHERE id = ?1", ALBUM_COLUMNS))?;
+        match stmt.query_row([id], |row| self.map_row(row)).optional()? {
+            Some((id, mut r)) => {
+                self.attach_children(id, &mut r)?;
+                Ok(Some((id, r)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    // Maps a row selected with ALBUM_COLUMNS. Tracks and reviews are filled in afterwards by
+    // `attach_children`, except for rows saved before their tables existed (legacy JSON columns).
+    fn map_row(&self, row: &rusqlite::Row) -> rusqlite::Result<(i64, AlbumRating)> {
+        let legacy_tracks: Option<String> = row.get(11)?;
+        let legacy_reviews: Option<String> = row.get(12)?;
+        Ok((row.get(0)?, AlbumRating {
+            album_name: row.get(1)?,
+            artist_name: row.get(2)?,
+            rym_rating: row.get(3)?,
+            rating_count: row.get(4)?,
+            rym_url: row.get(5)?,
+            genres: row.get(6)?,
+            secondary_genres: row.get(7)?,
+            descriptors: row.get(8)?,
+            language: row.get(9)?,
+            rank: row.get(10)?,
+            track_ratings: legacy_tracks.map(|json| parse_json_list(&json)).unwrap_or_default(),
+            reviews: legacy_reviews.map(|json| parse_json_list(&json)).unwrap_or_default(),
+            release_date: row.get(13)?,
+            timestamp: row.get(14)?,
             status: None,
-        })
+        }))
+    }
+
+    fn attach_children(&self, album_id: i64, rating: &mut AlbumRating) -> Result<()> {
+        let tracks = self.get_tracks(album_id)?;
+        if !tracks.is_empty() {
+            rating.track_ratings = tracks;
+        }
+        let reviews = self.get_reviews(album_id)?;
+        if !reviews.is_empty() {
+            rating.reviews = reviews;
+        }
+        Ok(())
+    }
+
+    pub fn get_tracks(&self, album_id: i64) -> Result<Vec<TrackRating>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT number, title, duration, rating, count FROM track_ratings
+             WHERE album_id = ?1 ORDER BY position",
+        )?;
+        let rows = stmt.query_map([album_id], |row| {
+            Ok(TrackRating {
+                number: row.get(0)?,
+                title: row.get(1)?,
+                duration: row.get(2)?,
+                rating: row.get(3)?,
+                count: row.get(4)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    pub fn get_reviews(&self, album_id: i64) -> Result<Vec<Review>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT review_id, reviewer, date, rating, body FROM reviews
+             WHERE album_id = ?1 ORDER BY first_seen, id",
+        )?;
+        let rows = stmt.query_map([album_id], |row| {
+            Ok(Review {
+                id: row.get(0)?,
+                reviewer: row.get(1)?,
+                date: row.get(2)?,
+                rating: row.get(3)?,
+                body: row.get(4)?,
+            })
+        })?;
+        rows.collect()
     }
     
     pub fn save_rating(&self, rating: &AlbumRating) -> Result<()> {
         println!("RYM-DATABASE: Saving/Updating \"{}\" by \"{}\"", rating.album_name, rating.artist_name);
         
-        // We use INSERT OR REPLACE, but because the keys might have changed slightly due to cleaning,
-        // we should actually check if a similar entry exists and update it, or just rely on the UNIQUE constraint
-        // if we are consistent with our cleaning.
-        
-        match self.conn.execute(
-            "INSERT OR REPLACE INTO album_ratings 
-             (album_name, artist_name, rym_rating, rating_count, rym_url, genres, 
-              secondary_genres, descriptors, language, rank, track_ratings, reviews,
-              release_date, timestamp)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
-            (
-                &rating.album_name,
-                &rating.artist_name,
-                rating.rym_rating as f64,
-                rating.rating_count as i64,
-                &rating.rym_url,
-                &rating.genres,
-                &rating.secondary_genres,
-                &rating.descriptors,
-                &rating.language,
-                &rating.rank,
-                &rating.track_ratings,
-                &rating.reviews,
-                &rating.release_date,
-                rating.timestamp,
-            ),
-        ) {
+        // Upsert instead of INSERT OR REPLACE so the row keeps its id and the
+        // track_ratings rows referencing it stay attached.
+        let result = (|| {
+            let tx = self.conn.unchecked_transaction()?;
+            tx.execute(
+                "INSERT INTO album_ratings 
+                 (album_name, artist_name, rym_rating, rating_count, rym_url, genres, 
+                  secondary_genres, descriptors, language, rank, track_ratings, reviews,
+                  release_date, timestamp)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, '', '', ?11, ?12)
+                 ON CONFLICT(album_name, artist_name) DO UPDATE SET
+                    rym_rating = excluded.rym_rating,
+                    rating_count = excluded.rating_count,
+                    rym_url = excluded.rym_url,
+                    genres = excluded.genres,
+                    secondary_genres = excluded.secondary_genres,
+                    descriptors = excluded.descriptors,
+                    language = excluded.language,
+                    rank = excluded.rank,
+                    track_ratings = '',
+                    reviews = '',
+                    release_date = excluded.release_date,
+                    timestamp = excluded.timestamp",
+                (
+                    &rating.album_name,
+                    &rating.artist_name,
+                    rating.rym_rating as f64,
+                    rating.rating_count as i64,
+                    &rating.rym_url,
+                    &rating.genres,
+                    &rating.secondary_genres,
+                    &rating.descriptors,
+                    &rating.language,
+                    &rating.rank,
+                    &rating.release_date,
+                    rating.timestamp,
+                ),
+            )?;
+
+            let album_id: i64 = tx.query_row(
+                "SELECT id FROM album_ratings WHERE album_name = ?1 AND artist_name = ?2",
+                [&rating.album_name, &rating.artist_name],
+                |row| row.get(0),
+            )?;
+            index_album_keys(&tx, album_id, &rating.album_name, &rating.artist_name)?;
+
+            tx.execute("DELETE FROM track_ratings WHERE album_id = ?1", [album_id])?;
+            for (position, track) in rating.track_ratings.iter().enumerate() {
+                tx.execute(
+                    "INSERT INTO track_ratings (album_id, position, number, title, duration, rating, count)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    (
+                        album_id,
+                        position as i64,
+                        &track.number,
+                        &track.title,
+                        &track.duration,
+                        track.rating.map(|r| r as f64),
+                        track.count,
+                    ),
+                )?;
+            }
+
+            tx.execute(
+                "INSERT INTO rating_snapshots (album_id, rym_rating, rating_count, timestamp)
+                 VALUES (?1, ?2, ?3, ?4)",
+                (album_id, rating.rym_rating as f64, rating.rating_count as i64, rating.timestamp),
+            )?;
+
+            for review in &rating.reviews {
+                tx.execute(
+                    "INSERT INTO reviews (album_id, review_id, reviewer, date, rating, body, first_seen, last_seen)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)
+                     ON CONFLICT(album_id, review_id) DO UPDATE SET
+                        date = excluded.date,
+                        rating = excluded.rating,
+                        body = excluded.body,
+                        last_seen = excluded.last_seen",
+                    (
+                        album_id,
+                        review.stable_id(),
+                        &review.reviewer,
+                        &review.date,
+                        review.rating.map(|r| r as f64),
+                        &review.body,
+                        rating.timestamp,
+                    ),
+                )?;
+            }
+
+            tx.commit()
+        })();
+
+        match result {
             Ok(_) => {
-                println!("RYM-DATABASE: Successfully saved to cache.");
+                println!(
+                    "RYM-DATABASE: Successfully saved to cache ({} tracks, {} reviews merged).",
+                    rating.track_ratings.len(),
+                    rating.reviews.len()
+                );
                 Ok(())
             },
             Err(e) => {
@@ -191,4 +835,1711 @@ impl Database {
             }
         }
     }
+
+    pub fn save_artist(&self, artist: &Artist) -> Result<()> {
+        println!("RYM-DATABASE: Saving artist \"{}\" ({} releases)", artist.name, artist.releases.len());
+
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO artists (name, name_key, romanized_key, rym_url, formed, born, genres, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(rym_url) DO UPDATE SET
+                name = excluded.name,
+                name_key = excluded.name_key,
+                romanized_key = excluded.romanized_key,
+                formed = excluded.formed,
+                born = excluded.born,
+                genres = excluded.genres,
+                timestamp = excluded.timestamp",
+            (
+                &artist.name,
+                normalize_key(&artist.name),
+                matcher::romanization(&artist.name).map(normalize_key).unwrap_or_default(),
+                &artist.rym_url,
+                &artist.formed,
+                &artist.born,
+                &artist.genres,
+                artist.timestamp,
+            ),
+        )?;
+        let artist_id: i64 = tx.query_row("SELECT id FROM artists WHERE rym_url = ?1", [&artist.rym_url], |row| row.get(0))?;
+
+        tx.execute("DELETE FROM artist_releases WHERE artist_id = ?1", [artist_id])?;
+        for (position, release) in artist.releases.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO artist_releases (artist_id, position, release_type, title, rym_url, year, rating, count)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                (
+                    artist_id,
+                    position as i64,
+                    &release.release_type,
+                    &release.title,
+                    &release.rym_url,
+                    release.year,
+                    release.rating.map(|r| r as f64),
+                    release.count,
+                ),
+            )?;
+        }
+        tx.commit()
+    }
+
+    // Looks an artist up by normalized name (native or romanized), most recently fetched first
+    fn find_artist_id(&self, name: &str) -> Result<Option<i64>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id FROM artists WHERE name_key = ?1 OR romanized_key = ?1 ORDER BY timestamp DESC LIMIT 1",
+        )?;
+        for key in key_variants(name) {
+            if let Some(id) = stmt.query_row([&key], |row| row.get(0)).optional()? {
+                return Ok(Some(id));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn get_artist(&self, name: &str) -> Result<Option<Artist>> {
+        let Some(artist_id) = self.find_artist_id(name)? else {
+            return Ok(None);
+        };
+        let mut artist = self.conn.query_row(
+            "SELECT name, rym_url, formed, born, genres, timestamp FROM artists WHERE id = ?1",
+            [artist_id],
+            |row| {
+                Ok(Artist {
+                    name: row.get(0)?,
+                    rym_url: row.get(1)?,
+                    formed: row.get(2)?,
+                    born: row.get(3)?,
+                    genres: row.get(4)?,
+                    releases: Vec::new(),
+                    timestamp: row.get(5)?,
+                })
+            },
+        )?;
+        artist.releases = self.query_artist_releases(artist_id, None, false, u32::MAX)?;
+        Ok(Some(artist))
+    }
+
+    // Highest-rated releases of an artist, optionally limited to one type ("album", "ep", ...)
+    pub fn get_best_releases(&self, name: &str, release_type: Option<&str>, limit: u32) -> Result<Vec<ArtistRelease>> {
+        match self.find_artist_id(name)? {
+            Some(artist_id) => self.query_artist_releases(artist_id, release_type, true, limit),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    // Page order, or rated releases best-first
+    fn query_artist_releases(&self, artist_id: i64, release_type: Option<&str>, best_first: bool, limit: u32) -> Result<Vec<ArtistRelease>> {
+        let (filter, order) = if best_first {
+            ("AND rating IS NOT NULL", "rating DESC, count DESC")
+        } else {
+            ("", "position")
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT release_type, title, rym_url, year, rating, count FROM artist_releases
+             WHERE artist_id = ?1 AND (?2 IS NULL OR release_type = ?2) {}
+             ORDER BY {} LIMIT ?3",
+            filter, order
+        ))?;
+        let rows = stmt.query_map((artist_id, release_type, limit as i64), |row| {
+            Ok(ArtistRelease {
+                release_type: row.get(0)?,
+                title: row.get(1)?,
+                rym_url: row.get(2)?,
+                year: row.get(3)?,
+                rating: row.get(4)?,
+                count: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    // Adds the page's entries to the snapshot for (chart URL, chart date), replacing any at the same positions
+    pub fn save_chart(&self, chart: &Chart) -> Result<()> {
+        println!(
+            "RYM-DATABASE: Saving chart \"{}\" for {} ({} entries)",
+            chart.title,
+            chart.chart_date,
+            chart.entries.len()
+        );
+
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO chart_snapshots (chart_url, chart_date, title, timestamp)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(chart_url, chart_date) DO UPDATE SET
+                title = excluded.title,
+                timestamp = excluded.timestamp",
+            (&chart.rym_url, &chart.chart_date, &chart.title, chart.timestamp),
+        )?;
+        let snapshot_id: i64 = tx.query_row(
+            "SELECT id FROM chart_snapshots WHERE chart_url = ?1 AND chart_date = ?2",
+            [&chart.rym_url, &chart.chart_date],
+            |row| row.get(0),
+        )?;
+
+        for entry in &chart.entries {
+            tx.execute(
+                "INSERT OR REPLACE INTO chart_entries
+                 (snapshot_id, position, artist_name, release_name, rym_url, year, release_date, genres, secondary_genres, rating, count, reviews)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                rusqlite::params![
+                    snapshot_id,
+                    entry.position,
+                    &entry.artist_name,
+                    &entry.release_name,
+                    &entry.rym_url,
+                    entry.year,
+                    &entry.release_date,
+                    &entry.genres,
+                    &entry.secondary_genres,
+                    entry.rating.map(|r| r as f64),
+                    entry.count,
+                    entry.reviews,
+                ],
+            )?;
+        }
+        tx.commit()
+    }
+
+    // Entries of the latest snapshot of a chart with their position in the one before it, in chart order
+    pub fn get_chart_movers(&self, chart_url: &str) -> Result<Vec<ChartMove>> {
+        let mut stmt = self.conn.prepare(
+            "WITH ranked AS (
+                SELECT id, ROW_NUMBER() OVER (ORDER BY chart_date DESC) AS rn
+                FROM chart_snapshots WHERE chart_url = ?1
+             )
+             SELECT cur.position, cur.artist_name, cur.release_name, cur.rym_url, cur.year, cur.release_date,
+                    cur.genres, cur.secondary_genres, cur.rating, cur.count, cur.reviews, prev.position
+             FROM chart_entries cur
+             JOIN ranked latest ON latest.id = cur.snapshot_id AND latest.rn = 1
+             LEFT JOIN ranked prior ON prior.rn = 2
+             LEFT JOIN chart_entries prev ON prev.snapshot_id = prior.id AND prev.rym_url = cur.rym_url
+             ORDER BY cur.position",
+        )?;
+        let rows = stmt.query_map([chart_url], |row| {
+            let position: u32 = row.get(0)?;
+            let previous_position: Option<u32> = row.get(11)?;
+            Ok(ChartMove {
+                entry: ChartEntry {
+                    position,
+                    artist_name: row.get(1)?,
+                    release_name: row.get(2)?,
+                    rym_url: row.get(3)?,
+                    year: row.get(4)?,
+                    release_date: row.get(5)?,
+                    genres: row.get(6)?,
+                    secondary_genres: row.get(7)?,
+                    rating: row.get(8)?,
+                    count: row.get(9)?,
+                    reviews: row.get(10)?,
+                },
+                previous_position,
+                change: previous_position.map(|prev| prev as i64 - position as i64),
+            })
+        })?;
+        rows.collect()
+    }
+
+    // Imports one page of a list. Entries replace those at the same positions, so
+    // re-importing refreshes a page and importing page 2 extends the collection.
+    pub fn save_collection(&self, collection: &Collection) -> Result<i64> {
+        println!(
+            "RYM-DATABASE: Saving collection \"{}\" by {} ({} entries)",
+            collection.title,
+            collection.author,
+            collection.entries.len()
+        );
+
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO collections (rym_url, title, author, description, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(rym_url) DO UPDATE SET
+                title = excluded.title,
+                author = excluded.author,
+                description = COALESCE(excluded.description, collections.description),
+                timestamp = excluded.timestamp",
+            (&collection.rym_url, &collection.title, &collection.author, &collection.description, collection.timestamp),
+        )?;
+        let collection_id: i64 =
+            tx.query_row("SELECT id FROM collections WHERE rym_url = ?1", [&collection.rym_url], |row| row.get(0))?;
+
+        for entry in &collection.entries {
+            tx.execute(
+                "INSERT OR REPLACE INTO collection_entries
+                 (collection_id, position, entry_type, artist_name, title, rym_url, year, description)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                (
+                    collection_id,
+                    entry.position,
+                    &entry.entry_type,
+                    &entry.artist_name,
+                    &entry.title,
+                    &entry.rym_url,
+                    entry.year,
+                    &entry.description,
+                ),
+            )?;
+        }
+        tx.commit()?;
+        Ok(collection_id)
+    }
+
+    // All imported collections without their entries, most recently imported first
+    pub fn list_collections(&self) -> Result<Vec<Collection>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.id, c.title, c.author, c.rym_url, c.description, c.cursor, c.timestamp,
+                    (SELECT COUNT(*) FROM collection_entries e WHERE e.collection_id = c.id)
+             FROM collections c ORDER BY c.timestamp DESC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Collection {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                author: row.get(2)?,
+                rym_url: row.get(3)?,
+                description: row.get(4)?,
+                entries: Vec::new(),
+                entry_count: row.get(7)?,
+                cursor: row.get(5)?,
+                timestamp: row.get(6)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    pub fn get_collection(&self, id: i64) -> Result<Option<Collection>> {
+        let Some(mut collection) = self.list_collections()?.into_iter().find(|c| c.id == id) else {
+            return Ok(None);
+        };
+        let mut stmt = self.conn.prepare(
+            "SELECT position, entry_type, artist_name, title, rym_url, year, description
+             FROM collection_entries WHERE collection_id = ?1 ORDER BY position",
+        )?;
+        let rows = stmt.query_map([id], map_collection_entry)?;
+        collection.entries = rows.collect::<Result<_>>()?;
+        Ok(Some(collection))
+    }
+
+    // Moves the collection's cursor to the next (or previous) entry and returns it.
+    // At either end the cursor stays put and None is returned.
+    pub fn step_collection(&self, id: i64, forward: bool) -> Result<Option<CollectionEntry>> {
+        let query = if forward {
+            "SELECT position, entry_type, artist_name, title, rym_url, year, description FROM collection_entries
+             WHERE collection_id = ?1 AND position > (SELECT cursor FROM collections WHERE id = ?1)
+             ORDER BY position LIMIT 1"
+        } else {
+            "SELECT position, entry_type, artist_name, title, rym_url, year, description FROM collection_entries
+             WHERE collection_id = ?1 AND position < (SELECT cursor FROM collections WHERE id = ?1)
+             ORDER BY position DESC LIMIT 1"
+        };
+        let entry = self.conn.query_row(query, [id], map_collection_entry).optional()?;
+        if let Some(entry) = &entry {
+            self.conn.execute("UPDATE collections SET cursor = ?1 WHERE id = ?2", (entry.position, id))?;
+        }
+        Ok(entry)
+    }
+
+    pub fn remove_collection(&self, id: i64) -> Result<bool> {
+        let changed = self.conn.execute("DELETE FROM collections WHERE id = ?1", [id])?;
+        Ok(changed > 0)
+    }
+
+    pub fn save_genre(&self, genre: &Genre) -> Result<()> {
+        println!("RYM-DATABASE: Saving genre \"{}\" ({} parents)", genre.name, genre.parents.len());
+        let tx = self.conn.unchecked_transaction()?;
+        write_genre(&tx, genre)?;
+        tx.commit()
+    }
+
+    // The genre index: every top-level and uncategorized genre in one go
+    pub fn save_genres(&self, genres: &[Genre]) -> Result<()> {
+        println!("RYM-DATABASE: Saving {} genres from the genre index", genres.len());
+        let tx = self.conn.unchecked_transaction()?;
+        for genre in genres {
+            write_genre(&tx, genre)?;
+        }
+        tx.commit()
+    }
+
+    pub fn get_genre(&self, name: &str) -> Result<Option<Genre>> {
+        let row = self
+            .conn
+            .query_row(
+                "SELECT id, name, rym_url, top_level, summary, description, akas, release_count, timestamp
+                 FROM genres WHERE name_key = ?1 ORDER BY top_level DESC, timestamp DESC LIMIT 1",
+                [normalize_key(name)],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        Genre {
+                            name: row.get(1)?,
+                            rym_url: row.get(2)?,
+                            parents: Vec::new(),
+                            summary: row.get(4)?,
+                            description: row.get(5)?,
+                            akas: row.get(6)?,
+                            release_count: row.get(7)?,
+                            top_level: row.get(3)?,
+                            top_releases: Vec::new(),
+                            timestamp: row.get(8)?,
+                        },
+                    ))
+                },
+            )
+            .optional()?;
+        let Some((genre_id, mut genre)) = row else {
+            return Ok(None);
+        };
+
+        let mut stmt = self.conn.prepare(
+            "WITH RECURSIVE up(id, depth) AS (
+                SELECT parent_id, 1 FROM genres WHERE id = ?1 AND parent_id IS NOT NULL
+                UNION
+                SELECT g.parent_id, up.depth + 1 FROM genres g JOIN up ON g.id = up.id
+                WHERE g.parent_id IS NOT NULL AND up.depth < ?2
+             )
+             SELECT g.name, g.rym_url FROM up JOIN genres g ON g.id = up.id ORDER BY up.depth DESC",
+        )?;
+        let parents = stmt.query_map((genre_id, MAX_GENRE_DEPTH), |row| {
+            Ok(GenreLink { name: row.get(0)?, rym_url: row.get(1)? })
+        })?;
+        genre.parents = parents.collect::<Result<_>>()?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT position, title, artist_name, rym_url FROM genre_releases WHERE genre_id = ?1 ORDER BY position",
+        )?;
+        let releases = stmt.query_map([genre_id], |row| {
+            Ok(GenreRelease {
+                position: row.get(0)?,
+                title: row.get(1)?,
+                artist_name: row.get(2)?,
+                rym_url: row.get(3)?,
+            })
+        })?;
+        genre.top_releases = releases.collect::<Result<_>>()?;
+        Ok(Some(genre))
+    }
+
+    // Every genre below `name` at any depth, alphabetically
+    pub fn get_subgenres(&self, name: &str) -> Result<Vec<GenreLink>> {
+        let mut stmt = self.conn.prepare(
+            "WITH RECURSIVE sub(id) AS (
+                SELECT id FROM genres WHERE name_key = ?1
+                UNION
+                SELECT g.id FROM genres g JOIN sub ON g.parent_id = sub.id
+             )
+             SELECT name, rym_url FROM genres
+             WHERE id IN sub AND name_key != ?1
+             ORDER BY name COLLATE NOCASE",
+        )?;
+        let rows = stmt.query_map([normalize_key(name)], |row| {
+            Ok(GenreLink { name: row.get(0)?, rym_url: row.get(1)? })
+        })?;
+        rows.collect()
+    }
+
+    // Cached albums with a primary genre of `name` or any of its subgenres, best rated first
+    pub fn get_albums_in_genre(&self, name: &str) -> Result<Vec<GenreAlbum>> {
+        let mut keys: HashSet<String> = self
+            .get_subgenres(name)?
+            .iter()
+            .map(|g| normalize_key(&g.name))
+            .collect();
+        keys.insert(normalize_key(name));
+
+        Ok(self
+            .cached_genre_albums()?
+            .into_iter()
+            .filter(|album| album.genres.split(',').any(|g| keys.contains(&normalize_key(g))))
+            .collect())
+    }
+
+    // Cached albums grouped by the top of the genre tree their primary genres belong to.
+    // An album lands in several groups if its genres span them; genres not in the
+    // tree yet are left out. Largest groups first.
+    pub fn group_albums_by_top_genre(&self) -> Result<Vec<GenreGroup>> {
+        let nodes: HashMap<i64, (GenreLink, Option<i64>)> = {
+            let mut stmt = self.conn.prepare("SELECT id, name, rym_url, parent_id FROM genres")?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get(0)?, (GenreLink { name: row.get(1)?, rym_url: row.get(2)? }, row.get(3)?)))
+            })?;
+            rows.collect::<Result<_>>()?
+        };
+        let root_of = |mut id: i64| {
+            for _ in 0..MAX_GENRE_DEPTH {
+                match nodes.get(&id).and_then(|(_, parent)| *parent) {
+                    Some(parent) if nodes.contains_key(&parent) => id = parent,
+                    _ => break,
+                }
+            }
+            id
+        };
+        let roots_by_key: HashMap<String, i64> = nodes
+            .iter()
+            .map(|(id, (link, _))| (normalize_key(&link.name), root_of(*id)))
+            .collect();
+
+        let mut groups: Vec<(i64, Vec<GenreAlbum>)> = Vec::new();
+        for album in self.cached_genre_albums()? {
+            let mut roots: Vec<i64> = album.genres.split(',').filter_map(|g| roots_by_key.get(&normalize_key(g)).copied()).collect();
+            roots.sort_unstable();
+            roots.dedup();
+            for root in roots {
+                match groups.iter_mut().find(|(id, _)| *id == root) {
+                    Some((_, albums)) => albums.push(album.clone()),
+                    None => groups.push((root, vec![album.clone()])),
+                }
+            }
+        }
+
+        groups.sort_by_key(|(_, albums)| std::cmp::Reverse(albums.len()));
+        Ok(groups
+            .into_iter()
+            .map(|(root, albums)| GenreGroup { genre: nodes[&root].0.clone(), albums })
+            .collect())
+    }
+
+    fn cached_genre_albums(&self) -> Result<Vec<GenreAlbum>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT artist_name, album_name, rym_url, rym_rating, genres FROM album_ratings
+             WHERE genres != '' ORDER BY rym_rating DESC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(GenreAlbum {
+                artist_name: row.get(0)?,
+                album_name: row.get(1)?,
+                rym_url: row.get(2)?,
+                rym_rating: row.get(3)?,
+                genres: row.get(4)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    pub fn save_song(&self, song: &SongRating) -> Result<()> {
+        println!(
+            "RYM-DATABASE: Saving song \"{}\" by {} ({} appearances)",
+            song.title,
+            song.artist_name,
+            song.appearances.len()
+        );
+
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO song_ratings (title, title_key, artist_name, artist_key, rym_url, rating, rating_count,
+                                       genres, secondary_genres, release_date, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+             ON CONFLICT(rym_url) DO UPDATE SET
+                title = excluded.title,
+                title_key = excluded.title_key,
+                artist_name = excluded.artist_name,
+                artist_key = excluded.artist_key,
+                rating = excluded.rating,
+                rating_count = excluded.rating_count,
+                genres = excluded.genres,
+                secondary_genres = excluded.secondary_genres,
+                release_date = excluded.release_date,
+                timestamp = excluded.timestamp",
+            (
+                &song.title,
+                normalize_key(&song.title),
+                &song.artist_name,
+                normalize_key(&song.artist_name),
+                &song.rym_url,
+                song.rating.map(|r| r as f64),
+                song.rating_count,
+                &song.genres,
+                &song.secondary_genres,
+                &song.release_date,
+                song.timestamp,
+            ),
+        )?;
+        let song_id: i64 = tx.query_row("SELECT id FROM song_ratings WHERE rym_url = ?1", [&song.rym_url], |row| row.get(0))?;
+
+        tx.execute("DELETE FROM song_appearances WHERE song_id = ?1", [song_id])?;
+        for (position, appearance) in song.appearances.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO song_appearances (song_id, position, title, rym_url, track_number)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                (song_id, position as i64, &appearance.title, &appearance.rym_url, &appearance.track_number),
+            )?;
+        }
+        tx.commit()
+    }
+
+    // Looks a song up by normalized title and artist (native or romanized), most recently fetched first
+    pub fn get_song_rating(&self, title: &str, artist_name: &str) -> Result<Option<SongRating>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, title, artist_name, rym_url, rating, rating_count, genres, secondary_genres, release_date, timestamp
+             FROM song_ratings WHERE title_key = ?1 AND artist_key = ?2
+             ORDER BY timestamp DESC LIMIT 1",
+        )?;
+        for title_key in key_variants(title) {
+            for artist_key in key_variants(artist_name) {
+                let found = stmt
+                    .query_row((&title_key, &artist_key), |row| {
+                        Ok((
+                            row.get::<_, i64>(0)?,
+                            SongRating {
+                                title: row.get(1)?,
+                                artist_name: row.get(2)?,
+                                rym_url: row.get(3)?,
+                                rating: row.get(4)?,
+                                rating_count: row.get(5)?,
+                                genres: row.get(6)?,
+                                secondary_genres: row.get(7)?,
+                                release_date: row.get(8)?,
+                                appearances: Vec::new(),
+                                timestamp: row.get(9)?,
+                            },
+                        ))
+                    })
+                    .optional()?;
+                if let Some((song_id, mut song)) = found {
+                    song.appearances = self.get_song_appearances(song_id)?;
+                    return Ok(Some(song));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn get_song_appearances(&self, song_id: i64) -> Result<Vec<SongAppearance>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT title, rym_url, track_number FROM song_appearances WHERE song_id = ?1 ORDER BY position",
+        )?;
+        let rows = stmt.query_map([song_id], |row| {
+            Ok(SongAppearance {
+                title: row.get(0)?,
+                rym_url: row.get(1)?,
+                track_number: row.get(2)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    // Replaces the streaming links stored for a release page
+    pub fn save_media_links(&self, release_url: &str, links: &[MediaLink]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM media_links WHERE release_url = ?1", [release_url])?;
+        for (position, link) in links.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO media_links (release_url, position, service, url, is_default) VALUES (?1, ?2, ?3, ?4, ?5)",
+                (release_url, position as i64, &link.service, &link.url, link.default),
+            )?;
+        }
+        tx.commit()
+    }
+
+    pub fn get_media_links(&self, release_url: &str) -> Result<Vec<MediaLink>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT service, url, is_default FROM media_links WHERE release_url = ?1 ORDER BY position")?;
+        let rows = stmt.query_map([release_url], |row| {
+            Ok(MediaLink {
+                service: row.get(0)?,
+                url: row.get(1)?,
+                default: row.get(2)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    // Queues a page once. Asking again for a queued page can only raise its priority;
+    // it keeps its place among pages of that priority.
+    pub fn enqueue_fetch(&self, url: &str, priority: FetchPriority, now: i64) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO fetch_queue (url, priority, enqueued_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(url) DO UPDATE SET priority = MAX(priority, excluded.priority)",
+            (url, priority as i64, now),
+        )?;
+        Ok(())
+    }
+
+    // Takes the most urgent page off the queue, oldest first within a priority
+    pub fn pop_fetch(&self) -> Result<Option<QueuedFetch>> {
+        self.conn
+            .query_row(
+                "DELETE FROM fetch_queue WHERE id = (
+                     SELECT id FROM fetch_queue ORDER BY priority DESC, enqueued_at, id LIMIT 1
+                 )
+                 RETURNING url, priority, enqueued_at",
+                [],
+                map_queued_fetch,
+            )
+            .optional()
+    }
+
+    pub fn clear_fetches(&self, priority: FetchPriority) -> Result<usize> {
+        self.conn.execute("DELETE FROM fetch_queue WHERE priority = ?1", [priority as i64])
+    }
+
+    // The queue in the order it will be fetched
+    pub fn pending_fetches(&self) -> Result<Vec<QueuedFetch>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT url, priority, enqueued_at FROM fetch_queue ORDER BY priority DESC, enqueued_at, id")?;
+        let rows = stmt.query_map([], map_queued_fetch)?;
+        rows.collect()
+    }
+
+    // Every cached album with a release page, oldest row first. Like ttl_policy::score_change,
+    // the score change skips snapshots that repeat the one before them.
+    pub fn cached_albums(&self) -> Result<Vec<CachedAlbum>> {
+        let mut stmt = self.conn.prepare(
+            "WITH changes AS (
+                SELECT id, album_id, rym_rating, timestamp,
+                       rym_rating IS NOT LAG(rym_rating) OVER w OR rating_count IS NOT LAG(rating_count) OVER w AS changed
+                FROM rating_snapshots
+                WINDOW w AS (PARTITION BY album_id ORDER BY timestamp, id)
+             ),
+             ranked AS (
+                SELECT album_id, rym_rating,
+                       ROW_NUMBER() OVER (PARTITION BY album_id ORDER BY timestamp DESC, id DESC) AS rn
+                FROM changes
+                WHERE changed
+             )
+             SELECT a.album_name, a.artist_name, a.rym_url, a.release_date, a.rating_count, a.timestamp,
+                    cur.rym_rating - prev.rym_rating
+             FROM album_ratings a
+             LEFT JOIN ranked cur ON cur.album_id = a.id AND cur.rn = 1
+             LEFT JOIN ranked prev ON prev.album_id = a.id AND prev.rn = 2
+             WHERE a.rym_url != ''
+             ORDER BY a.id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(CachedAlbum {
+                album_name: row.get(0)?,
+                artist_name: row.get(1)?,
+                rym_url: row.get(2)?,
+                release_date: row.get(3)?,
+                rating_count: row.get(4)?,
+                timestamp: row.get(5)?,
+                score_change: row.get::<_, Option<f64>>(6)?.map(|d| d as f32),
+            })
+        })?;
+        rows.collect()
+    }
+
+    pub fn refresh_pages(&self, day: &str) -> Result<u32> {
+        let pages = self
+            .conn
+            .query_row("SELECT pages FROM refresh_budget WHERE day = ?1", [day], |row| row.get(0))
+            .optional()?;
+        Ok(pages.unwrap_or(0))
+    }
+
+    // Counts one more background page against `day`, returning the new total
+    pub fn count_refresh_page(&self, day: &str) -> Result<u32> {
+        self.conn.query_row(
+            "INSERT INTO refresh_budget (day, pages) VALUES (?1, 1)
+             ON CONFLICT(day) DO UPDATE SET pages = pages + 1
+             RETURNING pages",
+            [day],
+            |row| row.get(0),
+        )
+    }
+
+    // Refreshes the feed from a new music page; releases seen before get their latest stats
+    pub fn save_upcoming_releases(&self, releases: &[UpcomingRelease]) -> Result<()> {
+        println!("RYM-DATABASE: Saving {} new releases", releases.len());
+
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO upcoming_releases (rym_url, release_type, title, artist_name, artist_urls, release_date,
+                                                release_ts, release_end_ts, genres, rating, count, wants, timestamp)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+                 ON CONFLICT(rym_url) DO UPDATE SET
+                    release_type = excluded.release_type,
+                    title = excluded.title,
+                    artist_name = excluded.artist_name,
+                    artist_urls = excluded.artist_urls,
+                    release_date = excluded.release_date,
+                    release_ts = excluded.release_ts,
+                    release_end_ts = excluded.release_end_ts,
+                    genres = excluded.genres,
+                    rating = excluded.rating,
+                    count = excluded.count,
+                    wants = excluded.wants,
+                    timestamp = excluded.timestamp",
+            )?;
+            for release in releases {
+                let date = ReleaseDate::parse(&release.release_date);
+                stmt.execute((
+                    &release.rym_url,
+                    &release.release_type,
+                    &release.title,
+                    &release.artist_name,
+                    release.artist_urls.join("\n"),
+                    &release.release_date,
+                    date.as_ref().and_then(|d| d.timestamp()),
+                    date.as_ref().and_then(|d| d.latest_timestamp()),
+                    &release.genres,
+                    release.rating.map(|r| r as f64),
+                    release.count,
+                    release.wants,
+                    release.timestamp,
+                ))?;
+            }
+        }
+        tx.commit()
+    }
+
+    pub fn get_upcoming_release(&self, rym_url: &str) -> Result<Option<UpcomingRelease>> {
+        self.conn
+            .query_row(
+                &format!("SELECT {} FROM upcoming_releases WHERE rym_url = ?1", UPCOMING_COLUMNS),
+                [rym_url],
+                map_upcoming_release,
+            )
+            .optional()
+    }
+
+    // The stored feed, limited to releases sharing a genre with the cache's favourites.
+    // Releases RYM hasn't tagged yet are kept when the artist is already cached.
+    // Upcoming releases come soonest first, otherwise the newest come first.
+    pub fn get_new_releases(&self, filter: &NewReleaseFilter, now: i64) -> Result<Vec<UpcomingRelease>> {
+        let favourites = self.favourite_genres(filter.min_rating, filter.min_albums)?;
+        let order = if filter.upcoming_only { "release_ts ASC" } else { "release_ts DESC" };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM upcoming_releases
+             WHERE ?1 = 0 OR release_end_ts >= ?2
+             ORDER BY release_ts IS NULL, {}, timestamp DESC",
+            UPCOMING_COLUMNS, order
+        ))?;
+        // Anything out today still counts as upcoming
+        let today = now - now.rem_euclid(86400);
+        let rows = stmt.query_map((filter.upcoming_only, today), map_upcoming_release)?;
+
+        let mut releases = Vec::new();
+        for release in rows {
+            let release = release?;
+            let keep = if release.genres.trim().is_empty() {
+                self.is_cached_artist(&release.artist_name)?
+            } else {
+                release.genres.split(',').any(|g| favourites.contains(&normalize_key(g)))
+            };
+            if keep {
+                releases.push(release);
+            }
+        }
+        if let Some(limit) = filter.limit {
+            releases.truncate(limit as usize);
+        }
+        Ok(releases)
+    }
+
+    // Whether any cached album or artist page is credited to this name
+    fn is_cached_artist(&self, artist_name: &str) -> Result<bool> {
+        let mut albums = self.conn.prepare("SELECT 1 FROM album_keys WHERE artist_key = ?1 LIMIT 1")?;
+        let mut artists = self.conn.prepare("SELECT 1 FROM artists WHERE name_key = ?1 OR romanized_key = ?1 LIMIT 1")?;
+        for key in key_variants(artist_name) {
+            if albums.exists([&key])? || artists.exists([&key])? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    // Normalized primary genres whose rated cached albums average at least `min_rating`
+    fn favourite_genres(&self, min_rating: f32, min_albums: u32) -> Result<HashSet<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT genres, rym_rating FROM album_ratings WHERE genres != '' AND rating_count > 0")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, f32>(1)?)))?;
+
+        let mut totals: HashMap<String, (f32, u32)> = HashMap::new();
+        for row in rows {
+            let (genres, rating) = row?;
+            for genre in genres.split(',').map(normalize_key).filter(|g| !g.is_empty()) {
+                let (sum, albums) = totals.entry(genre).or_default();
+                *sum += rating;
+                *albums += 1;
+            }
+        }
+        Ok(totals
+            .into_iter()
+            .filter(|(_, (sum, albums))| *albums >= min_albums.max(1) && sum / *albums as f32 >= min_rating)
+            .map(|(genre, _)| genre)
+            .collect())
+    }
+
+    pub fn get_alias_url(&self, album_name: &str, artist_name: &str) -> Result<Option<String>> {
+        self.conn
+            .query_row(
+                "SELECT rym_url FROM aliases WHERE album_key = ?1 AND artist_key = ?2",
+                [normalize_key(album_name), normalize_key(artist_name)],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    pub fn set_alias(&self, album_name: &str, artist_name: &str, rym_url: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO aliases (artist_name, album_name, artist_key, album_key, rym_url, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(album_key, artist_key) DO UPDATE SET
+                artist_name = excluded.artist_name,
+                album_name = excluded.album_name,
+                rym_url = excluded.rym_url,
+                created_at = excluded.created_at",
+            (
+                artist_name,
+                album_name,
+                normalize_key(artist_name),
+                normalize_key(album_name),
+                rym_url,
+                chrono::Utc::now().timestamp(),
+            ),
+        )?;
+        Ok(())
+    }
+
+    pub fn list_aliases(&self) -> Result<Vec<Alias>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, artist_name, album_name, rym_url, created_at FROM aliases
+             ORDER BY artist_name COLLATE NOCASE, album_name COLLATE NOCASE",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Alias {
+                id: row.get(0)?,
+                artist_name: row.get(1)?,
+                album_name: row.get(2)?,
+                rym_url: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    // Returns false when no alias has that id
+    pub fn edit_alias(&self, id: i64, rym_url: &str) -> Result<bool> {
+        let changed = self.conn.execute("UPDATE aliases SET rym_url = ?1 WHERE id = ?2", (rym_url, id))?;
+        Ok(changed > 0)
+    }
+
+    pub fn remove_alias(&self, id: i64) -> Result<bool> {
+        let changed = self.conn.execute("DELETE FROM aliases WHERE id = ?1", [id])?;
+        Ok(changed > 0)
+    }
+
+    pub fn get_rating_history(&self, album_name: &str, artist_name: &str) -> Result<Vec<RatingSnapshot>> {
+        let Some((album_id, _)) = self.find_album(album_name, artist_name)? else {
+            return Ok(Vec::new());
+        };
+        self.snapshots(album_id)
+    }
+
+    // History of a release already resolved to its page, without matching names again
+    pub fn get_rating_history_by_url(&self, rym_url: &str) -> Result<Vec<RatingSnapshot>> {
+        let album_id: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT id FROM album_ratings WHERE rym_url = ?1 AND rym_url != '' ORDER BY timestamp DESC LIMIT 1",
+                [rym_url],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match album_id {
+            Some(album_id) => self.snapshots(album_id),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn snapshots(&self, album_id: i64) -> Result<Vec<RatingSnapshot>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT rym_rating, rating_count, timestamp FROM rating_snapshots
+             WHERE album_id = ?1 ORDER BY timestamp, id",
+        )?;
+        let rows = stmt.query_map([album_id], |row| {
+            Ok(RatingSnapshot {
+                rym_rating: row.get(0)?,
+                rating_count: row.get(1)?,
+                timestamp: row.get(2)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    // Albums whose last two snapshots differ by at least `min_delta`, biggest moves first
+    pub fn get_rating_movers(&self, min_delta: f32) -> Result<Vec<RatingMove>> {
+        let mut stmt = self.conn.prepare(
+            "WITH ranked AS (
+                SELECT album_id, rym_rating, rating_count, timestamp,
+                       ROW_NUMBER() OVER (PARTITION BY album_id ORDER BY timestamp DESC, id DESC) AS rn
+                FROM rating_snapshots
+             )
+             SELECT a.artist_name, a.album_name, a.rym_url,
+                    prev.rym_rating, prev.rating_count, prev.timestamp,
+                    cur.rym_rating, cur.rating_count, cur.timestamp
+             FROM ranked cur
+             JOIN ranked prev ON prev.album_id = cur.album_id AND prev.rn = 2
+             JOIN album_ratings a ON a.id = cur.album_id
+             WHERE cur.rn = 1 AND ABS(cur.rym_rating - prev.rym_rating) >= ?1
+             ORDER BY ABS(cur.rym_rating - prev.rym_rating) DESC",
+        )?;
+        let rows = stmt.query_map([min_delta as f64], |row| {
+            Ok(RatingMove {
+                artist_name: row.get(0)?,
+                album_name: row.get(1)?,
+                rym_url: row.get(2)?,
+                previous: RatingSnapshot {
+                    rym_rating: row.get(3)?,
+                    rating_count: row.get(4)?,
+                    timestamp: row.get(5)?,
+                },
+                current: RatingSnapshot {
+                    rym_rating: row.get(6)?,
+                    rating_count: row.get(7)?,
+                    timestamp: row.get(8)?,
+                },
+            })
+        })?;
+        rows.collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    const ROWS: usize = 50_000;
+
+    // Bulk-load rows the way an old cache looks: no normalized keys yet
+    fn seeded_db() -> Database {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let tx = db.conn.unchecked_transaction().unwrap();
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO album_ratings (album_name, artist_name, rym_rating, rating_count, rym_url, timestamp)
+                     VALUES (?1, ?2, 3.5, 100, '', 0)",
+                )
+                .unwrap();
+            for i in 0..ROWS {
+                stmt.execute((format!("Album {} (Deluxe Edition)", i), format!("The Artist {}", i))).unwrap();
+            }
+        }
+        migrations::index_missing_keys(&tx).unwrap();
+        tx.commit().unwrap();
+        db
+    }
+
+    #[test]
+    fn fuzzy_lookup_uses_key_index() {
+        let db = seeded_db();
+        let plan: String = db
+            .conn
+            .query_row(&format!("EXPLAIN QUERY PLAN {}", KEY_LOOKUP), ["a", "b"], |row| row.get(3))
+            .unwrap();
+        assert!(plan.contains("idx_album_keys_lookup"), "unexpected plan: {}", plan);
+    }
+
+    #[test]
+    fn similarity_candidates_come_from_key_indexes() {
+        let db = seeded_db();
+        let mut stmt = db.conn.prepare(&format!("EXPLAIN QUERY PLAN {}", FUZZY_CANDIDATES)).unwrap();
+        let plan = stmt
+            .query_map(["a", "b*"], |row| row.get::<_, String>(3))
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap()
+            .join(" | ");
+        assert!(plan.contains("idx_album_keys_artist") && plan.contains("idx_album_keys_lookup"), "unexpected plan: {}", plan);
+        assert!(!plan.contains("SCAN"), "unexpected plan: {}", plan);
+    }
+
+    #[test]
+    fn empty_album_key_matches_on_artist_only() {
+        assert_eq!(candidate_pattern("abbeyroad"), Some("abbe*".to_string()));
+        assert_eq!(candidate_pattern("星間性交"), Some("星間性交*".to_string()));
+        assert_eq!(candidate_pattern(""), None);
+
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.save_rating(&rating("Abbey Road", "The Beatles", "abbey", 4.3)).unwrap();
+        db.save_rating(&rating("Low", "David Bowie", "low", 4.1)).unwrap();
+        let mut stmt = db.conn.prepare(FUZZY_CANDIDATES).unwrap();
+        let albums = stmt
+            .query_map((normalize_key("David Bowie"), candidate_pattern("")), |row| row.get::<_, String>(1))
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(albums, ["Low"]);
+    }
+
+    #[test]
+    fn similarity_match_ranks_only_related_releases() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.save_rating(&rating("Abbey Road", "The Beatles", "abbey", 4.3)).unwrap();
+        db.save_rating(&rating("The Dark Side of the Moon", "Pink Floyd", "dsotm", 4.2)).unwrap();
+
+        // Same artist key, album key differs from the first character
+        let found = db.get_rating("Dark Side of the Moon", "Pink Floyd").unwrap().unwrap();
+        assert_eq!(found.rym_url, "dsotm");
+        // Same album key prefix, artist key differs
+        let found = db.get_rating("Abbey Road - Super Deluxe Edition", "Beatles").unwrap().unwrap();
+        assert_eq!(found.rym_url, "abbey");
+        // Neither: not ranked at all, however close the names
+        assert!(db.get_rating("Dark Side of the Moon", "Floyd").unwrap().is_none());
+    }
+
+    #[test]
+    fn similarity_match_handles_non_latin_edition_suffixes() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.save_rating(&rating("宇多田 ベスト", "宇多田ヒカル", "utada-best", 3.4)).unwrap();
+
+        let found = db.get_rating("宇多田 ベスト Deluxe", "宇多田ヒカル").unwrap().unwrap();
+        assert_eq!(found.rym_url, "utada-best");
+        // Candidates by the same artist are still scored without panicking
+        assert!(db.get_rating("Best 星間 Deluxe", "宇多田ヒカル").unwrap().is_none());
+    }
+
+    // Wall-clock timing, so it is left out of normal runs; `fuzzy_lookup_uses_key_index`
+    // checks the plan. Run with `cargo test --release -- --ignored`.
+    #[test]
+    #[ignore]
+    fn fuzzy_lookup_benchmark_50k_rows() {
+        let db = seeded_db();
+
+        let start = Instant::now();
+        for i in (0..ROWS).step_by(50) {
+            let found = db.get_rating(&format!("album {}!", i), &format!("the artist {}", i)).unwrap();
+            assert_eq!(found.unwrap().album_name, format!("Album {} (Deluxe Edition)", i));
+        }
+        let per_lookup = start.elapsed() / (ROWS / 50) as u32;
+        println!("fuzzy lookup over {} rows: {:?} per lookup", ROWS, per_lookup);
+        // A full-table scan takes tens of milliseconds at this size
+        assert!(per_lookup.as_millis() < 5, "fuzzy lookup too slow: {:?}", per_lookup);
+
+        assert!(db.get_rating("Missing Album", "Nobody").unwrap().is_none());
+    }
+
+    #[test]
+    fn save_computes_keys() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let rating: AlbumRating = serde_json::from_value(serde_json::json!({
+            "album_name": "OK Computer [OKNOTOK]",
+            "artist_name": "Radiohead",
+            "rym_rating": 4.2,
+            "rating_count": 1,
+            "rym_url": "",
+            "genres": "",
+            "release_date": "",
+            "timestamp": 0,
+        }))
+        .unwrap();
+        db.save_rating(&rating).unwrap();
+
+        let found = db.get_rating("OK Computer", "radiohead").unwrap().unwrap();
+        assert_eq!(found.album_name, "OK Computer [OKNOTOK]");
+    }
+
+    fn rating(album: &str, artist: &str, url: &str, score: f32) -> AlbumRating {
+        serde_json::from_value(serde_json::json!({
+            "album_name": album,
+            "artist_name": artist,
+            "rym_rating": score,
+            "rating_count": 1,
+            "rym_url": url,
+            "genres": "",
+            "release_date": "",
+            "timestamp": 0,
+        }))
+        .unwrap()
+    }
+
+    fn track(number: &str, title: &str, rating: Option<f32>) -> TrackRating {
+        TrackRating {
+            number: number.to_string(),
+            title: title.to_string(),
+            duration: "4:00".to_string(),
+            rating,
+            count: rating.map(|_| 120),
+        }
+    }
+
+    #[test]
+    fn tracks_round_trip_and_are_replaced_on_resave() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let mut kid_a = rating("Kid A", "Radiohead", "https://rateyourmusic.com/release/album/radiohead/kid-a/", 4.2);
+        kid_a.track_ratings = vec![
+            track("1", "Everything in Its Right Place", Some(4.3)),
+            track("2", "Kid A", Some(3.9)),
+            track("", "Untitled", None),
+        ];
+        db.save_rating(&kid_a).unwrap();
+        assert_eq!(db.get_rating("Kid A", "Radiohead").unwrap().unwrap().track_ratings, kid_a.track_ratings);
+
+        // A re-save replaces the list rather than appending to it
+        kid_a.track_ratings = vec![track("1", "Everything in Its Right Place", Some(4.35))];
+        db.save_rating(&kid_a).unwrap();
+        assert_eq!(db.get_rating("Kid A", "Radiohead").unwrap().unwrap().track_ratings, kid_a.track_ratings);
+    }
+
+    fn review(id: &str, reviewer: &str, body: &str) -> Review {
+        Review { id: id.to_string(), reviewer: reviewer.to_string(), date: String::new(), rating: Some(4.0), body: body.to_string() }
+    }
+
+    #[test]
+    fn reviews_are_merged_across_saves() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let mut kid_a = rating("Kid A", "Radiohead", "https://rateyourmusic.com/release/album/radiohead/kid-a/", 4.2);
+        kid_a.reviews = vec![review("1", "alice", "Cold"), review("2", "bob", "Great")];
+        kid_a.timestamp = 100;
+        db.save_rating(&kid_a).unwrap();
+
+        // alice dropped off the first page, bob edited his review and carol is new
+        kid_a.reviews = vec![review("2", "bob", "Great, still"), review("", "carol", "Fine")];
+        kid_a.timestamp = 200;
+        db.save_rating(&kid_a).unwrap();
+        // Saving the same page again adds nothing
+        db.save_rating(&kid_a).unwrap();
+
+        let reviews: Vec<_> = db
+            .get_rating("Kid A", "Radiohead")
+            .unwrap()
+            .unwrap()
+            .reviews
+            .into_iter()
+            .map(|r| (r.id, r.reviewer, r.body))
+            .collect();
+        assert_eq!(reviews, vec![
+            ("1".to_string(), "alice".to_string(), "Cold".to_string()),
+            ("2".to_string(), "bob".to_string(), "Great, still".to_string()),
+            ("user:carol".to_string(), "carol".to_string(), "Fine".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn every_save_records_a_snapshot() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let url = "https://rateyourmusic.com/release/album/radiohead/kid-a/";
+        for (timestamp, score) in [(100, 4.0), (200, 4.1), (300, 4.05)] {
+            let mut kid_a = rating("Kid A", "Radiohead", url, score);
+            kid_a.timestamp = timestamp;
+            db.save_rating(&kid_a).unwrap();
+        }
+
+        let history: Vec<_> = db.get_rating_history("Kid A", "Radiohead").unwrap().iter().map(|s| (s.timestamp, s.rym_rating)).collect();
+        assert_eq!(history, vec![(100, 4.0), (200, 4.1), (300, 4.05)]);
+        assert!(db.get_rating_history("Amnesiac", "Radiohead").unwrap().is_empty());
+    }
+
+    #[test]
+    fn repeated_identical_saves_keep_the_last_score_change() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let url = "https://rateyourmusic.com/release/album/radiohead/kid-a/";
+        for (timestamp, score) in [(100, 3.5), (200, 3.75), (300, 3.75), (400, 3.75)] {
+            let mut kid_a = rating("Kid A", "Radiohead", url, score);
+            kid_a.timestamp = timestamp;
+            db.save_rating(&kid_a).unwrap();
+        }
+
+        let history = db.get_rating_history_by_url(url).unwrap();
+        assert_eq!(history.len(), 4);
+        assert_eq!(crate::ttl_policy::score_change(&history), Some(0.25));
+    }
+
+    #[test]
+    fn history_by_url_skips_name_matching() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let url = "https://rateyourmusic.com/release/album/radiohead/kid-a/";
+        for (timestamp, score) in [(100, 4.0), (200, 4.1)] {
+            let mut kid_a = rating("Kid A", "Radiohead", url, score);
+            kid_a.timestamp = timestamp;
+            db.save_rating(&kid_a).unwrap();
+        }
+        db.save_rating(&rating("Amnesiac", "Radiohead", "", 3.9)).unwrap();
+
+        let history: Vec<_> = db.get_rating_history_by_url(url).unwrap().iter().map(|s| s.rym_rating).collect();
+        assert_eq!(history, vec![4.0, 4.1]);
+        assert!(db.get_rating_history_by_url("").unwrap().is_empty());
+        assert!(db.get_rating_history_by_url("https://rateyourmusic.com/release/album/radiohead/amnesiac/").unwrap().is_empty());
+    }
+
+    #[test]
+    fn movers_compare_the_last_two_snapshots() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let url = |name: &str| format!("https://rateyourmusic.com/release/album/{}/", name);
+        // (album, scores in save order)
+        let albums: &[(&str, &[f32])] = &[
+            ("big-drop", &[3.0, 3.5, 3.25]),
+            ("small-move", &[3.0, 3.05]),
+            ("big-rise", &[2.0, 3.0, 3.5]),
+            ("single-save", &[4.0]),
+        ];
+        for &(album, scores) in albums {
+            for (i, &score) in scores.iter().enumerate() {
+                let mut r = rating(album, "Artist", &url(album), score);
+                r.timestamp = i as i64;
+                db.save_rating(&r).unwrap();
+            }
+        }
+
+        let movers = |min_delta| -> Vec<(String, f32)> {
+            db.get_rating_movers(min_delta)
+                .unwrap()
+                .into_iter()
+                .map(|m| (m.album_name, m.current.rym_rating - m.previous.rym_rating))
+                .collect()
+        };
+        assert_eq!(movers(0.2), vec![("big-rise".to_string(), 0.5), ("big-drop".to_string(), -0.25)]);
+        assert_eq!(movers(0.3), vec![("big-rise".to_string(), 0.5)]);
+        assert_eq!(movers(0.01).len(), 3);
+    }
+
+    #[test]
+    fn alias_resolves_to_canonical_row() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let url = "https://rateyourmusic.com/release/album/sigur-ros/agaetis-byrjun/";
+        db.save_rating(&rating("Ágætis byrjun", "Sigur Rós", url, 4.0)).unwrap();
+        db.set_alias("Agaetis Byrjun (Deluxe)", "Sigur Ros", url).unwrap();
+
+        // A refresh of the canonical row is visible through the alias, without a copy being stored
+        db.save_rating(&rating("Ágætis byrjun", "Sigur Rós", url, 4.1)).unwrap();
+        let found = db.get_rating("Agaetis Byrjun (Deluxe)", "Sigur Ros").unwrap().unwrap();
+        assert_eq!(found.rym_rating, 4.1);
+        assert_eq!(found.album_name, "Agaetis Byrjun (Deluxe)");
+        let rows: i64 = db.conn.query_row("SELECT COUNT(*) FROM album_ratings", [], |row| row.get(0)).unwrap();
+        assert_eq!(rows, 1);
+    }
+
+    #[test]
+    fn aliases_can_be_listed_edited_and_removed() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.save_rating(&rating("Let It Be", "The Beatles", "lib", 3.8)).unwrap();
+        db.save_rating(&rating("Let It Be... Naked", "The Beatles", "naked", 3.5)).unwrap();
+        db.set_alias("Let It Be (Remastered)", "The Beatles", "lib").unwrap();
+        // Re-linking the same Apple Music album replaces the alias
+        db.set_alias("Let It Be (Remastered)", "THE BEATLES", "lib").unwrap();
+
+        let aliases = db.list_aliases().unwrap();
+        assert_eq!(aliases.len(), 1);
+        assert_eq!(aliases[0].artist_name, "THE BEATLES");
+
+        assert!(db.edit_alias(aliases[0].id, "naked").unwrap());
+        let found = db.get_rating("Let It Be (Remastered)", "The Beatles").unwrap().unwrap();
+        assert_eq!(found.rym_rating, 3.5);
+
+        assert!(db.remove_alias(aliases[0].id).unwrap());
+        assert!(!db.remove_alias(aliases[0].id).unwrap());
+        assert!(db.list_aliases().unwrap().is_empty());
+    }
+
+    #[test]
+    fn normalizes_non_latin_names() {
+        assert_eq!(normalize_key("Björk"), "bjork");
+        assert_eq!(normalize_key("ＢＡＢＹＭＥＴＡＬ"), "babymetal");
+        assert_eq!(normalize_key("星間性交"), "星間性交");
+        assert_eq!(normalize_key("t e l e p a t h テレパシー能力者"), "telepathテレパシー能力者");
+        // Voicing marks are part of the kana, not accents
+        assert_eq!(normalize_key("ゆらゆら帝国"), "ゆらゆら帝国");
+        assert_eq!(normalize_key("ガールズ"), "ガールズ");
+        assert_eq!(normalize_key("Порез на Собаке"), "порезнасобаке");
+
+        assert_eq!(key_variants("سماع [Sama'a (Audition)]"), ["سماع", "samaa"]);
+        assert_eq!(key_variants("أحمد [Ahmed]"), ["أحمد", "ahmed"]);
+        assert_eq!(key_variants("아이유 [IU]"), ["아이유", "iu"]);
+        assert_eq!(key_variants("空洞です [Kūdō desu]"), ["空洞です", "kudodesu"]);
+        // Brackets after a Latin title are edition info, not a romanization
+        assert_eq!(key_variants("OK Computer [OKNOTOK]"), ["okcomputer"]);
+    }
+
+    #[test]
+    fn finds_releases_by_native_or_romanized_name() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let rating: AlbumRating = serde_json::from_value(serde_json::json!({
+            "album_name": "سماع [Sama'a (Audition)]",
+            "artist_name": "أحمد [Ahmed]",
+            "rym_rating": 3.6,
+            "rating_count": 40,
+            "rym_url": "https://rateyourmusic.com/release/album/أحمد/سماع/",
+            "genres": "",
+            "release_date": "",
+            "timestamp": 0,
+        }))
+        .unwrap();
+        db.save_rating(&rating).unwrap();
+
+        for (album, artist) in [("سماع", "أحمد"), ("Sama'a", "Ahmed"), ("SAMA'A", "أحمد"), ("سماع", "Ahmed")] {
+            let found = db.get_rating(album, artist).unwrap();
+            assert_eq!(found.map(|r| r.rating_count), Some(40), "{} by {}", album, artist);
+        }
+    }
+
+    #[test]
+    fn stores_artist_discography() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let release = |release_type: &str, title: &str, rating: Option<f32>, count: Option<i32>| ArtistRelease {
+            release_type: release_type.to_string(),
+            title: title.to_string(),
+            rym_url: format!("https://rateyourmusic.com/release/{}/deafheaven/{}/", release_type, title),
+            year: Some(2013),
+            rating,
+            count,
+        };
+        let mut artist = Artist {
+            name: "Deafheaven".to_string(),
+            rym_url: "https://rateyourmusic.com/artist/deafheaven/".to_string(),
+            formed: Some("February 2010, San Francisco, CA, United States".to_string()),
+            born: None,
+            genres: "Blackgaze, Post-Metal".to_string(),
+            releases: vec![
+                release("album", "Roads to Judah", Some(3.55), Some(9000)),
+                release("album", "Sunbather", Some(3.72), Some(24811)),
+                release("album", "Lonely People With Power", None, None),
+                release("ep", "From the Kettle Onto the Coil", Some(3.81), Some(1204)),
+            ],
+            timestamp: 0,
+        };
+        db.save_artist(&artist).unwrap();
+
+        let stored = db.get_artist("deafheaven").unwrap().unwrap();
+        assert_eq!(stored.formed, artist.formed);
+        assert_eq!(stored.releases, artist.releases);
+
+        let best = db.get_best_releases("Deafheaven", Some("album"), 5).unwrap();
+        assert_eq!(best.iter().map(|r| r.title.as_str()).collect::<Vec<_>>(), ["Sunbather", "Roads to Judah"]);
+        assert_eq!(db.get_best_releases("Deafheaven", None, 1).unwrap()[0].release_type, "ep");
+
+        // Re-saving replaces the discography instead of appending to it
+        artist.releases.truncate(1);
+        db.save_artist(&artist).unwrap();
+        assert_eq!(db.get_artist("Deafheaven").unwrap().unwrap().releases.len(), 1);
+        assert!(db.get_artist("Alcest").unwrap().is_none());
+    }
+
+    #[test]
+    fn diffs_chart_snapshots_week_over_week() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let entry = |position: u32, title: &str| ChartEntry {
+            position,
+            artist_name: "Various".to_string(),
+            release_name: title.to_string(),
+            rym_url: format!("https://rateyourmusic.com/release/album/various/{}/", title),
+            year: Some(2025),
+            release_date: "2025".to_string(),
+            genres: "Blackgaze".to_string(),
+            secondary_genres: None,
+            rating: Some(3.8),
+            count: Some(1000),
+            reviews: Some(10),
+        };
+        let chart = |date: &str, entries: Vec<ChartEntry>| Chart {
+            title: "Top albums of 2025".to_string(),
+            rym_url: "https://rateyourmusic.com/charts/top/album/2025/".to_string(),
+            chart_date: date.to_string(),
+            entries,
+            timestamp: 0,
+        };
+
+        db.save_chart(&chart("2025-12-09", vec![entry(1, "a"), entry(2, "b"), entry(3, "c")])).unwrap();
+        // Page 1 and page 2 of the next week's chart land in one snapshot
+        db.save_chart(&chart("2025-12-16", vec![entry(1, "c"), entry(2, "a")])).unwrap();
+        db.save_chart(&chart("2025-12-16", vec![entry(41, "d")])).unwrap();
+
+        let movers = db.get_chart_movers("https://rateyourmusic.com/charts/top/album/2025/").unwrap();
+        let summary: Vec<_> = movers.iter().map(|m| (m.entry.release_name.as_str(), m.previous_position, m.change)).collect();
+        assert_eq!(summary, [("c", Some(3), Some(2)), ("a", Some(1), Some(-1)), ("d", None, None)]);
+
+        assert!(db.get_chart_movers("https://rateyourmusic.com/charts/top/album/2024/").unwrap().is_empty());
+    }
+
+    #[test]
+    fn imports_list_pages_and_steps_through_them() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let entry = |position: u32, title: &str| CollectionEntry {
+            position,
+            entry_type: "album".to_string(),
+            artist_name: "Radiohead".to_string(),
+            title: title.to_string(),
+            rym_url: format!("https://rateyourmusic.com/release/album/radiohead/{}/", title),
+            year: None,
+            description: None,
+        };
+        let page = |entries: Vec<CollectionEntry>| Collection {
+            id: 0,
+            title: "Favourites".to_string(),
+            author: "someone".to_string(),
+            rym_url: "https://rateyourmusic.com/list/someone/favourites/".to_string(),
+            description: Some("Albums I keep coming back to".to_string()),
+            entry_count: entries.len() as u32,
+            entries,
+            cursor: 0,
+            timestamp: 0,
+        };
+
+        let id = db.save_collection(&page(vec![entry(1, "ok-computer"), entry(2, "kid-a")])).unwrap();
+        assert_eq!(db.save_collection(&page(vec![entry(26, "amnesiac")])).unwrap(), id);
+
+        let listed = db.list_collections().unwrap();
+        assert_eq!((listed.len(), listed[0].entry_count), (1, 3));
+
+        let step = |forward| db.step_collection(id, forward).unwrap().map(|e| e.title);
+        assert_eq!(step(false), None);
+        assert_eq!(step(true).as_deref(), Some("ok-computer"));
+        assert_eq!(step(true).as_deref(), Some("kid-a"));
+        assert_eq!(step(true).as_deref(), Some("amnesiac"));
+        assert_eq!(step(true), None);
+        assert_eq!(step(false).as_deref(), Some("kid-a"));
+
+        let stored = db.get_collection(id).unwrap().unwrap();
+        assert_eq!(stored.cursor, 2);
+        assert_eq!(stored.entries.len(), 3);
+
+        assert!(db.remove_collection(id).unwrap());
+        assert!(db.get_collection(id).unwrap().is_none());
+    }
+
+    #[test]
+    fn builds_genre_tree_from_pages() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let link = |name: &str| GenreLink {
+            name: name.to_string(),
+            rym_url: format!("https://rateyourmusic.com/genre/{}/", normalize_key(name)),
+        };
+        let genre = |name: &str, parents: &[&str], top_level: bool| Genre {
+            name: name.to_string(),
+            rym_url: link(name).rym_url,
+            parents: parents.iter().map(|p| link(p)).collect(),
+            summary: Some(format!("About {}", name)),
+            description: None,
+            akas: String::new(),
+            release_count: None,
+            top_level,
+            top_releases: Vec::new(),
+            timestamp: 0,
+        };
+
+        db.save_genres(&[genre("Jazz", &[], true), genre("Electronic", &[], true)]).unwrap();
+        db.save_genre(&genre("Jazz Fusion", &["Jazz"], false)).unwrap();
+        db.save_genre(&genre("Vaporwave", &["Electronic"], false)).unwrap();
+        // Breadcrumbs may only name the direct parent; Vaporwave keeps its own
+        db.save_genre(&genre("Slushwave", &["Vaporwave"], false)).unwrap();
+        db.save_genre(&genre("Spiritual Jazz", &["Jazz"], false)).unwrap();
+
+        let slushwave = db.get_genre("slushwave").unwrap().unwrap();
+        assert_eq!(slushwave.parents, vec![link("Electronic"), link("Vaporwave")]);
+        // Re-importing the index doesn't drop what genre pages added
+        db.save_genres(&[genre("Electronic", &[], true)]).unwrap();
+        assert_eq!(db.get_genre("Vaporwave").unwrap().unwrap().parents, vec![link("Electronic")]);
+
+        assert_eq!(db.get_subgenres("Jazz").unwrap(), vec![link("Jazz Fusion"), link("Spiritual Jazz")]);
+        assert_eq!(db.get_subgenres("Electronic").unwrap(), vec![link("Slushwave"), link("Vaporwave")]);
+
+        for (album, genres, score) in [
+            ("Bitches Brew", "Jazz Fusion", 3.9),
+            ("Karma", "Spiritual Jazz", 4.0),
+            ("Floral Shoppe", "Vaporwave, Jazz Fusion", 3.5),
+            ("Kid A", "Art Rock", 4.2),
+        ] {
+            let mut r = rating(album, "Various", &format!("https://rateyourmusic.com/release/album/{}/", album), score);
+            r.genres = genres.to_string();
+            db.save_rating(&r).unwrap();
+        }
+
+        let jazz: Vec<_> = db.get_albums_in_genre("Jazz").unwrap().into_iter().map(|a| a.album_name).collect();
+        assert_eq!(jazz, ["Karma", "Bitches Brew", "Floral Shoppe"]);
+
+        let groups: Vec<_> = db
+            .group_albums_by_top_genre()
+            .unwrap()
+            .into_iter()
+            .map(|g| (g.genre.name, g.albums.len()))
+            .collect();
+        assert_eq!(groups, [("Jazz".to_string(), 3), ("Electronic".to_string(), 1)]);
+    }
+
+    #[test]
+    fn stores_song_ratings_with_appearances() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let mut song = SongRating {
+            title: "Windowlicker".to_string(),
+            artist_name: "Aphex Twin".to_string(),
+            rym_url: "https://rateyourmusic.com/song/aphex-twin/windowlicker/".to_string(),
+            rating: Some(3.9),
+            rating_count: 4000,
+            genres: "IDM".to_string(),
+            secondary_genres: None,
+            release_date: "22 March 1999".to_string(),
+            appearances: vec![
+                SongAppearance {
+                    title: "Windowlicker".to_string(),
+                    rym_url: "https://rateyourmusic.com/release/single/aphex-twin/windowlicker/".to_string(),
+                    track_number: Some("1".to_string()),
+                },
+                SongAppearance {
+                    title: "Classics".to_string(),
+                    rym_url: "https://rateyourmusic.com/release/comp/aphex-twin/classics/".to_string(),
+                    track_number: None,
+                },
+            ],
+            timestamp: 0,
+        };
+        db.save_song(&song).unwrap();
+
+        // Apple Music track titles carry extra bits in brackets
+        let stored = db.get_song_rating("Windowlicker (Remastered)", "aphex twin").unwrap().unwrap();
+        assert_eq!(stored.rating, Some(3.9));
+        assert_eq!(stored.appearances, song.appearances);
+
+        // Re-fetching the page replaces the rating and the appearances
+        song.rating = Some(3.95);
+        song.appearances.truncate(1);
+        db.save_song(&song).unwrap();
+        let stored = db.get_song_rating("Windowlicker", "Aphex Twin").unwrap().unwrap();
+        assert_eq!(stored.rating, Some(3.95));
+        assert_eq!(stored.appearances.len(), 1);
+
+        assert!(db.get_song_rating("Windowlicker", "Autechre").unwrap().is_none());
+    }
+
+    #[test]
+    fn stores_media_links_per_release() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let url = "https://rateyourmusic.com/release/ep/blut-aus-nord/what-once-was-liber-iii/";
+        let link = |service: &str, url: &str, default: bool| MediaLink {
+            service: service.to_string(),
+            url: url.to_string(),
+            default,
+        };
+        let links = vec![
+            link("applemusic", "https://geo.music.apple.com/ru/album/what-once-was-liber-iii-ep/728629872", true),
+            link("bandcamp", "https://blutausnord.bandcamp.com/album/what-once-was-liber-iii", false),
+        ];
+
+        db.save_media_links(url, &links).unwrap();
+        assert_eq!(db.get_media_links(url).unwrap(), links);
+
+        db.save_media_links(url, &links[1..]).unwrap();
+        assert_eq!(db.get_media_links(url).unwrap(), &links[1..]);
+        assert!(db.get_media_links("https://rateyourmusic.com/release/album/other/").unwrap().is_empty());
+    }
+
+    #[test]
+    fn fetch_queue_orders_by_priority_and_dedupes() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let url = |name: &str| format!("https://rateyourmusic.com/release/album/{}/", name);
+
+        db.enqueue_fetch(&url("a"), FetchPriority::Prefetch, 1).unwrap();
+        db.enqueue_fetch(&url("b"), FetchPriority::Refresh, 2).unwrap();
+        db.enqueue_fetch(&url("c"), FetchPriority::Prefetch, 3).unwrap();
+        db.enqueue_fetch(&url("d"), FetchPriority::Foreground, 4).unwrap();
+        // Asking again raises a page's priority but never lowers it
+        db.enqueue_fetch(&url("c"), FetchPriority::Refresh, 5).unwrap();
+        db.enqueue_fetch(&url("d"), FetchPriority::Prefetch, 6).unwrap();
+
+        let pending = db.pending_fetches().unwrap();
+        let order: Vec<_> = pending.iter().map(|f| (f.url.clone(), f.priority, f.enqueued_at)).collect();
+        assert_eq!(order, vec![
+            (url("d"), FetchPriority::Foreground, 4),
+            (url("b"), FetchPriority::Refresh, 2),
+            (url("c"), FetchPriority::Refresh, 3),
+            (url("a"), FetchPriority::Prefetch, 1),
+        ]);
+
+        let popped: Vec<_> = std::iter::from_fn(|| db.pop_fetch().unwrap()).collect();
+        assert_eq!(popped, pending);
+        assert_eq!(db.pop_fetch().unwrap(), None);
+    }
+
+    #[test]
+    fn cached_albums_carry_their_latest_score_change() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let kid_a = "https://rateyourmusic.com/release/album/radiohead/kid-a/";
+        db.save_rating(&rating("Kid A", "Radiohead", kid_a, 4.0)).unwrap();
+        db.save_rating(&rating("Kid A", "Radiohead", kid_a, 4.25)).unwrap();
+        // A re-save of an unchanged page doesn't hide the last move
+        db.save_rating(&rating("Kid A", "Radiohead", kid_a, 4.25)).unwrap();
+        db.save_rating(&rating("Amnesiac", "Radiohead", "https://rateyourmusic.com/release/album/radiohead/amnesiac/", 3.75)).unwrap();
+        db.save_rating(&rating("Unmatched", "Nobody", "", 3.0)).unwrap();
+
+        let albums: Vec<_> = db.cached_albums().unwrap().into_iter().map(|a| (a.album_name, a.score_change)).collect();
+        assert_eq!(albums, vec![("Kid A".to_string(), Some(0.25)), ("Amnesiac".to_string(), None)]);
+
+        assert_eq!(db.refresh_pages("2025-12-15").unwrap(), 0);
+        assert_eq!(db.count_refresh_page("2025-12-15").unwrap(), 1);
+        assert_eq!(db.count_refresh_page("2025-12-15").unwrap(), 2);
+        assert_eq!(db.refresh_pages("2025-12-15").unwrap(), 2);
+        assert_eq!(db.refresh_pages("2025-12-16").unwrap(), 0);
+    }
+
+    #[test]
+    fn filters_new_releases_by_favourite_genres() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        for (album, genres, score) in [
+            ("a", "Ambient, Drone", 4.0),
+            ("b", "Ambient", 3.8),
+            ("c", "Indie Pop", 2.9),
+            ("d", "Indie Pop", 3.1),
+            ("e", "Drone", 3.9),
+        ] {
+            let mut r = rating(album, "Various", &format!("https://rateyourmusic.com/release/album/various/{}/", album), score);
+            r.genres = genres.to_string();
+            db.save_rating(&r).unwrap();
+        }
+        let release = |title: &str, date: &str, genres: &str| UpcomingRelease {
+            release_type: "album".to_string(),
+            title: title.to_string(),
+            artist_name: "Someone".to_string(),
+            artist_urls: vec!["https://rateyourmusic.com/artist/someone".to_string()],
+            rym_url: format!("https://rateyourmusic.com/release/album/someone/{}/", title),
+            release_date: date.to_string(),
+            genres: genres.to_string(),
+            rating: None,
+            count: None,
+            wants: Some(3),
+            timestamp: 0,
+        };
+        db.save_upcoming_releases(&[
+            release("drift", "21 November 2025", "Progressive Electronic, Ambient"),
+            release("jangle", "4 December 2025", "Indie Pop"),
+            release("hum", "December 2025", "Drone"),
+            release("later", "16 January 2026", "Dark Ambient, Ambient"),
+            // Not tagged yet: kept only for artists the cache knows
+            UpcomingRelease { artist_name: "Various".to_string(), ..release("untagged", "28 November 2025", "") },
+            release("unknown", "29 November 2025", ""),
+        ])
+        .unwrap();
+
+        let now = ReleaseDate::parse("2 December 2025").unwrap().timestamp().unwrap() + 3600;
+        let titles = |filter: NewReleaseFilter| -> Vec<String> {
+            db.get_new_releases(&filter, now).unwrap().into_iter().map(|r| r.title).collect()
+        };
+
+        // Indie Pop averages 3.0; "Drone" has two albums at 3.95
+        assert_eq!(titles(NewReleaseFilter::default()), ["later", "hum", "untagged", "drift"]);
+        // "December 2025" may still be ahead
+        assert_eq!(titles(NewReleaseFilter { upcoming_only: true, ..Default::default() }), ["hum", "later"]);
+        assert_eq!(titles(NewReleaseFilter { min_rating: 3.0, ..Default::default() }), ["later", "jangle", "hum", "untagged", "drift"]);
+        // Ambient averages 3.9, Drone 3.95
+        assert_eq!(titles(NewReleaseFilter { min_rating: 3.92, ..Default::default() }), ["hum", "untagged"]);
+        assert_eq!(titles(NewReleaseFilter { min_albums: 3, ..Default::default() }), ["untagged"]);
+        assert_eq!(titles(NewReleaseFilter { limit: Some(1), ..Default::default() }), ["later"]);
+
+        let stored = db.get_upcoming_release("https://rateyourmusic.com/release/album/someone/later/").unwrap().unwrap();
+        assert_eq!(stored, release("later", "16 January 2026", "Dark Ambient, Ambient"));
+    }
+
+    #[test]
+    fn finds_artist_by_romanized_name() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.save_artist(&Artist {
+            name: "아이유 [IU]".to_string(),
+            rym_url: "https://rateyourmusic.com/artist/iu".to_string(),
+            formed: None,
+            born: Some("16 May 1993, Seoul, South Korea".to_string()),
+            genres: "K-Pop".to_string(),
+            releases: Vec::new(),
+            timestamp: 0,
+        })
+        .unwrap();
+
+        assert!(db.get_artist("IU").unwrap().is_some());
+        assert!(db.get_artist("아이유").unwrap().is_some());
+    }
 }
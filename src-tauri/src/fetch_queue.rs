@@ -0,0 +1,311 @@
+use crate::database::{Database, FetchPriority, QueuedFetch};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{oneshot, Notify};
+use tokio::time::Instant;
+
+// RYM page loads: a few in quick succession, then one every two seconds
+pub const RYM_BURST: u32 = 3;
+pub const RYM_PAGES_PER_SECOND: f64 = 0.5;
+
+// Allows `capacity` requests at once and refills at `per_second`
+pub struct TokenBucket {
+    capacity: f64,
+    per_second: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(capacity: u32, per_second: f64) -> Self {
+        TokenBucket { capacity: capacity as f64, per_second, tokens: capacity as f64, updated: Instant::now() }
+    }
+
+    // Takes a token, or says how long until the next one
+    pub fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.capacity);
+        self.updated = self.updated.max(now);
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.per_second))
+        }
+    }
+}
+
+#[derive(Default)]
+struct Waiting {
+    // The page being fetched right now; asking for it again joins that fetch
+    in_flight: Option<String>,
+    callers: HashMap<String, Vec<oneshot::Sender<Result<(), String>>>>,
+}
+
+// RYM pages to fetch, most urgent first, one URL at most once. The queue lives in
+// SQLite so background refreshes survive a restart; a single worker (`run`) takes
+// pages off it as the token bucket allows.
+pub struct FetchQueue {
+    db: Arc<Mutex<Database>>,
+    bucket: Mutex<TokenBucket>,
+    waiting: Mutex<Waiting>,
+    // Wakes the worker when a page is queued
+    queued: Notify,
+    // When the user last asked for a page, or startup
+    last_foreground: Mutex<Instant>,
+}
+
+impl FetchQueue {
+    // Foreground requests left over from the last session were for windows that are
+    // gone; they are dropped
+    pub fn new(db: Arc<Mutex<Database>>, bucket: TokenBucket) -> Self {
+        match db.lock().unwrap().clear_fetches(FetchPriority::Foreground) {
+            Ok(0) => {}
+            Ok(n) => println!("RYM-QUEUE: Dropped {} foreground fetches from the last session", n),
+            Err(e) => eprintln!("RYM-QUEUE: ❌ Failed to clear old fetches: {}", e),
+        }
+        FetchQueue {
+            db,
+            bucket: Mutex::new(bucket),
+            waiting: Mutex::new(Waiting::default()),
+            queued: Notify::new(),
+            last_foreground: Mutex::new(Instant::now()),
+        }
+    }
+
+    // Queues a page without waiting for it
+    pub fn enqueue(&self, url: &str, priority: FetchPriority) -> Result<(), String> {
+        self.push(url, priority, None)
+    }
+
+    // Queues a page and waits until the worker has fetched it
+    pub async fn fetch(&self, url: &str, priority: FetchPriority) -> Result<(), String> {
+        let (tx, rx) = oneshot::channel();
+        self.push(url, priority, Some(tx))?;
+        rx.await.map_err(|_| "Fetch queue stopped".to_string())?
+    }
+
+    fn push(&self, url: &str, priority: FetchPriority, caller: Option<oneshot::Sender<Result<(), String>>>) -> Result<(), String> {
+        if priority == FetchPriority::Foreground {
+            *self.last_foreground.lock().unwrap() = Instant::now();
+        }
+        let mut waiting = self.waiting.lock().unwrap();
+        if waiting.in_flight.as_deref() != Some(url) {
+            self.db
+                .lock()
+                .unwrap()
+                .enqueue_fetch(url, priority, chrono::Utc::now().timestamp())
+                .map_err(|e| format!("Failed to queue {}: {}", url, e))?;
+            println!("RYM-QUEUE: Queued {} ({:?})", url, priority);
+        }
+        if let Some(tx) = caller {
+            waiting.callers.entry(url.to_string()).or_default().push(tx);
+        }
+        drop(waiting);
+        self.queued.notify_one();
+        Ok(())
+    }
+
+    pub fn pending(&self) -> Vec<QueuedFetch> {
+        self.db.lock().unwrap().pending_fetches().unwrap_or_default()
+    }
+
+    // Time since the user last asked for a page
+    pub fn idle_for(&self) -> Duration {
+        self.last_foreground.lock().unwrap().elapsed()
+    }
+
+    // Fetches queued pages for as long as the app runs
+    pub async fn run<F, Fut>(&self, mut fetch: F)
+    where
+        F: FnMut(QueuedFetch) -> Fut,
+        Fut: Future<Output = Result<(), String>>,
+    {
+        loop {
+            let page = self.next().await;
+            println!("RYM-QUEUE: Fetching {} ({:?})", page.url, page.priority);
+            let result = fetch(page.clone()).await;
+            if let Err(e) = &result {
+                eprintln!("RYM-QUEUE: ❌ {}: {}", page.url, e);
+            }
+
+            let callers = {
+                let mut waiting = self.waiting.lock().unwrap();
+                waiting.in_flight = None;
+                waiting.callers.remove(&page.url).unwrap_or_default()
+            };
+            for caller in callers {
+                let _ = caller.send(result.clone());
+            }
+        }
+    }
+
+    // The most urgent page once there is one and the bucket has a token for it. The
+    // page is picked after the wait, so a foreground request that arrives meanwhile
+    // goes first.
+    async fn next(&self) -> QueuedFetch {
+        loop {
+            while self.pending().is_empty() {
+                self.queued.notified().await;
+            }
+            self.take_token().await;
+
+            let mut waiting = self.waiting.lock().unwrap();
+            match self.db.lock().unwrap().pop_fetch() {
+                Ok(Some(page)) => {
+                    waiting.in_flight = Some(page.url.clone());
+                    return page;
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("RYM-QUEUE: ❌ Failed to read the queue: {}", e),
+            }
+        }
+    }
+
+    async fn take_token(&self) {
+        loop {
+            let wait = self.bucket.lock().unwrap().try_take(Instant::now());
+            match wait {
+                Ok(()) => return,
+                Err(wait) => {
+                    println!("RYM-QUEUE: Delaying next fetch by {}ms to respect rate limits", wait.as_millis());
+                    tokio::time::sleep(wait).await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn memory_db() -> Arc<Mutex<Database>> {
+        Arc::new(Mutex::new(Database::new(PathBuf::from(":memory:")).unwrap()))
+    }
+
+    fn url(name: &str) -> String {
+        format!("https://rateyourmusic.com/release/album/{}/", name)
+    }
+
+    // Starts a worker that records each page it fetches
+    fn start(queue: &Arc<FetchQueue>) -> Arc<Mutex<Vec<(String, Instant)>>> {
+        let fetched = Arc::new(Mutex::new(Vec::new()));
+        let (queue, log) = (queue.clone(), fetched.clone());
+        tokio::spawn(async move {
+            queue
+                .run(|page| {
+                    log.lock().unwrap().push((page.url, Instant::now()));
+                    async { Ok(()) }
+                })
+                .await
+        });
+        fetched
+    }
+
+    #[test]
+    fn bucket_allows_a_burst_then_refills() {
+        let mut bucket = TokenBucket::new(3, 0.5);
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            assert_eq!(bucket.try_take(start), Ok(()));
+        }
+        let wait = bucket.try_take(start).unwrap_err();
+        assert!(wait > Duration::from_millis(1900) && wait <= Duration::from_secs(2), "{:?}", wait);
+
+        assert!(bucket.try_take(start + Duration::from_secs(1)).is_err());
+        assert_eq!(bucket.try_take(start + Duration::from_secs(2)), Ok(()));
+        // A long pause refills up to the burst, not beyond
+        let later = start + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert_eq!(bucket.try_take(later), Ok(()));
+        }
+        assert!(bucket.try_take(later).is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fetches_are_rate_limited_in_priority_order() {
+        let queue = Arc::new(FetchQueue::new(memory_db(), TokenBucket::new(1, 10.0)));
+        queue.enqueue(&url("prefetch"), FetchPriority::Prefetch).unwrap();
+        queue.enqueue(&url("refresh"), FetchPriority::Refresh).unwrap();
+        let fetched = start(&queue);
+
+        // Arrives while the worker waits for a token, and still goes second
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        queue.fetch(&url("foreground"), FetchPriority::Foreground).await.unwrap();
+        while fetched.lock().unwrap().len() < 3 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let fetched = fetched.lock().unwrap().clone();
+        let order: Vec<_> = fetched.iter().map(|(u, _)| u.clone()).collect();
+        assert_eq!(order, vec![url("refresh"), url("foreground"), url("prefetch")]);
+        for pair in fetched.windows(2) {
+            let gap = pair[1].1 - pair[0].1;
+            assert!(gap >= Duration::from_millis(100), "{:?}", gap);
+        }
+        assert!(queue.pending().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn same_url_is_fetched_once() {
+        let queue = Arc::new(FetchQueue::new(memory_db(), TokenBucket::new(1, 20.0)));
+        let fetched = start(&queue);
+
+        let (kid_a, amnesiac) = (url("kid-a"), url("amnesiac"));
+        let (a, b, c) = tokio::join!(
+            queue.fetch(&kid_a, FetchPriority::Prefetch),
+            queue.fetch(&kid_a, FetchPriority::Foreground),
+            queue.fetch(&amnesiac, FetchPriority::Refresh),
+        );
+        assert!(a.is_ok() && b.is_ok() && c.is_ok());
+
+        let order: Vec<_> = fetched.lock().unwrap().iter().map(|(u, _)| u.clone()).collect();
+        assert_eq!(order, vec![url("kid-a"), url("amnesiac")]);
+    }
+
+    #[tokio::test]
+    async fn fetch_errors_reach_every_caller() {
+        let queue = Arc::new(FetchQueue::new(memory_db(), TokenBucket::new(2, 20.0)));
+        let worker = queue.clone();
+        tokio::spawn(async move { worker.run(|page| async move { Err(format!("{} is gone", page.url)) }).await });
+
+        let lost = url("lost");
+        let (a, b) = tokio::join!(
+            queue.fetch(&lost, FetchPriority::Foreground),
+            queue.fetch(&lost, FetchPriority::Foreground),
+        );
+        let expected = Err(format!("{} is gone", lost));
+        assert_eq!((a, b), (expected.clone(), expected));
+    }
+
+    #[tokio::test]
+    async fn background_fetches_survive_a_restart() {
+        let path = std::env::temp_dir().join(format!("rym_fetch_queue_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        {
+            let db = Arc::new(Mutex::new(Database::new(path.clone()).unwrap()));
+            let queue = FetchQueue::new(db, TokenBucket::new(1, 1.0));
+            queue.enqueue(&url("stale"), FetchPriority::Refresh).unwrap();
+            queue.enqueue(&url("clicked"), FetchPriority::Foreground).unwrap();
+            queue.enqueue(&url("upcoming"), FetchPriority::Prefetch).unwrap();
+        }
+
+        let db = Arc::new(Mutex::new(Database::new(path.clone()).unwrap()));
+        let queue = Arc::new(FetchQueue::new(db, TokenBucket::new(2, 1.0)));
+        let urls: Vec<_> = queue.pending().into_iter().map(|f| f.url).collect();
+        assert_eq!(urls, vec![url("stale"), url("upcoming")]);
+
+        let fetched = start(&queue);
+        while fetched.lock().unwrap().len() < 2 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(queue.pending().is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+}
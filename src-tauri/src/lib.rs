@@ -1,141 +1,238 @@
+mod browser;
 mod database;
+mod fetch_queue;
+mod lookup;
+mod matcher;
+mod media_links;
+mod migrations;
+mod refresher;
 mod release_date;
+mod rym_fetch;
+mod rym_parse;
 mod supabase;
-
-use database::{AlbumRating, Database};
-use std::sync::Mutex;
+mod sync;
+mod ttl_policy;
+
+use browser::{BrowserSurface, Window};
+use database::{AlbumRating, Alias, Artist, ArtistRelease, ChartMove, Collection, CollectionEntry, Database, FetchPriority, Genre, GenreAlbum, GenreGroup, GenreLink, MediaLink, NewReleaseFilter, QueuedFetch, RatingMove, RatingSnapshot, SongRating, UpcomingRelease};
+use fetch_queue::{FetchQueue, TokenBucket, RYM_BURST, RYM_PAGES_PER_SECOND};
+use lookup::LookupService;
+use refresher::{RefreshStatus, Refresher};
+use rym_fetch::HttpFetcher;
+use rym_parse::{ListPage, ListSummary, SearchResult};
+use std::sync::{Arc, Mutex};
 use tauri::{Emitter, Manager, State, window::Color, menu::{AboutMetadata, Menu, MenuItem, PredefinedMenuItem, Submenu}};
 use supabase::SupabaseClient;
+use sync::{MusicSync, SyncAlbum, SyncController, RYM_HOME_URL};
+use ttl_policy::{PopularityPolicy, TtlInput, TtlPolicy, TtlSettings};
 
 // Application state to hold the database connection and Supabase client
 pub struct AppState {
-    db: Mutex<Database>,
+    db: Arc<Mutex<Database>>,
     supabase: Option<SupabaseClient>,
-    pending_apple_music_url: Mutex<Option<String>>,
-    // Track what each window is currently displaying to prevent redundant syncs
-    music_current_info: Mutex<Option<String>>, // "Artist - Album"
-    rym_current_info: Mutex<Option<String>>,    // "Artist - Album"
-    current_music_url: Mutex<Option<String>>,   // Exact URL currently on AM, for ping-pong prevention
-    // Rate limiting for RYM navigation (timestamp in milliseconds)
-    last_rym_navigation: Mutex<Option<u128>>,
-    rym_initialized: Mutex<bool>, // Track if RYM window has been loaded at least once
-    prevent_next_am_sync: Mutex<bool>, // Force blocking of the next sync from AM to RYM
+    // Keeps the two windows on the same album and rate-limits RYM page loads
+    sync: SyncController,
+    // Shares one SQLite/Supabase lookup between concurrent requests for the same album
+    lookups: LookupService<Option<CachedRating>>,
+    // Decides how long a cached rating stays fresh
+    ttl_policy: Arc<dyn TtlPolicy>,
+    // Refreshes stale ratings in the background while RYM is idle
+    refresher: Refresher,
+    // Fetches background pages without the RYM window; None unless RYM_HEADLESS_FETCH=1
+    fetcher: Option<HttpFetcher>,
 }
 
-// IPC Command to get RYM rating for an album
-#[tauri::command]
-async fn get_rym_rating(
-    artist: String,
-    album: String,
-    state: State<'_, AppState>,
+// The sync flow's view of the app's webview windows
+struct TauriBrowser {
     app: tauri::AppHandle,
-) -> Result<Option<AlbumRating>, String> {
-    use release_date::{parse_release_date_to_timestamp, compute_ttl_seconds, is_fresh};
-    
-    println!("RYM-GET-RATING: ========================================");
-    println!("RYM-GET-RATING: Request for: {} - {}", artist, album);
-    
+}
+
+impl TauriBrowser {
+    fn new(app: tauri::AppHandle) -> Self {
+        TauriBrowser { app }
+    }
+
+    fn window(&self, window: Window) -> Option<tauri::WebviewWindow> {
+        self.app.get_webview_window(window.label())
+    }
+}
+
+impl BrowserSurface for TauriBrowser {
+    fn has_window(&self, window: Window) -> bool {
+        self.window(window).is_some()
+    }
+
+    fn navigate(&self, window: Window, url: &str) -> Result<(), String> {
+        let w = self.window(window).ok_or_else(|| format!("{} window not found", window.name()))?;
+        let url = url.parse().map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+        w.navigate(url).map_err(|e| format!("Failed to navigate: {}", e))
+    }
+
+    fn eval(&self, window: Window, js: &str) {
+        if let Some(w) = self.window(window) {
+            let _ = w.eval(js);
+        }
+    }
+
+    fn show(&self, window: Window) {
+        if let Some(w) = self.window(window) {
+            let _ = w.show();
+            let _ = w.set_focus();
+        }
+    }
+
+    fn hide(&self, window: Window) {
+        if let Some(w) = self.window(window) {
+            let _ = w.hide();
+        }
+    }
+
+    fn is_visible(&self, window: Window) -> bool {
+        self.window(window).is_some_and(|w| w.is_visible().unwrap_or(false))
+    }
+
+    fn emit_rating(&self, rating: &AlbumRating) {
+        let _ = self.app.emit("rym-rating-updated", rating.clone());
+    }
+}
+
+// Best cached rating for an album, with status "fresh" or "stale"
+#[derive(Clone)]
+struct CachedRating {
+    rating: AlbumRating,
+    // Fresh Supabase hits were just copied into SQLite and haven't been broadcast yet
+    from_supabase: bool,
+}
+
+// Checks local SQLite, then Supabase. Callers asking for the same album while a lookup
+// is in flight wait for it instead of repeating it.
+async fn lookup_cached_rating(artist: &str, album: &str, state: &AppState) -> Option<CachedRating> {
+    state.lookups.get_or_fetch(artist, album, || resolve_cached_rating(artist, album, state)).await
+}
+
+// TTL of a cached rating under the app's policy, using the album's local snapshot history.
+// The rating is already resolved, so its history is read by URL rather than matched by name again.
+fn rating_ttl(rating: &AlbumRating, now: i64, state: &AppState) -> i64 {
+    let history = {
+        let db = state.db.lock().unwrap();
+        db.get_rating_history_by_url(&rating.rym_url).unwrap_or_default()
+    };
+    let release = release_date::ReleaseDate::parse(&rating.release_date);
+    state.ttl_policy.ttl_seconds(&TtlInput {
+        now,
+        release: release.as_ref(),
+        rating_count: rating.rating_count,
+        score_change: ttl_policy::score_change(&history),
+    })
+}
+
+async fn resolve_cached_rating(artist: &str, album: &str, state: &AppState) -> Option<CachedRating> {
+    use release_date::is_fresh;
+
     let now = chrono::Utc::now().timestamp();
-    
+
     // 1. Check SQLite
-    println!("RYM-GET-RATING: Checking local SQLite cache...");
+    println!("RYM-LOOKUP: Checking local SQLite cache for {} - {}...", artist, album);
     let local_rating = {
         let db = state.db.lock().unwrap();
-        db.get_rating(&album, &artist).ok().flatten()
+        db.get_rating(album, artist).ok().flatten()
     };
-    
+
     let mut best_candidate: Option<AlbumRating> = None;
-    
+
     if let Some(mut rating) = local_rating {
-        let release_ts = parse_release_date_to_timestamp(&rating.release_date);
-        let ttl = compute_ttl_seconds(now, release_ts);
-        
-        let tracks_json = rating.track_ratings.as_ref().map(|s| s.as_str()).unwrap_or("[]");
-        let has_tracks = tracks_json.len() > 5;
-        let reviews_json = rating.reviews.as_ref().map(|s| s.as_str()).unwrap_or("[]");
-        let has_reviews = reviews_json.len() > 5;
-        
-        println!("RYM-GET-RATING: Found local cache entry.");
-        println!("RYM-GET-RATING:   - Last fetched: {} ({} seconds ago)", rating.timestamp, now - rating.timestamp);
-        println!("RYM-GET-RATING:   - TTL: {} seconds", ttl);
-        println!("RYM-GET-RATING:   - Has tracks: {} (JSON len: {})", has_tracks, tracks_json.len());
-        println!("RYM-GET-RATING:   - Has reviews: {} (JSON len: {})", has_reviews, reviews_json.len());
-        
+        let ttl = rating_ttl(&rating, now, state);
+
+        let has_tracks = !rating.track_ratings.is_empty();
+        let has_reviews = !rating.reviews.is_empty();
+
+        println!("RYM-LOOKUP: Found local cache entry.");
+        println!("RYM-LOOKUP:   - Last fetched: {} ({} seconds ago)", rating.timestamp, now - rating.timestamp);
+        println!("RYM-LOOKUP:   - TTL: {} seconds", ttl);
+        println!("RYM-LOOKUP:   - Has tracks: {} ({} tracks)", has_tracks, rating.track_ratings.len());
+        println!("RYM-LOOKUP:   - Has reviews: {} ({} reviews)", has_reviews, rating.reviews.len());
+
         if is_fresh(rating.timestamp, ttl, now) && has_tracks {
-            println!("RYM-GET-RATING: ✓ LOCAL CACHE HIT (FRESH & COMPLETE)");
+            println!("RYM-LOOKUP: ✓ LOCAL CACHE HIT (FRESH & COMPLETE)");
             rating.status = Some("fresh".to_string());
-            return Ok(Some(rating));
+            return Some(CachedRating { rating, from_supabase: false });
         } else {
             let reason = if !has_tracks { "INCOMPLETE DATA (No tracks)" } else { "STALE (TTL expired)" };
-            println!("RYM-GET-RATING: ⚠️ LOCAL CACHE HIT (RE-FETCH NEEDED - Reason: {})", reason);
+            println!("RYM-LOOKUP: ⚠️ LOCAL CACHE HIT (RE-FETCH NEEDED - Reason: {})", reason);
             rating.status = Some("stale".to_string());
             best_candidate = Some(rating);
         }
     } else {
-        println!("RYM-GET-RATING: ❌ Local cache miss");
+        println!("RYM-LOOKUP: ❌ Local cache miss");
     }
-    
+
     // 2. Check Supabase
-    println!("RYM-GET-RATING: Checking Supabase cache...");
+    println!("RYM-LOOKUP: Checking Supabase cache...");
     if let Some(supabase) = &state.supabase {
-        if let Some(mut rating) = supabase.get_cached_rating(&artist, &album).await {
-             let release_ts = parse_release_date_to_timestamp(&rating.release_date);
-             let ttl = compute_ttl_seconds(now, release_ts);
-             
-             if is_fresh(rating.timestamp, ttl, now) {
-                 println!("RYM-GET-RATING: ✓ SUPABASE CACHE HIT (FRESH)");
-                 
-                 // Save to local
-                 println!("RYM-GET-RATING: Saving to local cache...");
-                 let _ = state.db.lock().unwrap().save_rating(&rating);
-                 
-                 // Broadcast
-                 let _ = app.emit("rym-rating-updated", rating.clone());
-                 
-                 rating.status = Some("fresh".to_string());
-                 return Ok(Some(rating));
-             } else {
-                 println!("RYM-GET-RATING: ⚠️ SUPABASE CACHE HIT (STALE)");
-                 
-                 let use_supabase = match &best_candidate {
-                     Some(local) => rating.timestamp > local.timestamp,
-                     None => true,
-                 };
-                 
-                 if use_supabase {
-                     rating.status = Some("stale".to_string());
-                     best_candidate = Some(rating);
-                 }
-             }
+        if let Some(mut rating) = supabase.get_cached_rating(artist, album).await {
+            let ttl = rating_ttl(&rating, now, state);
+
+            if is_fresh(rating.timestamp, ttl, now) {
+                println!("RYM-LOOKUP: ✓ SUPABASE CACHE HIT (FRESH)");
+                rating.status = Some("fresh".to_string());
+
+                println!("RYM-LOOKUP: Saving Supabase data to local cache...");
+                let _ = state.db.lock().unwrap().save_rating(&rating);
+
+                return Some(CachedRating { rating, from_supabase: true });
+            } else {
+                println!("RYM-LOOKUP: ⚠️ SUPABASE CACHE HIT (STALE)");
+
+                let use_supabase = match &best_candidate {
+                    Some(local) => rating.timestamp > local.timestamp,
+                    None => true,
+                };
+
+                if use_supabase {
+                    rating.status = Some("stale".to_string());
+                    best_candidate = Some(rating);
+                }
+            }
         }
     } else {
-        println!("RYM-GET-RATING: Supabase client not configured");
+        println!("RYM-LOOKUP: Supabase client not configured");
     }
+
+    best_candidate.map(|rating| CachedRating { rating, from_supabase: false })
+}
+
+// IPC Command to get RYM rating for an album
+#[tauri::command]
+async fn get_rym_rating(
+    artist: String,
+    album: String,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<Option<AlbumRating>, String> {
+    println!("RYM-GET-RATING: ========================================");
+    println!("RYM-GET-RATING: Request for: {} - {}", artist, album);
+
+    let best_candidate = match lookup_cached_rating(&artist, &album, &state).await {
+        Some(cached) if cached.rating.status.as_deref() == Some("fresh") => {
+            if cached.from_supabase {
+                let _ = app.emit("rym-rating-updated", cached.rating.clone());
+            }
+            return Ok(Some(cached.rating));
+        }
+        Some(cached) => Some(cached.rating),
+        None => None,
+    };
     
     // 3. Handle Miss / Stale
     
     // Check if user is on RYM tab
-    let is_rym_visible = if let Some(w) = app.get_webview_window("rym") {
-        w.is_visible().unwrap_or(false)
-    } else {
-        false
-    };
-    
-    if is_rym_visible {
+    if TauriBrowser::new(app.clone()).is_visible(Window::Rym) {
         println!("RYM-GET-RATING: User is on RYM tab. Initiating navigation/scrape...");
         
-        let query = format!("{} {}", artist, album);
-        let encoded_query = urlencoding::encode(&query);
-        let search_url = format!("https://rateyourmusic.com/search?searchterm={}&searchtype=l", encoded_query);
-        
         let app_handle = app.clone();
-        // I'll just use the app_handle to get state inside the spawn
         tauri::async_runtime::spawn(async move {
-            if navigate_to_rym_with_rate_limit(&app_handle, search_url).await.is_ok() {
-                let state = app_handle.state::<AppState>();
-                let mut init = state.rym_initialized.lock().unwrap();
-                *init = true;
-            }
+            let state = app_handle.state::<AppState>();
+            let _ = state.sync.search_rym(&artist, &album, FetchPriority::Foreground).await;
         });
         
         if let Some(rating) = best_candidate {
@@ -174,23 +271,28 @@ async fn save_rym_rating(
     println!("RYM-SAVE-RATING:   - Rank: {:?}", rating.rank);
     println!("RYM-SAVE-RATING:   - Release Date: {}", rating.release_date);
     
-    let track_count = rating.track_ratings.as_ref().map(|s| s.matches("title").count()).unwrap_or(0);
-    let review_count = rating.reviews.as_ref().map(|s| s.matches("reviewer").count()).unwrap_or(0);
+    let track_count = rating.track_ratings.len();
+    let review_count = rating.reviews.len();
     println!("RYM-SAVE-RATING:   - Tracks Found: {}", track_count);
     println!("RYM-SAVE-RATING:   - Reviews Found: {}", review_count);
     println!("RYM-SAVE-RATING:   - Timestamp: {}", rating.timestamp);
     
-    // Clear loop prevention on RYM side if this is a fresh extraction
-    if let Some(rym_window) = app.get_webview_window("rym") {
-        let _ = rym_window.eval("localStorage.removeItem('tauri_ignore_next_sync')");
-        println!("RYM-SAVE-RATING: Cleared sync loop prevention flag");
-    }
+    store_rating(&rating, &state, &app)?;
+
+    // The RYM window is showing this album now
+    state.sync.rym_loaded(SyncAlbum::new(&rating.artist_name, &rating.album_name));
+
+    println!("RYM-SAVE-RATING: ========================================");
+    Ok(())
+}
 
+// Saves a rating locally and to Supabase, and broadcasts it to the windows
+fn store_rating(rating: &AlbumRating, state: &AppState, app: &tauri::AppHandle) -> Result<(), String> {
     // Save to local SQLite database
     println!("RYM-SAVE-RATING: Saving to local SQLite database...");
     {
         let db = state.db.lock().unwrap();
-        db.save_rating(&rating)
+        db.save_rating(rating)
             .map_err(|e| {
                 eprintln!("RYM-SAVE-RATING: ❌ Failed to save to local database: {}", e);
                 format!("Failed to save rating locally: {}", e)
@@ -203,13 +305,6 @@ async fn save_rym_rating(
     let _ = app.emit("rym-rating-updated", rating.clone());
     println!("RYM-SAVE-RATING: ✓ Broadcast complete");
 
-    // Update RYM state in protector
-    {
-        let mut rym_info = state.rym_current_info.lock().unwrap();
-        *rym_info = Some(format!("{} - {}", rating.artist_name, rating.album_name));
-        println!("RYM-SAVE-RATING: ✓ Updated RYM state tracker");
-    }
-
     // Save to Supabase asynchronously
     if let Some(supabase) = &state.supabase {
         println!("RYM-SAVE-RATING: Initiating async Supabase save...");
@@ -225,11 +320,329 @@ async fn save_rym_rating(
     } else {
         println!("RYM-SAVE-RATING: Supabase client not configured, skipping cloud save");
     }
-    
-    println!("RYM-SAVE-RATING: ========================================");
     Ok(())
 }
 
+// Fetches a background release page over HTTP with the RYM window's session and
+// stores it like one scraped from the window
+async fn fetch_release_offscreen(fetcher: &HttpFetcher, url: String, app: &tauri::AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(Window::Rym.label()) {
+        match window.cookies_for_url(tauri::Url::parse(RYM_HOME_URL).unwrap()) {
+            Ok(cookies) => {
+                fetcher.import_cookies(cookies.iter().map(|c| (c.name(), c.value())));
+            }
+            Err(e) => eprintln!("RYM-HEADLESS: ❌ Could not read the RYM session cookies: {}", e),
+        }
+    }
+
+    println!("RYM-HEADLESS: Fetching {}", url);
+    let page = fetcher.fetch_release(&url).await?;
+    let state = app.state::<AppState>();
+    if let Err(e) = state.db.lock().unwrap().save_media_links(&page.rating.rym_url, &page.links) {
+        eprintln!("RYM-HEADLESS: ❌ Failed to save media links: {}", e);
+    }
+    store_rating(&page.rating, &state, app)?;
+    println!("RYM-HEADLESS: ✓ Refreshed {} - {}", page.rating.artist_name, page.rating.album_name);
+    Ok(())
+}
+
+// IPC Command to parse a raw RYM release page in Rust and save the result
+#[tauri::command]
+async fn save_rym_page(
+    url: String,
+    html: String,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    println!("RYM-SAVE-RATING: Parsing release page natively: {}", url);
+    let rating = rym_parse::parse_release_page(&html, &url).map_err(|e| {
+        eprintln!("RYM-SAVE-RATING: ❌ Failed to parse release page: {}", e);
+        format!("Failed to parse release page: {}", e)
+    })?;
+    let links = media_links::parse_media_links(&html);
+    if let Err(e) = state.db.lock().unwrap().save_media_links(&rating.rym_url, &links) {
+        eprintln!("RYM-SAVE-RATING: ❌ Failed to save media links: {}", e);
+    }
+    save_rym_rating(rating, state, app).await
+}
+
+// IPC Command for the media-link container of the release open in the RYM window:
+// stores the release's streaming links and sends Apple Music to the same album
+#[tauri::command]
+fn save_rym_media_links(
+    url: String,
+    artist: String,
+    album: String,
+    html: String,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<Vec<MediaLink>, String> {
+    let links = media_links::parse_media_links(&html);
+    println!("RYM-MEDIA-LINKS: {} links for {} - {}", links.len(), artist, album);
+    // "https://rateyourmusic.com/release/album/x/y/#reviews"
+    let release_url = url.split(['#', '?']).next().unwrap_or(&url).to_string();
+    {
+        let db = state.db.lock().unwrap();
+        db.save_media_links(&release_url, &links)
+            .map_err(|e| format!("Failed to save media links: {}", e))?;
+    }
+
+    let apple_music_url = media_links::apple_music_url(&links);
+    state.sync.sync_to_music(&TauriBrowser::new(app), SyncAlbum::new(&artist, &album), apple_music_url.as_deref());
+    Ok(links)
+}
+
+// IPC Command to get the stored streaming links of a release
+#[tauri::command]
+fn get_media_links(rym_url: String, state: State<'_, AppState>) -> Result<Vec<MediaLink>, String> {
+    let db = state.db.lock().unwrap();
+    db.get_media_links(&rym_url).map_err(|e| e.to_string())
+}
+
+// IPC Command to parse a raw RYM artist page and cache the artist with their discography
+#[tauri::command]
+fn save_rym_artist_page(url: String, html: String, state: State<'_, AppState>) -> Result<(), String> {
+    println!("RYM-SAVE-ARTIST: Parsing artist page: {}", url);
+    let artist = rym_parse::parse_artist_page(&html, &url).map_err(|e| {
+        eprintln!("RYM-SAVE-ARTIST: ❌ Failed to parse artist page: {}", e);
+        format!("Failed to parse artist page: {}", e)
+    })?;
+    let db = state.db.lock().unwrap();
+    db.save_artist(&artist).map_err(|e| format!("Failed to save artist: {}", e))
+}
+
+// IPC Command to get a cached artist with their full discography
+#[tauri::command]
+fn get_rym_artist(artist: String, state: State<'_, AppState>) -> Result<Option<Artist>, String> {
+    let db = state.db.lock().unwrap();
+    db.get_artist(&artist).map_err(|e| e.to_string())
+}
+
+// IPC Command for "best albums by this artist", from the cached discography
+#[tauri::command]
+fn get_best_releases(
+    artist: String,
+    release_type: Option<String>,
+    limit: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<Vec<ArtistRelease>, String> {
+    let db = state.db.lock().unwrap();
+    db.get_best_releases(&artist, release_type.as_deref(), limit.unwrap_or(10))
+        .map_err(|e| e.to_string())
+}
+
+// IPC Command to parse a RYM song page and cache the song's own rating
+#[tauri::command]
+fn save_rym_song_page(url: String, html: String, state: State<'_, AppState>, app: tauri::AppHandle) -> Result<(), String> {
+    println!("RYM-SAVE-SONG: Parsing song page: {}", url);
+    let song = rym_parse::parse_song_page(&html, &url).map_err(|e| {
+        eprintln!("RYM-SAVE-SONG: ❌ Failed to parse song page: {}", e);
+        format!("Failed to parse song page: {}", e)
+    })?;
+    {
+        let db = state.db.lock().unwrap();
+        db.save_song(&song).map_err(|e| format!("Failed to save song: {}", e))?;
+    }
+    // The player shows it if this is the track that's playing
+    let _ = app.emit("rym-song-rating-updated", song);
+    Ok(())
+}
+
+// IPC Command to get the cached RYM rating of a single track
+#[tauri::command]
+fn get_song_rating(artist: String, title: String, state: State<'_, AppState>) -> Result<Option<SongRating>, String> {
+    let db = state.db.lock().unwrap();
+    db.get_song_rating(&title, &artist).map_err(|e| e.to_string())
+}
+
+// IPC Command to parse one page of a RYM chart into that week's snapshot
+#[tauri::command]
+fn save_rym_chart_page(url: String, html: String, state: State<'_, AppState>) -> Result<(), String> {
+    println!("RYM-SAVE-CHART: Parsing chart page: {}", url);
+    let chart = rym_parse::parse_chart_page(&html, &url).map_err(|e| {
+        eprintln!("RYM-SAVE-CHART: ❌ Failed to parse chart page: {}", e);
+        format!("Failed to parse chart page: {}", e)
+    })?;
+    let db = state.db.lock().unwrap();
+    db.save_chart(&chart).map_err(|e| format!("Failed to save chart: {}", e))
+}
+
+// IPC Command to compare the latest snapshot of a chart with the previous one
+#[tauri::command]
+fn get_chart_movers(chart_url: String, state: State<'_, AppState>) -> Result<Vec<ChartMove>, String> {
+    let db = state.db.lock().unwrap();
+    db.get_chart_movers(&rym_parse::first_page_url(&chart_url)).map_err(|e| e.to_string())
+}
+
+// IPC Command to import one page of a RYM list into a local collection
+#[tauri::command]
+fn import_rym_list_page(url: String, html: String, state: State<'_, AppState>) -> Result<ListPage, String> {
+    println!("RYM-SAVE-LIST: Parsing list page: {}", url);
+    let mut page = rym_parse::parse_list_page(&html, &url).map_err(|e| {
+        eprintln!("RYM-SAVE-LIST: ❌ Failed to parse list page: {}", e);
+        format!("Failed to parse list page: {}", e)
+    })?;
+    let db = state.db.lock().unwrap();
+    page.collection.id = db
+        .save_collection(&page.collection)
+        .map_err(|e| format!("Failed to save collection: {}", e))?;
+    Ok(page)
+}
+
+// IPC Command to read the lists shown on a RYM lists portal page
+#[tauri::command]
+fn parse_rym_lists_page(html: String) -> Vec<ListSummary> {
+    rym_parse::parse_lists_page(&html)
+}
+
+#[tauri::command]
+fn list_collections(state: State<'_, AppState>) -> Result<Vec<Collection>, String> {
+    let db = state.db.lock().unwrap();
+    db.list_collections().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_collection(id: i64, state: State<'_, AppState>) -> Result<Option<Collection>, String> {
+    let db = state.db.lock().unwrap();
+    db.get_collection(id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn remove_collection(id: i64, state: State<'_, AppState>) -> Result<bool, String> {
+    let db = state.db.lock().unwrap();
+    db.remove_collection(id).map_err(|e| e.to_string())
+}
+
+// IPC Command to walk a collection: moves to the next (or previous) entry and
+// searches for it in the Apple Music window
+#[tauri::command]
+fn step_collection(id: i64, forward: bool, state: State<'_, AppState>, app: tauri::AppHandle) -> Result<Option<CollectionEntry>, String> {
+    let entry = {
+        let db = state.db.lock().unwrap();
+        db.step_collection(id, forward).map_err(|e| e.to_string())?
+    };
+    let Some(entry) = entry else {
+        println!("RYM-COLLECTION: Reached the {} of collection {}", if forward { "end" } else { "start" }, id);
+        return Ok(None);
+    };
+
+    println!("RYM-COLLECTION: #{} {} - {}", entry.position, entry.artist_name, entry.title);
+    search_apple_music(&state, app, &entry.artist_name, &entry.title);
+    Ok(Some(entry))
+}
+
+// Searches Apple Music for a release in the music window and brings it to the front
+fn search_apple_music(state: &AppState, app: tauri::AppHandle, artist: &str, title: &str) {
+    let search_url = format!(
+        "https://music.apple.com/search?term={}",
+        urlencoding::encode(&format!("{} {}", artist, title))
+    );
+    let browser = TauriBrowser::new(app);
+    let _ = browser.navigate(Window::Music, &search_url);
+    state.sync.show_music(&browser);
+}
+
+// IPC Command to parse RYM's new music page into the local new releases feed
+#[tauri::command]
+fn save_rym_new_music_page(html: String, state: State<'_, AppState>) -> Result<usize, String> {
+    let releases = rym_parse::parse_new_music_page(&html);
+    println!("RYM-NEW-MUSIC: Found {} releases on the new music page", releases.len());
+    let db = state.db.lock().unwrap();
+    db.save_upcoming_releases(&releases)
+        .map_err(|e| format!("Failed to save new releases: {}", e))?;
+    Ok(releases.len())
+}
+
+// IPC Command for the new releases feed, limited to genres the cache rates highly
+#[tauri::command]
+fn get_new_releases(filter: Option<NewReleaseFilter>, state: State<'_, AppState>) -> Result<Vec<UpcomingRelease>, String> {
+    let db = state.db.lock().unwrap();
+    db.get_new_releases(&filter.unwrap_or_default(), chrono::Utc::now().timestamp())
+        .map_err(|e| e.to_string())
+}
+
+// IPC Command to open a release from the feed in Apple Music
+#[tauri::command]
+fn sync_new_release(rym_url: String, state: State<'_, AppState>, app: tauri::AppHandle) -> Result<(), String> {
+    let release = {
+        let db = state.db.lock().unwrap();
+        db.get_upcoming_release(&rym_url).map_err(|e| e.to_string())?
+    };
+    let release = release.ok_or_else(|| format!("Unknown new release: {}", rym_url))?;
+    println!("RYM-NEW-MUSIC: Searching Apple Music for {} - {}", release.artist_name, release.title);
+    search_apple_music(&state, app, &release.artist_name, &release.title);
+    Ok(())
+}
+
+// IPC Command to parse a RYM genre page into the genre tree
+#[tauri::command]
+fn save_rym_genre_page(url: String, html: String, state: State<'_, AppState>) -> Result<(), String> {
+    println!("RYM-SAVE-GENRE: Parsing genre page: {}", url);
+    let genre = rym_parse::parse_genre_page(&html, &url).map_err(|e| {
+        eprintln!("RYM-SAVE-GENRE: ❌ Failed to parse genre page: {}", e);
+        format!("Failed to parse genre page: {}", e)
+    })?;
+    let db = state.db.lock().unwrap();
+    db.save_genre(&genre).map_err(|e| format!("Failed to save genre: {}", e))
+}
+
+// IPC Command to seed the genre tree from the RYM genre index
+#[tauri::command]
+fn save_rym_genres_page(html: String, state: State<'_, AppState>) -> Result<usize, String> {
+    let genres = rym_parse::parse_genres_page(&html);
+    if genres.is_empty() {
+        return Err("No genres found on page".to_string());
+    }
+    let db = state.db.lock().unwrap();
+    db.save_genres(&genres).map_err(|e| format!("Failed to save genres: {}", e))?;
+    Ok(genres.len())
+}
+
+#[tauri::command]
+fn get_genre(name: String, state: State<'_, AppState>) -> Result<Option<Genre>, String> {
+    let db = state.db.lock().unwrap();
+    db.get_genre(&name).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_subgenres(name: String, state: State<'_, AppState>) -> Result<Vec<GenreLink>, String> {
+    let db = state.db.lock().unwrap();
+    db.get_subgenres(&name).map_err(|e| e.to_string())
+}
+
+// IPC Command for "everything cached under Jazz", subgenres included
+#[tauri::command]
+fn get_albums_in_genre(name: String, state: State<'_, AppState>) -> Result<Vec<GenreAlbum>, String> {
+    let db = state.db.lock().unwrap();
+    db.get_albums_in_genre(&name).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_albums_by_top_genre(state: State<'_, AppState>) -> Result<Vec<GenreGroup>, String> {
+    let db = state.db.lock().unwrap();
+    db.group_albums_by_top_genre().map_err(|e| e.to_string())
+}
+
+// IPC Command to get the score history of an album, oldest first
+#[tauri::command]
+fn get_rating_history(artist: String, album: String, state: State<'_, AppState>) -> Result<Vec<RatingSnapshot>, String> {
+    let db = state.db.lock().unwrap();
+    db.get_rating_history(&album, &artist).map_err(|e| e.to_string())
+}
+
+// IPC Command to list albums whose score moved by at least `min_delta` since the previous fetch
+#[tauri::command]
+fn get_rating_movers(min_delta: f32, state: State<'_, AppState>) -> Result<Vec<RatingMove>, String> {
+    let db = state.db.lock().unwrap();
+    db.get_rating_movers(min_delta).map_err(|e| e.to_string())
+}
+
+// IPC Command to report what the background refresher is doing
+#[tauri::command]
+fn get_refresh_status(state: State<'_, AppState>) -> Result<RefreshStatus, String> {
+    Ok(state.refresher.status(chrono::Utc::now().timestamp()))
+}
+
 // IPC Command to manually link a specific RYM page to an AM Artist/Album
 #[tauri::command]
 async fn set_manual_match(
@@ -244,31 +657,29 @@ async fn set_manual_match(
     println!("RYM-MANUAL-MATCH:   - Target (AM): {} - {}", target_artist, target_album);
     println!("RYM-MANUAL-MATCH:   - Source (RYM): {} - {} ({})", rating.artist_name, rating.album_name, rating.rym_url);
 
-    // Create a new rating object that uses the TARGET artist/album as the primary keys
-    // This ensures that when AM asks for "Artist A - Album X", it finds this entry.
-    // We keep the rest of the data (URL, rating, etc.) from the actual RYM page.
-    let mut linked_rating = rating.clone();
-    linked_rating.artist_name = target_artist.clone();
-    linked_rating.album_name = target_album.clone();
-    
-    // Save to local SQLite database
-    println!("RYM-MANUAL-MATCH: Saving link to local database...");
+    // Keep a single copy of the RYM data under its real names and point an alias at it,
+    // so refreshes of the canonical row are seen by the Apple Music album too.
+    println!("RYM-MANUAL-MATCH: Saving RYM data and alias to local database...");
     {
         let db = state.db.lock().unwrap();
-        db.save_rating(&linked_rating)
+        db.save_rating(&rating)
+            .and_then(|_| db.set_alias(&target_album, &target_artist, &rating.rym_url))
             .map_err(|e| {
                 eprintln!("RYM-MANUAL-MATCH: ❌ Failed to save: {}", e);
                 format!("Failed to save manual match: {}", e)
             })?;
     }
-    
-    // Broadcast update so AM UI reflects the new correct data immediately
+
+    // Broadcast under the AM names so the AM UI reflects the new correct data immediately
     println!("RYM-MANUAL-MATCH: Broadcasting update...");
-    let _ = app.emit("rym-rating-updated", linked_rating.clone());
+    let mut linked_rating = rating.clone();
+    linked_rating.artist_name = target_artist.clone();
+    linked_rating.album_name = target_album.clone();
+    let _ = app.emit("rym-rating-updated", linked_rating);
 
-    // Also save to Supabase (optional, but good for persistence)
+    // Also save the canonical rating to Supabase (optional, but good for persistence)
     if let Some(supabase) = &state.supabase {
-        let r = linked_rating.clone();
+        let r = rating.clone();
         let client = supabase.clone();
         tokio::spawn(async move {
             let _ = client.save_rating(&r).await;
@@ -280,6 +691,33 @@ async fn set_manual_match(
     Ok(())
 }
 
+// IPC Commands to manage manual matches
+#[tauri::command]
+fn list_aliases(state: State<'_, AppState>) -> Result<Vec<Alias>, String> {
+    let db = state.db.lock().unwrap();
+    db.list_aliases().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn edit_alias(id: i64, rym_url: String, state: State<'_, AppState>) -> Result<(), String> {
+    let db = state.db.lock().unwrap();
+    match db.edit_alias(id, &rym_url) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(format!("No alias with id {}", id)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+fn remove_alias(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    let db = state.db.lock().unwrap();
+    match db.remove_alias(id) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(format!("No alias with id {}", id)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
 #[tauri::command]
 async fn proxy_play(url: String, aria_label: String, app: tauri::AppHandle) -> Result<(), String> {
     println!("RYM-PROXY-PLAY: Request to play '{}' at URL: {}", aria_label, url);
@@ -333,499 +771,59 @@ fn start_drag(window: tauri::Window) {
     let _ = window.start_dragging();
 }
 
-// Helper function to navigate to RYM with rate limiting
-async fn navigate_to_rym_with_rate_limit(
-    app: &tauri::AppHandle,
-    url: String,
-) -> Result<(), String> {
-    const MIN_DELAY_MS: u128 = 2000; // 2 seconds minimum between RYM page loads
-    
-    println!("RYM-RATE-LIMIT: Request to navigate to: {}", url);
-    
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_millis();
-    
-    // Calculate wait time in a separate scope to drop the lock
-    let wait_time = {
-        let state = app.state::<AppState>();
-        let last_nav = state.last_rym_navigation.lock().unwrap();
-        
-        if let Some(last_time) = *last_nav {
-            let elapsed = now - last_time;
-            if elapsed < MIN_DELAY_MS {
-                Some(MIN_DELAY_MS - elapsed)
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    }; // Lock is dropped here
-    
-    // Sleep if needed (without holding any locks)
-    if let Some(delay) = wait_time {
-        println!("RYM-RATE-LIMIT: Delaying navigation by {}ms to respect rate limits", delay);
-        tokio::time::sleep(tokio::time::Duration::from_millis(delay as u64)).await;
-    }
-    
-    // Update the last navigation time
-    {
-        let state = app.state::<AppState>();
-        let mut last_nav = state.last_rym_navigation.lock().unwrap();
-        *last_nav = Some(now);
-    } // Lock is dropped here
-    
-    println!("RYM-RATE-LIMIT: Getting RYM window...");
-    let rym_window = app.get_webview_window("rym")
-        .ok_or_else(|| {
-            println!("RYM-RATE-LIMIT: ❌ ERROR - RYM window not found!");
-            "RYM window not found".to_string()
-        })?;
-    
-    println!("RYM-RATE-LIMIT: Navigating now...");
-    rym_window.navigate(url.parse().unwrap())
-        .map_err(|e| {
-            let err_msg = format!("Failed to navigate: {}", e);
-            println!("RYM-RATE-LIMIT: ❌ ERROR - {}", err_msg);
-            err_msg
-        })?;
-    
-    println!("RYM-RATE-LIMIT: ✓ Navigation successful");
-    Ok(())
-}
-
 #[tauri::command]
-fn show_music(app: tauri::AppHandle) {
-    if let Some(m) = app.get_webview_window("music") {
-        let _ = m.show();
-        let _ = m.set_focus();
-    }
-    if let Some(r) = app.get_webview_window("rym") {
-        let _ = r.hide();
-    }
+fn show_music(state: State<'_, AppState>, app: tauri::AppHandle) {
+    state.sync.show_music(&TauriBrowser::new(app));
 }
 
 #[tauri::command]
-async fn show_rym(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<(), String> {
-    println!("RYM-SHOW: ========================================");
-    println!("RYM-SHOW: show_rym command called");
-    
-    if let Some(r) = app.get_webview_window("rym") {
-        println!("RYM-SHOW: ✓ RYM window found");
-        
-        // Check if this is the first time showing RYM window
-        let needs_init = {
-            let initialized = state.rym_initialized.lock().unwrap();
-            !*initialized
-        }; 
-        
-        if needs_init {
-            println!("RYM-INIT: First time showing RYM window (or previous init failed), loading homepage...");
-            println!("RYM-SHOW: Calling navigate_to_rym_with_rate_limit...");
-            
-            match navigate_to_rym_with_rate_limit(&app, "https://rateyourmusic.com".to_string()).await {
-                Ok(_) => {
-                    println!("RYM-SHOW: ✓ Navigation completed successfully");
-                    let mut initialized = state.rym_initialized.lock().unwrap();
-                    *initialized = true;
-                    let mut rym_info = state.rym_current_info.lock().unwrap();
-                    *rym_info = Some("Home".to_string());
-                },
-                Err(e) => {
-                    println!("RYM-SHOW: ❌ Navigation failed: {}", e);
-                    // Do NOT set initialized to true, so it tries again next time
-                }
-            }
-        } else {
-             println!("RYM-SHOW: RYM already initialized, skipping navigation");
-        }
-        
-        println!("RYM-SHOW: Showing RYM window and hiding Music window...");
-        let _ = r.show();
-        let _ = r.set_focus();
-        println!("RYM-SHOW: ✓ RYM window shown and focused");
-    } else {
-        println!("RYM-SHOW: ❌ ERROR - RYM window not found!");
-        println!("RYM-SHOW: ========================================");
-        return Err("RYM window not found".to_string());
-    }
-    
-    if let Some(m) = app.get_webview_window("music") {
-        let _ = m.hide();
-        println!("RYM-SHOW: ✓ Music window hidden");
-    }
-    
-    println!("RYM-SHOW: ========================================");
-    Ok(())
+async fn show_rym(state: State<'_, AppState>, app: tauri::AppHandle) -> Result<(), String> {
+    state.sync.show_rym(&TauriBrowser::new(app)).await
 }
 
 #[tauri::command]
 fn set_pending_music_url(url: String, artist: Option<String>, album: Option<String>, state: State<'_, AppState>, app: tauri::AppHandle) {
     println!("RYM-APPLE-MUSIC: Received sync URL: {}", url);
-    
-    // CONSTRUCT KEY
-    let metadata_key = if let (Some(a), Some(b)) = (&artist, &album) {
-        Some(format!("{} - {}", a, b))
-    } else {
-        None
-    };
-
-    // --- HARD SYNC BLOCKER ---
-    // We are about to force a navigation to AM. We MUST ignore the sync request that AM will
-    // inevitably send back when it loads this album.
-    {
-        let mut prevent = state.prevent_next_am_sync.lock().unwrap();
-        *prevent = true;
-        println!("RYM-APPLE-MUSIC: 🛡️ ACTIVATED HARD SYNC BLOCKER (prevent_next_am_sync = true)");
-    }
-    
-    if let Some(key) = &metadata_key {
-        println!("RYM-APPLE-MUSIC: Sync request metadata: {}", key);
-        
-        let current_info = state.music_current_info.lock().unwrap();
-        if let Some(current) = &*current_info {
-            println!("RYM-APPLE-MUSIC: Current AM state: {}", current);
-            // Allow loose matching (contains) or exact
-            if current == key || current.contains(key) || key.contains(current) {
-                println!("RYM-APPLE-MUSIC: ❌ Ignoring sync request - AM is already on this Album (Metadata Match)");
-                // Even if we skip navigation, we should ensure RYM state is updated to this album
-                // since the user is clearly looking at it on RYM
-                let mut rym_info = state.rym_current_info.lock().unwrap();
-                *rym_info = Some(key.clone());
-                
-                // CRITICAL FIX: Also update the current music URL so subsequent 
-                // URL-based checks (like normalization) correctly identify we are here.
-                let mut current_url = state.current_music_url.lock().unwrap();
-                *current_url = Some(url.clone());
-                
-                return;
-            }
-        }
-    }
-    
-    // Fallback: Check if we are already on this URL (legacy/backup check)
-    {
-        let current_url_opt = state.current_music_url.lock().unwrap();
-        if let Some(current_url) = &*current_url_opt {
-             println!("RYM-APPLE-MUSIC: Checking against current URL: {}", current_url);
-             println!("RYM-APPLE-MUSIC: Incoming URL: {}", url);
-             
-             // Normalize check: handle geo.music vs music.apple
-            let normalized_current = current_url.replace("geo.music.apple.com", "music.apple.com");
-            let normalized_incoming = url.replace("geo.music.apple.com", "music.apple.com");
-            
-            if normalized_current == normalized_incoming {
-                 println!("RYM-APPLE-MUSIC: ❌ Ignoring sync request - URLs match after normalization");
-                 return;
-            }
-        }
-    }
-
-    let mut pending = state.pending_apple_music_url.lock().unwrap();
-    
-    // Only proceed if it's a new URL
-    if *pending == Some(url.clone()) { return; }
-    
-    *pending = Some(url.clone());
-    
-    // Update current music URL since we are navigating there
-    let mut current_url = state.current_music_url.lock().unwrap();
-    *current_url = Some(url.clone());
-
-    // CRITICAL FIX: Update RYM state tracker.
-    // Since this request comes FROM RYM (user clicked a link or is on a page),
-    // we know RYM is displaying this album. Updating this state prevents the
-    // "piong-pong" loop where AM loads -> notices album -> syncs back to RYM.
-    if let Some(key) = &metadata_key {
-        let mut rym_info = state.rym_current_info.lock().unwrap();
-        *rym_info = Some(key.clone());
-        println!("RYM-APPLE-MUSIC: ✓ Updated RYM state tracker to prevent loop: {}", key);
-    }
-    
-    // Navigate immediately so it's ready when the user switches
-    if let Some(m) = app.get_webview_window("music") {
-        if let Ok(parsed_url) = url.parse() {
-            // Tell the music window to ignore the NEXT sync detection to prevent loops
-            let _ = m.eval("localStorage.setItem('tauri_ignore_next_sync', 'true')");
-            let _ = m.navigate(parsed_url);
-            
-            // Trigger notification in Music window so user knows it's ready
-            let _ = m.eval("if (window.showSyncToast) window.showSyncToast('Synced from RYM')");
-            
-            // Also trigger in RYM window if that's where they are
-            if let Some(r) = app.get_webview_window("rym") {
-               let _ = r.eval("if (window.showSyncToast) window.showSyncToast('Synced to Apple Music')");
-            }
-        }
-    }
+    let shown = SyncAlbum::new(artist.as_deref().unwrap_or_default(), album.as_deref().unwrap_or_default());
+    state.sync.sync_to_music(&TauriBrowser::new(app), shown, Some(&url));
 }
 
 #[tauri::command]
 async fn sync_to_rym(artist: String, album: String, background: bool, force: bool, music_url: Option<String>, state: State<'_, AppState>, app: tauri::AppHandle) -> Result<(), String> {
-    use release_date::{parse_release_date_to_timestamp, compute_ttl_seconds, is_fresh};
+    let request = MusicSync { artist, album, background, force, music_url };
+    let (request_ref, state_ref) = (&request, &state);
+    let cached = move || async move {
+        lookup_cached_rating(&request_ref.artist, &request_ref.album, state_ref).await.map(|cached| cached.rating)
+    };
+    let alias_url = || state.db.lock().unwrap().get_alias_url(&request.album, &request.artist).ok().flatten();
+    state.sync.sync_to_rym(&TauriBrowser::new(app), &request, cached, alias_url).await
+}
 
-    let album_key = format!("{} - {}", artist, album);
-    
-    println!("RYM-SYNC: ========================================");
-    println!("RYM-SYNC: Starting sync to RYM for: {}", album_key);
-    println!("RYM-SYNC: Background mode: {}, Force: {}", background, force);
-    if let Some(url) = &music_url {
-        println!("RYM-SYNC: Source Music URL: {}", url);
-    }
-    
-    // 0. CHECK HARD SYNC BLOCKER
-    let skip_rym_navigation = {
-        let mut prevent = state.prevent_next_am_sync.lock().unwrap();
-        if *prevent {
-            println!("RYM-SYNC: 🛑 Hard Sync Blocker active - Will skip RYM navigation but allow UI update");
-            *prevent = false;
-            true
-        } else {
-            false
-        }
+// IPC Command sent by the RYM window when a search results page loads. If the search
+// was started for an album, navigates to the best-matching release.
+#[tauri::command]
+async fn resolve_rym_search(url: String, html: String, state: State<'_, AppState>) -> Result<Option<SearchResult>, String> {
+    let Some((artist, album)) = state.sync.take_pending_search(&url) else {
+        return Ok(None);
     };
-    
-    // LOOP PREVENTION: If RYM window already has this album, skip.
-    let mut match_found = false;
-    {
-        let rym_info = state.rym_current_info.lock().unwrap();
-        if let Some(current) = &*rym_info {
-            // Relaxed check: Exact match OR Contains OR Case-insensitive
-            match_found = current == &album_key 
-                || current.contains(&album_key) 
-                || album_key.contains(current)
-                || current.to_lowercase() == album_key.to_lowercase();
-                
-            if match_found && !force && background {
-                println!("RYM-SYNC: ❌ Skipping sync - RYM window already has {} (Match: {})", album_key, current);
-                println!("RYM-SYNC: ========================================");
-                return Ok(())
-            }
-        }
-    }
 
-    // Update Music state in protector
-    {
-        let mut music_info = state.music_current_info.lock().unwrap();
-        *music_info = Some(album_key.clone());
-        println!("RYM-SYNC: ✓ Updated music state tracker");
-        
-        // Update current music URL for ping-pong prevention
-        if let Some(url) = &music_url {
-            let mut current_url = state.current_music_url.lock().unwrap();
-            *current_url = Some(url.clone());
-        }
+    let results = rym_parse::parse_search_page(&html);
+    println!("RYM-SEARCH: {} results for {} - {}", results.len(), artist, album);
+    let ranked = rym_parse::rank_search_results(results, &artist, &album);
+    for c in ranked.iter().take(3) {
+        println!("RYM-SEARCH:   {:.3} {} - {} ({})", c.score, c.artist_name, c.album_name, c.item.release_type);
     }
 
-    if let Some(rym_window) = app.get_webview_window("rym") {
-        let now = chrono::Utc::now().timestamp();
-        let mut best_candidate: Option<AlbumRating> = None;
-
-        // STEP 1: Check local SQLite database first (Skip if force)
-        let mut _cached_rating = None;
-        if !force {
-            println!("RYM-SYNC: Step 1 - Checking local SQLite database...");
-            _cached_rating = {
-                let db = state.db.lock().unwrap();
-                db.get_rating(&album, &artist).ok().flatten()
-            };
-
-            if let Some(mut rating) = _cached_rating {
-                let release_ts = parse_release_date_to_timestamp(&rating.release_date);
-                let ttl = compute_ttl_seconds(now, release_ts);
-                
-                let tracks_json = rating.track_ratings.as_ref().map(|s| s.as_str()).unwrap_or("[]");
-                let has_tracks = tracks_json.len() > 5;
-                
-                println!("RYM-SYNC: Found local cache entry.");
-                println!("RYM-SYNC:   - Last fetched: {} ({} seconds ago)", rating.timestamp, now - rating.timestamp);
-                println!("RYM-SYNC:   - TTL: {} seconds", ttl);
-                println!("RYM-SYNC:   - Has tracks: {}", has_tracks);
-
-                if is_fresh(rating.timestamp, ttl, now) && has_tracks {
-                    println!("RYM-SYNC: ✓ LOCAL CACHE HIT (FRESH & COMPLETE)");
-                    rating.status = Some("fresh".to_string());
-                    
-                    println!("RYM-SYNC: Broadcasting fresh cached data...");
-                    let _ = app.emit("rym-rating-updated", rating);
-                    
-                    // If foreground, we still need to show the window
-                    if !background {
-                        if match_found {
-                             println!("RYM-SYNC: Match already loaded. Showing window.");
-                             let _ = rym_window.show();
-                             let _ = rym_window.set_focus();
-                             if let Some(m) = app.get_webview_window("music") { let _ = m.hide(); }
-                             return Ok(());
-                        }
-                    } else {
-                        return Ok(());
-                    }
-                    // If foreground but not match_found, continue to navigation
-                } else {
-                     let reason = if !has_tracks { "INCOMPLETE DATA" } else { "STALE" };
-                     println!("RYM-SYNC: ⚠️ LOCAL CACHE HIT ({})", reason);
-                     rating.status = Some("stale".to_string());
-                     best_candidate = Some(rating);
-                }
-            } else {
-                println!("RYM-SYNC: ❌ Local cache miss");
-            }
-                
-            // STEP 2: Check Supabase if not fresh local
-            println!("RYM-SYNC: Step 2 - Checking Supabase...");
-            if let Some(supabase) = &state.supabase {
-                if let Some(mut rating) = supabase.get_cached_rating(&artist, &album).await {
-                     let release_ts = parse_release_date_to_timestamp(&rating.release_date);
-                     let ttl = compute_ttl_seconds(now, release_ts);
-                     
-                     if is_fresh(rating.timestamp, ttl, now) {
-                         println!("RYM-SYNC: ✓ SUPABASE CACHE HIT (FRESH)");
-                         rating.status = Some("fresh".to_string());
-                         
-                         // Save to local
-                         println!("RYM-SYNC: Saving Supabase data to local cache...");
-                         let _ = state.db.lock().unwrap().save_rating(&rating);
-                         
-                         println!("RYM-SYNC: Broadcasting fresh cached data...");
-                         let _ = app.emit("rym-rating-updated", rating);
-                         
-                         if !background {
-                            if match_found {
-                                 let _ = rym_window.show();
-                                 let _ = rym_window.set_focus();
-                                 if let Some(m) = app.get_webview_window("music") { let _ = m.hide(); }
-                                 return Ok(());
-                            }
-                         } else {
-                            return Ok(());
-                         }
-                     } else {
-                         println!("RYM-SYNC: ⚠️ SUPABASE CACHE HIT (STALE)");
-                         let use_supabase = match &best_candidate {
-                             Some(local) => rating.timestamp > local.timestamp,
-                             None => true,
-                         };
-                         if use_supabase {
-                             rating.status = Some("stale".to_string());
-                             best_candidate = Some(rating);
-                         }
-                     }
-                }
-            }
-        }
-
-        // STEP 3: Fallback / Miss Handling
-        
-        // Always emit current best status so UI can show buttons (Refresh/Open)
-        if let Some(rating) = &best_candidate {
-             println!("RYM-SYNC: Broadcasting current candidate status: {:?}", rating.status);
-             let _ = app.emit("rym-rating-updated", rating.clone());
-        } else {
-             println!("RYM-SYNC: Broadcasting MISSING status to UI...");
-             let missing_rating = AlbumRating {
-                 album_name: album.clone(),
-                 artist_name: artist.clone(),
-                 rym_rating: 0.0,
-                 rating_count: 0,
-                 rym_url: "NO_MATCH".to_string(), 
-                 genres: "".to_string(),
-                 secondary_genres: None,
-                 descriptors: None,
-                 language: None,
-                 rank: None,
-                 track_ratings: None,
-                 reviews: None,
-                 release_date: "".to_string(),
-                 timestamp: now,
-                 status: Some("missing".to_string()),
-             };
-             let _ = app.emit("rym-rating-updated", missing_rating);
-        }
-
-        // DECIDE TO NAVIGATE
-        let is_rym_visible = rym_window.is_visible().unwrap_or(false);
-        
-        // Rule: If background (auto-sync) AND not on RYM tab AND NOT force -> NO NAVIGATION
-        // EXCEPTION: If data is MISSING (no best_candidate), we allow one background auto-nav to fetch it.
-        if background && !is_rym_visible && !force && best_candidate.is_some() {
-             println!("RYM-SYNC: Auto background sync & User NOT on RYM tab. Data exists but stale. Skipping navigation.");
-             return Ok(())
-        }
-        
-        // If we have a match already and not forcing, just show window and return
-        // BUT only if we aren't already on the RYM tab (if we are, we might want to refresh)
-        if !force && match_found && !background && !is_rym_visible {
-             println!("RYM-SYNC: Match already loaded. Showing window (No Nav).");
-             let _ = rym_window.show();
-             let _ = rym_window.set_focus();
-             if let Some(m) = app.get_webview_window("music") { let _ = m.hide(); }
-             return Ok(())
-        }
-        
-        if skip_rym_navigation {
-             println!("RYM-SYNC: 🛑 Skipping RYM navigation (Hard Sync Blocker)");
-             return Ok(())
-        }
-        
-        // DETERMINE TARGET URL
-        let target_url = if let Some(rating) = best_candidate {
-            rating.rym_url
-        } else {
-            let query = format!(r"\ site:rateyourmusic.com/release {} {}", artist, album);
-            let encoded_query = urlencoding::encode(&query);
-            format!("https://duckduckgo.com/?q={}", encoded_query)
-        };
-        
-        println!("RYM-SYNC: Navigating to refresh/find data: {}", target_url);
-        let _ = rym_window.eval("localStorage.setItem('tauri_ignore_next_sync', 'true')");
-        
-        let safe_key = album_key.replace("'", r"'\'");
-        let js_key = format!("localStorage.setItem('tauri_last_synced_album', '{}')", safe_key);
-        let _ = rym_window.eval(&js_key);
-        let _ = rym_window.eval("localStorage.setItem('tauri_sync_occurred', 'true')");
-        
-        if target_url.contains("duckduckgo") {
-            let _ = rym_window.eval("localStorage.setItem('tauri_ddg_sync_active', 'true')");
-        }
-        
-        if let Some(src_url) = &music_url {
-             let safe_url = src_url.replace("'", r"'\'");
-             let js = format!("localStorage.setItem('tauri_last_pending_url', '{}')", safe_url);
-             let _ = rym_window.eval(&js);
-        }
-        
-        navigate_to_rym_with_rate_limit(&app, target_url).await?;
-        println!("RYM-SYNC: ✓ Navigation initiated");
-
-        {
-            let mut init = state.rym_initialized.lock().unwrap();
-            *init = true;
-            let mut rym_info = state.rym_current_info.lock().unwrap();
-            *rym_info = Some(album_key.clone());
-        }
+    // Same threshold as cache lookups
+    let threshold = state.db.lock().unwrap().match_threshold();
+    let Some(best) = ranked.into_iter().next().filter(|c| c.score >= threshold) else {
+        println!("RYM-SEARCH: ❌ No result above {:.2}, leaving the results page open", threshold);
+        return Ok(None);
+    };
 
-        // Show toast notifications
-        if background {
-            let msg = format!("if (window.showSyncToast) window.showSyncToast(`Synced: {}`)", album.replace("`", r"\`"));
-            let _ = rym_window.eval(&msg);
-            if let Some(m) = app.get_webview_window("music") {
-                let _ = m.eval(&msg);
-            }
-        } else {
-            let _ = rym_window.show();
-            let _ = rym_window.set_focus();
-            if let Some(m) = app.get_webview_window("music") { let _ = m.hide(); }
-        }
-    } else {
-        println!("RYM-SYNC: ❌ ERROR - RYM window not found!");
-        return Err("RYM window not found".to_string());
-    }
-    
-    println!("RYM-SYNC: ========================================");
-    Ok(())
+    println!("RYM-SEARCH: ✓ Opening {}", best.item.rym_url);
+    state.sync.open_rym_release(&best.item.rym_url, SyncAlbum::new(&artist, &album), FetchPriority::Foreground).await?;
+    Ok(Some(best.item))
 }
 
 #[tauri::command]
@@ -875,19 +873,18 @@ fn get_active_window(app: &tauri::AppHandle) -> Option<tauri::WebviewWindow> {
 }
 
 async fn toggle_windows(app: &tauri::AppHandle) {
-    let music = app.get_webview_window("music");
-    let rym = app.get_webview_window("rym");
-
-    if let (Some(m), Some(_r)) = (music, rym) {
-        let is_music_visible = m.is_visible().unwrap_or(false);
-        if is_music_visible {
-            // Switching to RYM - use the command to handle initialization
-            let state = app.state::<AppState>();
-            let _ = show_rym(app.clone(), state).await;
-        } else {
-            // Switching to Music
-            show_music(app.clone());
-        }
+    let browser = TauriBrowser::new(app.clone());
+    if !browser.has_window(Window::Music) || !browser.has_window(Window::Rym) {
+        return;
+    }
+
+    let state = app.state::<AppState>();
+    if browser.is_visible(Window::Music) {
+        // Switching to RYM - handles initialization
+        let _ = state.sync.show_rym(&browser).await;
+    } else {
+        // Switching to Music
+        state.sync.show_music(&browser);
     }
 }
 
@@ -914,7 +911,9 @@ pub fn run() {
             let _ = std::fs::create_dir_all(&app_dir);
             let db_path = app_dir.join("rym_bridge.db");
             
-            let db = Database::new(db_path).expect("Failed to initialize database");
+            let mut db = Database::new(db_path).expect("Failed to initialize database");
+            db.set_match_threshold(matcher::threshold_from_env());
+            let ttl_settings = TtlSettings::load(&app_dir.join("ttl_settings.json"));
             
             let supabase = SupabaseClient::from_env();
             if supabase.is_some() {
@@ -923,16 +922,34 @@ pub fn run() {
                 println!("RYM-INIT: ⚠️ Supabase client failed to initialize (Missing keys?)");
             }
 
+            let fetcher = if rym_fetch::headless_from_env() {
+                match HttpFetcher::new() {
+                    Ok(fetcher) => {
+                        println!("RYM-INIT: ✓ Headless RYM fetching enabled");
+                        Some(fetcher)
+                    }
+                    Err(e) => {
+                        eprintln!("RYM-INIT: ❌ Headless RYM fetching disabled: {}", e);
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+
+            let db = Arc::new(Mutex::new(db));
+            let queue = Arc::new(FetchQueue::new(db.clone(), TokenBucket::new(RYM_BURST, RYM_PAGES_PER_SECOND)));
+            let ttl_policy: Arc<dyn TtlPolicy> = Arc::new(PopularityPolicy::new(ttl_settings));
+            let refresher = Refresher::new(db.clone(), queue.clone(), ttl_policy.clone(), refresher::daily_budget_from_env());
+
             app.manage(AppState {
-                db: Mutex::new(db),
+                db,
                 supabase,
-                pending_apple_music_url: Mutex::new(None),
-                music_current_info: Mutex::new(None),
-                rym_current_info: Mutex::new(None),
-                current_music_url: Mutex::new(None),
-                last_rym_navigation: Mutex::new(None),
-                rym_initialized: Mutex::new(false),
-                prevent_next_am_sync: Mutex::new(false),
+                sync: SyncController::new(queue),
+                lookups: LookupService::new(),
+                ttl_policy,
+                refresher,
+                fetcher,
             });
 
             let _app_handle_clone = app_handle.clone();
@@ -1219,49 +1236,15 @@ pub fn run() {
                             return null;
                         };
 
+                        // The media-link container of a release page; its links are parsed in Rust
                         window.extractRYMInfo = function() {
                             const container = document.querySelector('#media_link_button_container_top[data-medialink="true"]');
                             if (!container) return null;
-                            
-                            // Helper to ensure 'geo.' prefix
-                            const ensureGeo = (u) => {
-                                if (u && u.includes('music.apple.com') && !u.includes('geo.music.apple.com')) {
-                                    return u.replace('music.apple.com', 'geo.music.apple.com');
-                                }
-                                return u;
+                            return {
+                                artist: container.getAttribute('data-artists'),
+                                album: container.getAttribute('data-albums'),
+                                html: container.outerHTML
                             };
-
-                            // Try to find an actual link rendered by RYM first
-                            const renderedLink = container.querySelector('a[href*="apple.com"]');
-                            if (renderedLink && renderedLink.href) {
-                                return { 
-                                    artist: container.getAttribute('data-artists'), 
-                                    album: container.getAttribute('data-albums'), 
-                                    url: ensureGeo(renderedLink.href)
-                                };
-                            }
-
-                            const artists = container.getAttribute('data-artists');
-                            const albums = container.getAttribute('data-albums');
-                            const linksStr = container.getAttribute('data-links');
-                            if (!linksStr) return null;
-                            try {
-                                const links = JSON.parse(linksStr);
-                                if (links.applemusic) {
-                                    const amId = Object.keys(links.applemusic)[0];
-                                    const amData = links.applemusic[amId];
-                                    if (amData.url) return { artist: artists, album: albums, url: ensureGeo(amData.url) };
-                                    
-                                    const loc = amData.loc || 'us'; 
-                                    const albumSlug = amData.album || 'album';
-                                    
-                                    if (amId) {
-                                        const amUrl = `https://geo.music.apple.com/${loc}/album/${albumSlug}/${amId}`;
-                                        return { artist: artists, album: albums, url: amUrl };
-                                    }
-                                }
-                            } catch (e) {}
-                            return null;
                         };
 
                         setInterval(function() {
@@ -1269,24 +1252,38 @@ pub fn run() {
                                 const info = window.extractMusicInfo();
                                 if (info) {
                                     const albumKey = info.artist + ' - ' + info.album;
-                                    if (albumKey !== localStorage.getItem('tauri_last_synced_album')) {
-                                        localStorage.setItem('tauri_last_synced_album', albumKey);
+                                    if (albumKey !== window.tauriReportedAlbum) {
+                                        window.tauriReportedAlbum = albumKey;
                                         window.__TAURI__.core.invoke('sync_to_rym', { artist: info.artist, album: info.album, background: true, force: false, musicUrl: window.location.href });
                                     }
                                 }
                             }
 
                             if (IS_RYM) {
+                                if (window.location.pathname.startsWith('/search') && !window.tauriSearchResolved) {
+                                    window.tauriSearchResolved = true;
+                                    window.__TAURI__.core.invoke('resolve_rym_search', { url: window.location.href, html: document.documentElement.outerHTML });
+                                }
+                                if (window.location.pathname.startsWith('/song/') && !window.tauriSongSaved) {
+                                    window.tauriSongSaved = true;
+                                    window.__TAURI__.core.invoke('save_rym_song_page', { url: window.location.href, html: document.documentElement.outerHTML });
+                                }
+                                if (window.location.pathname.startsWith('/new-music') && !window.tauriNewMusicSaved) {
+                                    window.tauriNewMusicSaved = true;
+                                    window.__TAURI__.core.invoke('save_rym_new_music_page', { html: document.documentElement.outerHTML });
+                                }
+
                                 const info = window.extractRYMInfo();
-                                if (info && info.url) {
+                                if (info) {
                                     const albumKey = info.artist + ' - ' + info.album;
-                                    if (albumKey !== localStorage.getItem('tauri_last_synced_album')) {
-                                        localStorage.setItem('tauri_last_synced_album', albumKey);
+                                    if (albumKey !== window.tauriReportedAlbum) {
+                                        window.tauriReportedAlbum = albumKey;
                                         console.log('RYM-APPLE-MUSIC: Syncing RYM album to Apple Music:', albumKey);
-                                        window.__TAURI__.core.invoke('set_pending_music_url', { 
-                                            url: info.url, 
-                                            artist: info.artist, 
-                                            album: info.album 
+                                        window.__TAURI__.core.invoke('save_rym_media_links', {
+                                            url: window.location.href,
+                                            artist: info.artist,
+                                            album: info.album,
+                                            html: info.html
                                         });
                                     }
                                 }
@@ -1351,6 +1348,28 @@ pub fn run() {
             rym_window.open_devtools();
             player_window.open_devtools();
 
+            // Works through queued RYM pages for as long as the app runs. Background
+            // release pages are fetched over HTTP so they never take over the RYM window.
+            let queue_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                let state = queue_handle.state::<AppState>();
+                let offscreen = |page: &QueuedFetch| {
+                    let fetcher = state.fetcher.as_ref().filter(|_| page.url.contains("/release/"))?;
+                    Some(fetch_release_offscreen(fetcher, page.url.clone(), &queue_handle))
+                };
+                state.sync.run_rym_queue(&TauriBrowser::new(queue_handle.clone()), offscreen).await;
+            });
+
+            // Stale ratings get refreshed through the queue while the user is idle. Without
+            // the headless fetcher refreshes load in the RYM window, so they also wait for
+            // it to be out of sight.
+            let refresh_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                let state = refresh_handle.state::<AppState>();
+                let browser = TauriBrowser::new(refresh_handle.clone());
+                state.refresher.run(|| state.fetcher.is_none() && browser.is_visible(Window::Rym)).await;
+            });
+
             // Initial alignment
             if let (Ok(pos), Ok(size)) = (music_window.outer_position(), music_window.outer_size()) {
                 let _ = player_window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { 
@@ -1441,7 +1460,7 @@ pub fn run() {
             
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![get_rym_rating, save_rym_rating, show_music, show_rym, set_pending_music_url, sync_to_rym, go_back, go_forward, save_sample_html, start_drag, set_manual_match, proxy_play])
+        .invoke_handler(tauri::generate_handler![get_rym_rating, save_rym_rating, save_rym_page, save_rym_media_links, get_media_links, save_rym_artist_page, get_rym_artist, get_best_releases, save_rym_song_page, get_song_rating, save_rym_chart_page, get_chart_movers, import_rym_list_page, parse_rym_lists_page, list_collections, get_collection, remove_collection, step_collection, save_rym_new_music_page, get_new_releases, sync_new_release, save_rym_genre_page, save_rym_genres_page, get_genre, get_subgenres, get_albums_in_genre, get_albums_by_top_genre, show_music, show_rym, set_pending_music_url, sync_to_rym, resolve_rym_search, go_back, go_forward, save_sample_html, start_drag, set_manual_match, list_aliases, edit_alias, remove_alias, proxy_play, get_rating_history, get_rating_movers, get_refresh_status])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
\ No newline at end of file
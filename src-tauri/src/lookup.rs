@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+// Coalesces concurrent lookups for the same album. The first caller for a key runs
+// the fetch; everyone who arrives while it is in flight waits on the same cell and
+// gets a clone of its result. Once it completes the key is forgotten, so the next
+// request does a fresh lookup. If the running caller is cancelled, one of the
+// waiters takes over the fetch.
+pub struct LookupService<T> {
+    in_flight: Mutex<HashMap<String, Arc<OnceCell<T>>>>,
+}
+
+// "Björk", "Homogenic" and " BJÖRK", "homogenic " share one lookup. Bracketed text is
+// kept: "Vol. 1 (Deluxe)" and "Vol. 1 (Live)" are different releases.
+pub fn lookup_key(artist: &str, album: &str) -> String {
+    format!("{}|{}", artist.trim().to_lowercase(), album.trim().to_lowercase())
+}
+
+impl<T: Clone> LookupService<T> {
+    pub fn new() -> Self {
+        LookupService { in_flight: Mutex::new(HashMap::new()) }
+    }
+
+    pub async fn get_or_fetch<F, Fut>(&self, artist: &str, album: &str, fetch: F) -> T
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let key = lookup_key(artist, album);
+        let cell = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            match in_flight.get(&key) {
+                Some(cell) => {
+                    println!("RYM-LOOKUP: Joining in-flight lookup for {}", key);
+                    cell.clone()
+                }
+                None => {
+                    let cell = Arc::new(OnceCell::new());
+                    in_flight.insert(key.clone(), cell.clone());
+                    cell
+                }
+            }
+        };
+
+        let result = cell.get_or_init(fetch).await.clone();
+
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight.get(&key).is_some_and(|c| Arc::ptr_eq(c, &cell)) {
+            in_flight.remove(&key);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    async fn slow_fetch(calls: Arc<AtomicUsize>, value: String) -> String {
+        calls.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        value
+    }
+
+    #[tokio::test]
+    async fn concurrent_callers_share_one_fetch() {
+        let service = Arc::new(LookupService::new());
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..10)
+            .map(|i| {
+                let service = service.clone();
+                let calls = calls.clone();
+                tokio::spawn(async move {
+                    service
+                        .get_or_fetch("Björk", "Homogenic", || slow_fetch(calls, format!("result {}", i)))
+                        .await
+                })
+            })
+            .collect();
+
+        let mut results = Vec::new();
+        for h in handles {
+            results.push(h.await.unwrap());
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(results.iter().all(|r| r == &results[0]), "{:?}", results);
+        assert!(service.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn case_and_whitespace_coalesce() {
+        let service = LookupService::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let (a, b) = tokio::join!(
+            service.get_or_fetch("Björk", "Homogenic", || slow_fetch(calls.clone(), "first".to_string())),
+            service.get_or_fetch(" BJÖRK", "homogenic ", || slow_fetch(calls.clone(), "second".to_string())),
+        );
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(a, "first");
+        assert_eq!(b, "first");
+    }
+
+    #[tokio::test]
+    async fn different_albums_fetch_independently() {
+        let service = LookupService::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let (a, b) = tokio::join!(
+            service.get_or_fetch("Radiohead", "Kid A", || slow_fetch(calls.clone(), "kid a".to_string())),
+            service.get_or_fetch("Radiohead", "Amnesiac", || slow_fetch(calls.clone(), "amnesiac".to_string())),
+        );
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!((a.as_str(), b.as_str()), ("kid a", "amnesiac"));
+    }
+
+    #[tokio::test]
+    async fn bracketed_editions_fetch_independently() {
+        let service = LookupService::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let (a, b) = tokio::join!(
+            service.get_or_fetch("Sufjan Stevens", "Vol. 1 (Deluxe)", || slow_fetch(calls.clone(), "deluxe".to_string())),
+            service.get_or_fetch("Sufjan Stevens", "Vol. 1 (Live)", || slow_fetch(calls.clone(), "live".to_string())),
+        );
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!((a.as_str(), b.as_str()), ("deluxe", "live"));
+    }
+
+    #[tokio::test]
+    async fn completed_lookups_are_not_cached() {
+        let service = LookupService::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        service.get_or_fetch("Radiohead", "Kid A", || slow_fetch(calls.clone(), "old".to_string())).await;
+        let second = service.get_or_fetch("Radiohead", "Kid A", || slow_fetch(calls.clone(), "new".to_string())).await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(second, "new");
+    }
+
+    #[tokio::test]
+    async fn waiter_takes_over_when_first_caller_is_cancelled() {
+        let service = Arc::new(LookupService::new());
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let first = {
+            let service = service.clone();
+            let calls = calls.clone();
+            tokio::spawn(async move { service.get_or_fetch("Radiohead", "Kid A", || slow_fetch(calls, "first".to_string())).await })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let second = {
+            let service = service.clone();
+            let calls = calls.clone();
+            tokio::spawn(async move { service.get_or_fetch("Radiohead", "Kid A", || slow_fetch(calls, "second".to_string())).await })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        first.abort();
+
+        assert_eq!(second.await.unwrap(), "second");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(service.in_flight.lock().unwrap().is_empty());
+    }
+}
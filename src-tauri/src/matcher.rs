@@ -0,0 +1,306 @@
+use std::collections::BTreeSet;
+use unicode_normalization::UnicodeNormalization;
+
+// Ranks cached RYM releases against an Apple Music album/artist pair.
+// Both sides are canonicalized (diacritics folded, "&" -> "and", leading articles,
+// "feat." credits and remaster/deluxe suffixes dropped) and then compared with a
+// blend of token-set and token-sort ratios, so word order and extra edition words
+// don't sink an otherwise obvious match.
+
+pub const DEFAULT_THRESHOLD: f32 = 0.85;
+
+const ARTICLES: &[&str] = &["the", "a", "an"];
+const FEATURING: &[&str] = &["feat", "ft", "featuring"];
+const EDITION_WORDS: &[&str] = &[
+    "remaster", "remastered", "deluxe", "edition", "anniversary", "expanded", "reissue", "mono", "stereo", "bonus",
+];
+const EDITION_PREFIXES: &[&str] = &["super", "special", "legacy", "collectors"];
+const ROMAN_NUMERALS: &[&str] = &["ii", "iii", "iv", "v", "vi", "vii", "viii", "ix", "x"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate<T> {
+    pub item: T,
+    pub album_name: String,
+    pub artist_name: String,
+    pub score: f32,
+}
+
+// Threshold for accepting the best candidate, overridable with RYM_MATCH_THRESHOLD
+pub fn threshold_from_env() -> f32 {
+    std::env::var("RYM_MATCH_THRESHOLD")
+        .ok()
+        .and_then(|v| v.trim().parse::<f32>().ok())
+        .filter(|t| (0.0..=1.0).contains(t))
+        .unwrap_or(DEFAULT_THRESHOLD)
+}
+
+// NFKD, drop the accents in the Combining Diacritical Marks block, then recompose.
+// Compatibility forms fold to their plain equivalents ("ＢＡＢＹＭＥＴＡＬ" -> "BABYMETAL"),
+// while kana voicing marks and Hangul syllables survive the round trip.
+pub fn fold_diacritics(s: &str) -> String {
+    s.nfkd().filter(|c| !('\u{0300}'..='\u{036F}').contains(c)).nfc().collect()
+}
+
+// RYM appends a romanization to non-Latin names: "سماع [Sama'a (Audition)]", "아이유 [IU]".
+// Latin names keep their trailing brackets as edition info, so they get None.
+pub fn romanization(s: &str) -> Option<&str> {
+    let trimmed = s.trim_end();
+    let inner_end = trimmed.strip_suffix(']')?.len();
+
+    let mut depth = 0;
+    for (i, c) in trimmed.char_indices().rev() {
+        match c {
+            ']' => depth += 1,
+            '[' => {
+                depth -= 1;
+                if depth == 0 {
+                    let native = &trimmed[..i];
+                    let romanized = trimmed[i + 1..inner_end].trim();
+                    return (is_non_latin(native) && !romanized.is_empty()).then_some(romanized);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn is_non_latin(s: &str) -> bool {
+    fold_diacritics(s).chars().any(|c| c.is_alphabetic() && c > '\u{024F}')
+}
+
+fn variants(s: &str) -> Vec<&str> {
+    let mut v = vec![s];
+    v.extend(romanization(s));
+    v
+}
+
+pub fn canonical_tokens(s: &str) -> Vec<String> {
+    let mut cleaned = String::new();
+    let mut depth = 0;
+    for c in fold_diacritics(s).to_lowercase().chars() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = (depth - 1).max(0),
+            _ if depth > 0 => {}
+            '\'' | '’' | '`' => {}
+            '&' => cleaned.push_str(" and "),
+            c if c.is_alphanumeric() => cleaned.push(c),
+            _ => cleaned.push(' '),
+        }
+    }
+
+    let mut tokens: Vec<String> = cleaned.split_whitespace().map(str::to_string).collect();
+
+    // "Artist feat. Guest" -> "Artist"
+    if let Some(pos) = tokens.iter().skip(1).position(|t| FEATURING.contains(&t.as_str())) {
+        tokens.truncate(pos + 1);
+    }
+
+    // "Rumours - Super Deluxe", "Dark Side of the Moon - 50th Anniversary"
+    if let Some(pos) = tokens.iter().skip(1).position(|t| EDITION_WORDS.contains(&t.as_str())) {
+        let mut cut = pos + 1;
+        while cut > 1 && is_edition_prefix(&tokens[cut - 1]) {
+            cut -= 1;
+        }
+        tokens.truncate(cut);
+    }
+
+    if tokens.len() > 1 && ARTICLES.contains(&tokens[0].as_str()) {
+        tokens.remove(0);
+    }
+
+    tokens
+}
+
+fn is_edition_prefix(token: &str) -> bool {
+    let is_year = token.len() == 4 && token.chars().all(|c| c.is_ascii_digit());
+    let is_ordinal = ["st", "nd", "rd", "th"].iter().any(|suffix| {
+        token
+            .strip_suffix(suffix)
+            .is_some_and(|digits| !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()))
+    });
+    is_year || is_ordinal || EDITION_PREFIXES.contains(&token)
+}
+
+fn is_numeral(token: &str) -> bool {
+    token.chars().all(|c| c.is_ascii_digit()) || ROMAN_NUMERALS.contains(&token)
+}
+
+fn numerals<'a>(tokens: &BTreeSet<&'a str>) -> BTreeSet<&'a str> {
+    tokens.iter().filter(|t| is_numeral(t)).copied().collect()
+}
+
+// Normalized Levenshtein similarity in 0.0..=1.0
+fn ratio(a: &str, b: &str) -> f32 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    1.0 - prev[b.len()] as f32 / longest as f32
+}
+
+fn token_sort_ratio(a: &BTreeSet<&str>, b: &BTreeSet<&str>) -> f32 {
+    ratio(&join(a.iter()), &join(b.iter()))
+}
+
+fn token_set_ratio(a: &BTreeSet<&str>, b: &BTreeSet<&str>) -> f32 {
+    let common = join(a.intersection(b));
+    let with_a = join_parts(&common, &join(a.difference(b)));
+    let with_b = join_parts(&common, &join(b.difference(a)));
+    ratio(&common, &with_a).max(ratio(&common, &with_b)).max(ratio(&with_a, &with_b))
+}
+
+fn join<'a>(tokens: impl Iterator<Item = &'a &'a str>) -> String {
+    tokens.copied().collect::<Vec<_>>().join(" ")
+}
+
+fn join_parts(a: &str, b: &str) -> String {
+    format!("{} {}", a, b).trim().to_string()
+}
+
+// Similarity of two titles (or two artist names) in 0.0..=1.0, taking the best of
+// the native and romanized forms on each side
+pub fn similarity(a: &str, b: &str) -> f32 {
+    let mut best: f32 = 0.0;
+    for a in variants(a) {
+        for b in variants(b) {
+            best = best.max(variant_similarity(a, b));
+        }
+    }
+    best
+}
+
+fn variant_similarity(a: &str, b: &str) -> f32 {
+    let ta = canonical_tokens(a);
+    let tb = canonical_tokens(b);
+
+    // Titles made entirely of symbols ("★", "÷") only match themselves
+    if ta.is_empty() || tb.is_empty() {
+        let (a, b) = (a.trim().to_lowercase(), b.trim().to_lowercase());
+        return if !a.is_empty() && a == b { 1.0 } else { 0.0 };
+    }
+
+    let sa: BTreeSet<&str> = ta.iter().map(String::as_str).collect();
+    let sb: BTreeSet<&str> = tb.iter().map(String::as_str).collect();
+
+    // "Led Zeppelin II" and "Led Zeppelin III" are different records
+    if numerals(&sa) != numerals(&sb) {
+        return 0.0;
+    }
+
+    // Token-set alone scores any subset as a perfect match ("Kid A" vs "Kid A Mnesia"),
+    // so average it with the order-insensitive full comparison
+    (token_set_ratio(&sa, &sb) + token_sort_ratio(&sa, &sb)) / 2.0
+}
+
+pub fn score(album: &str, artist: &str, candidate_album: &str, candidate_artist: &str) -> f32 {
+    // Geometric mean: a perfect title by the wrong artist should not pass
+    (similarity(album, candidate_album) * similarity(artist, candidate_artist)).sqrt()
+}
+
+// Scores every candidate and returns them best-first
+pub fn rank<T>(album: &str, artist: &str, candidates: impl IntoIterator<Item = (T, String, String)>) -> Vec<Candidate<T>> {
+    let mut ranked: Vec<Candidate<T>> = candidates
+        .into_iter()
+        .map(|(item, album_name, artist_name)| {
+            let score = score(album, artist, &album_name, &artist_name);
+            Candidate { item, album_name, artist_name, score }
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (Apple Music album, Apple Music artist, RYM album, RYM artist, should match)
+    const CORPUS: &[(&str, &str, &str, &str, bool)] = &[
+        ("Abbey Road (Remastered)", "The Beatles", "Abbey Road", "The Beatles", true),
+        ("Abbey Road", "Beatles", "Abbey Road", "The Beatles", true),
+        ("Sgt. Pepper's Lonely Hearts Club Band (Remastered 2009)", "The Beatles", "Sgt. Pepper's Lonely Hearts Club Band", "The Beatles", true),
+        ("Simon & Garfunkel's Greatest Hits", "Simon & Garfunkel", "Simon and Garfunkel's Greatest Hits", "Simon and Garfunkel", true),
+        ("Rumours - Super Deluxe", "Fleetwood Mac", "Rumours", "Fleetwood Mac", true),
+        ("The Dark Side of the Moon - 50th Anniversary", "Pink Floyd", "The Dark Side of the Moon", "Pink Floyd", true),
+        ("Exile On Main St. (2010 Remastered)", "The Rolling Stones", "Exile on Main St.", "The Rolling Stones", true),
+        ("Purple Rain Deluxe - Expanded Edition", "Prince & The Revolution", "Purple Rain", "Prince and The Revolution", true),
+        ("Pet Sounds (Mono & Stereo)", "The Beach Boys", "Pet Sounds", "The Beach Boys", true),
+        ("Homogenic", "Björk", "Homogenic", "Bjork", true),
+        ("El Mal Querer", "ROSALÍA", "El mal querer", "Rosalía", true),
+        ("Ágætis Byrjun", "Sigur Rós", "Ágætis byrjun", "Sigur Ros", true),
+        ("Mr. Morale & The Big Steppers", "Kendrick Lamar", "Mr. Morale & the Big Steppers", "Kendrick Lamar", true),
+        ("Watch the Throne (Deluxe)", "JAY-Z & Kanye West", "Watch the Throne", "JAY-Z and Kanye West", true),
+        ("Empire State of Mind", "JAY-Z feat. Alicia Keys", "Empire State of Mind", "JAY-Z", true),
+        ("Stankonia", "OutKast", "Stankonia", "Outkast", true),
+        ("Astral Weeks (Expanded & Remastered)", "Van Morrison", "Astral Weeks", "Van Morrison", true),
+        ("Deluxe", "Harmonia", "Deluxe", "Harmonia", true),
+        ("星間性交", "t e l e p a t h テレパシー能力者", "星間性交", "t e l e p a t h テレパシー能力者", true),
+        ("宇多田 ベスト Deluxe", "宇多田ヒカル", "宇多田 ベスト", "宇多田ヒカル", true),
+        ("Best 星間 Deluxe Edition", "t e l e p a t h テレパシー能力者", "Best 星間", "t e l e p a t h テレパシー能力者", true),
+        ("Sama'a", "Ahmed", "سماع [Sama'a (Audition)]", "أحمد [Ahmed]", true),
+        ("سماع", "أحمد", "سماع [Sama'a (Audition)]", "أحمد [Ahmed]", true),
+        ("Palette", "IU", "Palette", "아이유 [IU]", true),
+        ("Kūdō desu", "Yura Yura Teikoku", "空洞です [Kūdō desu]", "ゆらゆら帝国 [Yura Yura Teikoku]", true),
+        ("METAL RESISTANCE", "ＢＡＢＹＭＥＴＡＬ", "Metal Resistance", "BABYMETAL", true),
+        ("Abbey Road", "The Beatles", "Let It Be", "The Beatles", false),
+        ("空洞です", "ゆらゆら帝国", "ゆらゆら帝国 III", "ゆらゆら帝国", false),
+        ("Kid A", "Radiohead", "Kid A Mnesia", "Radiohead", false),
+        ("Led Zeppelin II", "Led Zeppelin", "Led Zeppelin III", "Led Zeppelin", false),
+        ("Blue", "Joni Mitchell", "Blue", "Weezer", false),
+        ("Ten", "Pearl Jam", "Vs.", "Pearl Jam", false),
+        ("Abbey Road", "The Beatles", "Abbey Road", "George Benson", false),
+    ];
+
+    #[test]
+    fn corpus_matches_at_default_threshold() {
+        for (am_album, am_artist, rym_album, rym_artist, expected) in CORPUS {
+            let s = score(am_album, am_artist, rym_album, rym_artist);
+            assert_eq!(
+                s >= DEFAULT_THRESHOLD,
+                *expected,
+                "\"{}\" by \"{}\" vs \"{}\" by \"{}\" scored {:.3}",
+                am_album, am_artist, rym_album, rym_artist, s
+            );
+        }
+    }
+
+    #[test]
+    fn canonicalizes_titles() {
+        assert_eq!(canonical_tokens("The Dark Side of the Moon - 50th Anniversary"), ["dark", "side", "of", "the", "moon"]);
+        assert_eq!(canonical_tokens("Prince & The Revolution"), ["prince", "and", "the", "revolution"]);
+        assert_eq!(canonical_tokens("JAY-Z feat. Alicia Keys"), ["jay", "z"]);
+        assert_eq!(canonical_tokens("Sgt. Pepper's (Remastered)"), ["sgt", "peppers"]);
+        assert_eq!(canonical_tokens("Björk"), ["bjork"]);
+        assert_eq!(canonical_tokens("The The"), ["the"]);
+        assert_eq!(canonical_tokens("Best 星間 Deluxe"), ["best", "星間"]);
+        assert_eq!(canonical_tokens("宇多田 ベスト Deluxe"), ["宇多田", "ベスト"]);
+    }
+
+    #[test]
+    fn ranks_best_candidate_first() {
+        let cached = vec![
+            (1, "Let It Be".to_string(), "The Beatles".to_string()),
+            (2, "Abbey Road".to_string(), "The Beatles".to_string()),
+            (3, "Abbey Road".to_string(), "George Benson".to_string()),
+        ];
+        let ranked = rank("Abbey Road (2019 Mix)", "Beatles", cached);
+        assert_eq!(ranked.iter().map(|c| c.item).collect::<Vec<_>>(), [2, 3, 1]);
+        assert!(ranked[0].score >= DEFAULT_THRESHOLD);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::database::MediaLink;
+use crate::rym_parse::sel;
+use scraper::Html;
+use serde_json::{Map, Value};
+
+// Streaming links from the media-link container on RYM release pages. Its `data-links`
+// attribute maps each service to link ids with a few fields per link:
+//   {"applemusic":{"1443500666":{"default":true,"album":"like-cats-and-dogs","loc":"us"}},
+//    "spotify":{"582IZKK4KyVX9Einme5K6d":{"default":true,"type":"album"}}}
+// and every service builds its URL from those differently.
+
+// RYM's keys for the streaming services it links, with their display names
+pub const SERVICES: &[(&str, &str)] = &[
+    ("applemusic", "Apple Music"),
+    ("bandcamp", "Bandcamp"),
+    ("deezer", "Deezer"),
+    ("qobuz", "Qobuz"),
+    ("soundcloud", "SoundCloud"),
+    ("spotify", "Spotify"),
+    ("tidal", "Tidal"),
+    ("youtube", "YouTube"),
+];
+
+// Links from a release page, or just its media-link container. RYM sometimes renders
+// the Apple Music button already; its link wins over the one built from the JSON.
+pub fn parse_media_links(html: &str) -> Vec<MediaLink> {
+    let doc = Html::parse_document(html);
+    let Some(container) = doc
+        .select(&sel("#media_link_button_container_top[data-medialink=\"true\"]"))
+        .next()
+    else {
+        return Vec::new();
+    };
+
+    let rendered = container
+        .select(&sel("a[href*=\"apple.com\"]"))
+        .next()
+        .and_then(|a| a.value().attr("href"))
+        .map(|href| MediaLink { service: "applemusic".to_string(), url: geo_url(href), default: true });
+
+    let mut links: Vec<MediaLink> = parse_links_json(container.value().attr("data-links").unwrap_or_default())
+        .into_iter()
+        .filter(|l| rendered.is_none() || l.service != "applemusic")
+        .collect();
+    if let Some(link) = rendered {
+        links.insert(0, link);
+    }
+    links
+}
+
+// The `data-links` JSON on its own: RYM's default links first, then by service in
+// SERVICES order. Unknown services and links that can't be turned into a URL are skipped.
+pub fn parse_links_json(json: &str) -> Vec<MediaLink> {
+    let Ok(Value::Object(services)) = serde_json::from_str::<Value>(json) else {
+        return Vec::new();
+    };
+
+    let mut links = Vec::new();
+    for (service, ids) in &services {
+        let Value::Object(ids) = ids else { continue };
+        if !SERVICES.iter().any(|(key, _)| key == service) {
+            continue;
+        }
+        for (id, fields) in ids {
+            let empty = Map::new();
+            let fields = fields.as_object().unwrap_or(&empty);
+            if let Some(url) = link_url(service, id, fields) {
+                links.push(MediaLink {
+                    service: service.clone(),
+                    url,
+                    default: fields.get("default").and_then(Value::as_bool).unwrap_or(false),
+                });
+            }
+        }
+    }
+    links.sort_by_key(|l| (!l.default, SERVICES.iter().position(|(key, _)| *key == l.service)));
+    links
+}
+
+fn link_url(service: &str, id: &str, fields: &Map<String, Value>) -> Option<String> {
+    let field = |name: &str| fields.get(name).and_then(Value::as_str).filter(|s| !s.is_empty());
+
+    match service {
+        "applemusic" => Some(match field("url") {
+            Some(url) => geo_url(url),
+            None => format!(
+                "https://geo.music.apple.com/{}/album/{}/{}",
+                field("loc").unwrap_or("us"),
+                field("album").unwrap_or("album"),
+                id
+            ),
+        }),
+        "spotify" => Some(format!("https://open.spotify.com/{}/{}", field("type").unwrap_or("album"), id)),
+        "youtube" => Some(format!("https://www.youtube.com/watch?v={}", id)),
+        "deezer" if field("url").is_none() => Some(format!("https://www.deezer.com/{}/{}", field("type").unwrap_or("album"), id)),
+        "tidal" if field("url").is_none() => Some(format!("https://tidal.com/browse/{}/{}", field("type").unwrap_or("album"), id)),
+        // Bandcamp, SoundCloud and Qobuz store the page itself, without the scheme
+        _ => field("url").map(with_scheme),
+    }
+}
+
+fn with_scheme(url: &str) -> String {
+    if url.starts_with("http") {
+        url.to_string()
+    } else {
+        format!("https://{}", url.trim_start_matches("//"))
+    }
+}
+
+// geo.music.apple.com redirects to the user's own storefront
+fn geo_url(url: &str) -> String {
+    let url = with_scheme(url);
+    if url.contains("music.apple.com") && !url.contains("geo.music.apple.com") {
+        url.replacen("music.apple.com", "geo.music.apple.com", 1)
+    } else {
+        url
+    }
+}
+
+// The link to open in the Apple Music window, preferring RYM's default
+pub fn apple_music_url(links: &[MediaLink]) -> Option<String> {
+    let apple = || links.iter().filter(|l| l.service == "applemusic");
+    apple().find(|l| l.default).or_else(|| apple().next()).map(|l| l.url.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(service: &str, url: &str) -> MediaLink {
+        MediaLink { service: service.to_string(), url: url.to_string(), default: true }
+    }
+
+    #[test]
+    fn parses_links_from_release_fixtures() {
+        let comp = parse_media_links(include_str!("../../sample_pages/release_comp_sample.html"));
+        assert_eq!(comp, vec![
+            link("applemusic", "https://geo.music.apple.com/us/album/like-cats-and-dogs/1443500666"),
+            link("soundcloud", "https://soundcloud.com/catherine-wheel-official/sets/like-cats-and-dogs"),
+            link("spotify", "https://open.spotify.com/album/582IZKK4KyVX9Einme5K6d"),
+        ]);
+
+        let ep = parse_media_links(include_str!("../../sample_pages/release_ep_sample.html"));
+        assert_eq!(ep, vec![
+            link("applemusic", "https://geo.music.apple.com/ru/album/what-once-was-liber-iii-ep/728629872"),
+            link("bandcamp", "https://blutausnord.bandcamp.com/album/what-once-was-liber-iii"),
+            link("spotify", "https://open.spotify.com/album/14EKl5yDV8XTkFCi9E1V6y"),
+        ]);
+
+        let single = parse_media_links(include_str!("../../sample_pages/release_single_sample.html"));
+        assert_eq!(single, vec![link("youtube", "https://www.youtube.com/watch?v=kJTi-xqngls")]);
+        assert_eq!(apple_music_url(&single), None);
+
+        let video = parse_media_links(include_str!("../../sample_pages/release_video_sample.html"));
+        assert_eq!(video, vec![link("youtube", "https://www.youtube.com/watch?v=aCkHuU-5GHw")]);
+
+        // A second, region-limited Bandcamp link that isn't RYM's default comes last
+        let album = parse_media_links(include_str!("../../sample_pages/release_album_sample.html"));
+        assert_eq!(album, vec![
+            link("bandcamp", "https://telepathtelepath.bandcamp.com/album/--40"),
+            link("soundcloud", "https://soundcloud.com/memes-season/sets/t-e-l-e-p-a-t-h"),
+            link("youtube", "https://www.youtube.com/watch?v=GENZTmioPyU"),
+            MediaLink {
+                service: "bandcamp".to_string(),
+                url: "https://geometriclullaby.bandcamp.com/album/--38".to_string(),
+                default: false,
+            },
+        ]);
+
+        // Song pages have a container of their own
+        assert!(parse_media_links(include_str!("../../sample_pages/song_sample.html")).is_empty());
+    }
+
+    #[test]
+    fn prefers_rendered_and_default_apple_music_links() {
+        let html = r#"
+            <div id="media_link_button_container_top" data-medialink="true"
+                 data-links='{"applemusic":{"1":{"album":"a","loc":"gb"},"2":{"default":true,"url":"music.apple.com/jp/album/b/2"}}}'></div>"#;
+        let links = parse_media_links(html);
+        assert_eq!(links.len(), 2);
+        assert_eq!(apple_music_url(&links).as_deref(), Some("https://geo.music.apple.com/jp/album/b/2"));
+
+        let rendered = r#"
+            <div id="media_link_button_container_top" data-medialink="true"
+                 data-links='{"applemusic":{"1":{"default":true,"album":"a","loc":"gb"}}}'>
+              <a href="https://music.apple.com/us/album/rendered/3">Apple Music</a>
+            </div>"#;
+        assert_eq!(parse_media_links(rendered), vec![link("applemusic", "https://geo.music.apple.com/us/album/rendered/3")]);
+    }
+
+    #[test]
+    fn covers_every_service_rym_supports() {
+        let html = include_str!("../../sample_pages/submit_media_link_sample.html");
+        let doc = Html::parse_document(html);
+        // "The following streaming services are currently supported: <ul>..."
+        let supported: Vec<String> = doc
+            .select(&sel(".large-4.columns ul li a"))
+            .map(|a| a.text().collect::<String>().trim().to_string())
+            .collect();
+
+        assert_eq!(supported.len(), SERVICES.len());
+        for name in &supported {
+            assert!(SERVICES.iter().any(|(_, n)| n == name), "{} is not handled", name);
+        }
+    }
+
+    #[test]
+    fn orders_default_links_first_then_by_service() {
+        let json = r#"{"youtube":{"a":{"default":true}},"applemusic":{"1":{"album":"x"}},"bandcamp":{"2":{"default":true,"url":"x.bandcamp.com/album/y"}}}"#;
+        let order: Vec<_> = parse_links_json(json).into_iter().map(|l| (l.service, l.default)).collect();
+        assert_eq!(order, [
+            ("bandcamp".to_string(), true),
+            ("youtube".to_string(), true),
+            ("applemusic".to_string(), false),
+        ]);
+    }
+
+    #[test]
+    fn ignores_empty_or_broken_json() {
+        assert!(parse_links_json("{}").is_empty());
+        assert!(parse_links_json("not json").is_empty());
+        // Bandcamp links without a page can't be opened
+        assert!(parse_links_json(r#"{"bandcamp":{"1":{"default":true}}}"#).is_empty());
+        assert!(parse_links_json(r#"{"napster":{"1":{"default":true,"url":"napster.com/x"}}}"#).is_empty());
+    }
+}
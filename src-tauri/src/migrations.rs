@@ -0,0 +1,689 @@
+use crate::database::{index_album_keys, normalize_key};
+use crate::release_date::ReleaseDate;
+use rusqlite::{Connection, Result, Transaction};
+
+// Schema migrations for the local SQLite cache.
+// Each migration runs exactly once, inside its own transaction, and bumps
+// `PRAGMA user_version` to its version on success. Append new migrations to
+// the end of MIGRATIONS; never edit or reorder one that has shipped.
+struct Migration {
+    version: i64,
+    name: &'static str,
+    up: fn(&Transaction) -> Result<()>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, name: "album_ratings baseline", up: album_ratings_baseline },
+    Migration { version: 2, name: "track_ratings table", up: track_ratings_table },
+    Migration { version: 3, name: "reviews table", up: reviews_table },
+    Migration { version: 4, name: "rating_snapshots table", up: rating_snapshots_table },
+    Migration { version: 5, name: "normalized lookup keys", up: normalized_keys },
+    Migration { version: 6, name: "manual match aliases", up: aliases_table },
+    Migration { version: 7, name: "native and romanized lookup keys", up: album_keys_table },
+    Migration { version: 8, name: "artists and discographies", up: artists_tables },
+    Migration { version: 9, name: "chart snapshots", up: chart_snapshots_tables },
+    Migration { version: 10, name: "collections", up: collections_tables },
+    Migration { version: 11, name: "genre tree", up: genres_tables },
+    Migration { version: 12, name: "song ratings", up: song_ratings_tables },
+    Migration { version: 13, name: "upcoming releases", up: upcoming_releases_table },
+    Migration { version: 14, name: "media links", up: media_links_table },
+    Migration { version: 15, name: "fetch queue", up: fetch_queue_table },
+    Migration { version: 16, name: "refresh budget", up: refresh_budget_table },
+    Migration { version: 17, name: "artist key index", up: artist_key_index },
+    Migration { version: 18, name: "upcoming release end dates", up: upcoming_release_end_ts },
+];
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+pub fn schema_version(conn: &Connection) -> Result<i64> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+pub fn run_migrations(conn: &mut Connection) -> std::result::Result<(), String> {
+    let current = schema_version(conn).map_err(|e| format!("Failed to read schema version: {}", e))?;
+    let latest = latest_version();
+
+    if current > latest {
+        return Err(format!(
+            "Database schema version {} is newer than this app supports ({}). Refusing to open it.",
+            current, latest
+        ));
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        println!("RYM-DATABASE: Applying migration {} ({})...", migration.version, migration.name);
+
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Migration {} ({}) could not start: {}", migration.version, migration.name, e))?;
+        (migration.up)(&tx)
+            .and_then(|_| tx.pragma_update(None, "user_version", migration.version))
+            .map_err(|e| format!("Migration {} ({}) failed: {}", migration.version, migration.name, e))?;
+        tx.commit()
+            .map_err(|e| format!("Migration {} ({}) could not commit: {}", migration.version, migration.name, e))?;
+    }
+
+    Ok(())
+}
+
+fn has_column(tx: &Transaction, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = tx.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in names {
+        if name? == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// Caches created before versioning may already have some of these columns
+fn add_column_if_missing(tx: &Transaction, table: &str, column: &str, def: &str) -> Result<()> {
+    if !has_column(tx, table, column)? {
+        tx.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, def), [])?;
+    }
+    Ok(())
+}
+
+fn album_ratings_baseline(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS album_ratings (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            album_name TEXT NOT NULL,
+            artist_name TEXT NOT NULL,
+            rym_rating REAL NOT NULL,
+            rating_count INTEGER NOT NULL,
+            rym_url TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            UNIQUE(album_name, artist_name)
+        )",
+        [],
+    )?;
+
+    let columns = [
+        ("genres", "TEXT NOT NULL DEFAULT ''"),
+        ("secondary_genres", "TEXT DEFAULT ''"),
+        ("descriptors", "TEXT DEFAULT ''"),
+        ("language", "TEXT DEFAULT ''"),
+        ("rank", "TEXT DEFAULT ''"),
+        ("track_ratings", "TEXT DEFAULT ''"),
+        ("reviews", "TEXT DEFAULT ''"),
+        ("release_date", "TEXT NOT NULL DEFAULT ''"),
+    ];
+    for (col, def) in columns {
+        add_column_if_missing(tx, "album_ratings", col, def)?;
+    }
+    Ok(())
+}
+
+// Per-track ratings, one row per tracklist line of an album
+fn track_ratings_table(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS track_ratings (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            album_id INTEGER NOT NULL REFERENCES album_ratings(id) ON DELETE CASCADE,
+            position INTEGER NOT NULL,
+            number TEXT NOT NULL DEFAULT '',
+            title TEXT NOT NULL,
+            duration TEXT NOT NULL DEFAULT '',
+            rating REAL,
+            count INTEGER,
+            UNIQUE(album_id, position)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// Reviews are merged rather than replaced, so ones RYM has paginated away are kept
+fn reviews_table(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS reviews (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            album_id INTEGER NOT NULL REFERENCES album_ratings(id) ON DELETE CASCADE,
+            review_id TEXT NOT NULL,
+            reviewer TEXT NOT NULL,
+            date TEXT NOT NULL DEFAULT '',
+            rating REAL,
+            body TEXT NOT NULL DEFAULT '',
+            first_seen INTEGER NOT NULL,
+            last_seen INTEGER NOT NULL,
+            UNIQUE(album_id, review_id)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// Append-only score history, one row per save
+fn rating_snapshots_table(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS rating_snapshots (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            album_id INTEGER NOT NULL REFERENCES album_ratings(id) ON DELETE CASCADE,
+            rym_rating REAL NOT NULL,
+            rating_count INTEGER NOT NULL,
+            timestamp INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    tx.execute(
+        "CREATE INDEX IF NOT EXISTS idx_rating_snapshots_album ON rating_snapshots(album_id, timestamp)",
+        [],
+    )?;
+    // Seed history with the cached value of albums saved before snapshots existed
+    tx.execute(
+        "INSERT INTO rating_snapshots (album_id, rym_rating, rating_count, timestamp)
+         SELECT id, rym_rating, rating_count, timestamp FROM album_ratings
+         WHERE id NOT IN (SELECT album_id FROM rating_snapshots)",
+        [],
+    )?;
+    Ok(())
+}
+
+fn normalized_keys(tx: &Transaction) -> Result<()> {
+    add_column_if_missing(tx, "album_ratings", "album_key", "TEXT NOT NULL DEFAULT ''")?;
+    add_column_if_missing(tx, "album_ratings", "artist_key", "TEXT NOT NULL DEFAULT ''")?;
+    backfill_keys(tx)?;
+    tx.execute(
+        "CREATE INDEX IF NOT EXISTS idx_album_ratings_keys ON album_ratings(album_key, artist_key)",
+        [],
+    )?;
+    tx.execute(
+        "CREATE INDEX IF NOT EXISTS idx_album_ratings_nocase
+         ON album_ratings(album_name COLLATE NOCASE, artist_name COLLATE NOCASE)",
+        [],
+    )?;
+    Ok(())
+}
+
+// Manual matches: an Apple Music artist/album pointing at the canonical RYM release
+fn aliases_table(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS aliases (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            artist_name TEXT NOT NULL,
+            album_name TEXT NOT NULL,
+            artist_key TEXT NOT NULL,
+            album_key TEXT NOT NULL,
+            rym_url TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            UNIQUE(album_key, artist_key)
+        )",
+        [],
+    )?;
+    tx.execute("CREATE INDEX IF NOT EXISTS idx_album_ratings_url ON album_ratings(rym_url)", [])?;
+    Ok(())
+}
+
+// Fill album_key/artist_key for rows saved before the key columns existed.
+// Callers own the transaction. Migration 7 moves the keys to album_keys.
+fn backfill_keys(conn: &Connection) -> Result<()> {
+    let rows = {
+        let mut stmt = conn.prepare("SELECT id, album_name, artist_name FROM album_ratings WHERE album_key = ''")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?;
+        rows.collect::<Result<Vec<_>>>()?
+    };
+
+    if !rows.is_empty() {
+        println!("RYM-DATABASE: Backfilling normalized keys for {} rows...", rows.len());
+        let mut stmt = conn.prepare("UPDATE album_ratings SET album_key = ?1, artist_key = ?2 WHERE id = ?3")?;
+        for (id, album, artist) in &rows {
+            stmt.execute((normalize_key(album), normalize_key(artist), id))?;
+        }
+    }
+    Ok(())
+}
+
+// One row per (album key, artist key) a release can be found under, so names RYM
+// shows with a bracketed romanization are indexed in both forms
+fn album_keys_table(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS album_keys (
+            album_id INTEGER NOT NULL REFERENCES album_ratings(id) ON DELETE CASCADE,
+            album_key TEXT NOT NULL,
+            artist_key TEXT NOT NULL
+        )",
+        [],
+    )?;
+    tx.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_album_keys_lookup ON album_keys(album_key, artist_key, album_id)",
+        [],
+    )?;
+    tx.execute("CREATE INDEX IF NOT EXISTS idx_album_keys_album ON album_keys(album_id)", [])?;
+    index_missing_keys(tx)?;
+
+    tx.execute("DROP INDEX IF EXISTS idx_album_ratings_keys", [])?;
+    tx.execute("ALTER TABLE album_ratings DROP COLUMN album_key", [])?;
+    tx.execute("ALTER TABLE album_ratings DROP COLUMN artist_key", [])?;
+
+    // Alias keys were computed before diacritic folding; a collision keeps the newer alias
+    let aliases = {
+        let mut stmt = tx.prepare("SELECT id, album_name, artist_name FROM aliases ORDER BY created_at")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?;
+        rows.collect::<Result<Vec<_>>>()?
+    };
+    let mut stmt = tx.prepare("UPDATE OR REPLACE aliases SET album_key = ?1, artist_key = ?2 WHERE id = ?3")?;
+    for (id, album, artist) in &aliases {
+        stmt.execute((normalize_key(album), normalize_key(artist), id))?;
+    }
+    Ok(())
+}
+
+// Artist pages: one row per artist plus their discography in page order
+fn artists_tables(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS artists (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            name_key TEXT NOT NULL,
+            romanized_key TEXT NOT NULL DEFAULT '',
+            rym_url TEXT NOT NULL UNIQUE,
+            formed TEXT,
+            born TEXT,
+            genres TEXT NOT NULL DEFAULT '',
+            timestamp INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    tx.execute("CREATE INDEX IF NOT EXISTS idx_artists_name_key ON artists(name_key)", [])?;
+    tx.execute("CREATE INDEX IF NOT EXISTS idx_artists_romanized_key ON artists(romanized_key)", [])?;
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS artist_releases (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            artist_id INTEGER NOT NULL REFERENCES artists(id) ON DELETE CASCADE,
+            position INTEGER NOT NULL,
+            release_type TEXT NOT NULL,
+            title TEXT NOT NULL,
+            rym_url TEXT NOT NULL,
+            year INTEGER,
+            rating REAL,
+            count INTEGER,
+            UNIQUE(artist_id, position)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// One snapshot per chart and chart date; later pages of the same chart add entries to it
+fn chart_snapshots_tables(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS chart_snapshots (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            chart_url TEXT NOT NULL,
+            chart_date TEXT NOT NULL,
+            title TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            UNIQUE(chart_url, chart_date)
+        )",
+        [],
+    )?;
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS chart_entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            snapshot_id INTEGER NOT NULL REFERENCES chart_snapshots(id) ON DELETE CASCADE,
+            position INTEGER NOT NULL,
+            artist_name TEXT NOT NULL,
+            release_name TEXT NOT NULL,
+            rym_url TEXT NOT NULL,
+            year INTEGER,
+            release_date TEXT NOT NULL DEFAULT '',
+            genres TEXT NOT NULL DEFAULT '',
+            secondary_genres TEXT,
+            rating REAL,
+            count INTEGER,
+            reviews INTEGER,
+            UNIQUE(snapshot_id, position)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// Imported RYM lists; entries are keyed by their list position so later pages add to them
+fn collections_tables(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS collections (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            rym_url TEXT NOT NULL UNIQUE,
+            title TEXT NOT NULL,
+            author TEXT NOT NULL DEFAULT '',
+            description TEXT,
+            cursor INTEGER NOT NULL DEFAULT 0,
+            timestamp INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS collection_entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            collection_id INTEGER NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
+            position INTEGER NOT NULL,
+            entry_type TEXT NOT NULL,
+            artist_name TEXT NOT NULL,
+            title TEXT NOT NULL,
+            rym_url TEXT NOT NULL,
+            year INTEGER,
+            description TEXT,
+            UNIQUE(collection_id, position)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// The RYM genre hierarchy as an adjacency list, plus each genre's top releases
+fn genres_tables(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS genres (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            name_key TEXT NOT NULL,
+            rym_url TEXT NOT NULL UNIQUE,
+            parent_id INTEGER REFERENCES genres(id) ON DELETE SET NULL,
+            top_level INTEGER NOT NULL DEFAULT 0,
+            summary TEXT,
+            description TEXT,
+            akas TEXT NOT NULL DEFAULT '',
+            release_count INTEGER,
+            timestamp INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    tx.execute("CREATE INDEX IF NOT EXISTS idx_genres_name_key ON genres(name_key)", [])?;
+    tx.execute("CREATE INDEX IF NOT EXISTS idx_genres_parent ON genres(parent_id)", [])?;
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS genre_releases (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            genre_id INTEGER NOT NULL REFERENCES genres(id) ON DELETE CASCADE,
+            position INTEGER NOT NULL,
+            title TEXT NOT NULL,
+            artist_name TEXT NOT NULL,
+            rym_url TEXT NOT NULL,
+            UNIQUE(genre_id, position)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// Songs are looked up by normalized title and artist, like albums
+fn song_ratings_tables(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS song_ratings (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            title TEXT NOT NULL,
+            title_key TEXT NOT NULL,
+            artist_name TEXT NOT NULL,
+            artist_key TEXT NOT NULL,
+            rym_url TEXT NOT NULL UNIQUE,
+            rating REAL,
+            rating_count INTEGER NOT NULL DEFAULT 0,
+            genres TEXT NOT NULL DEFAULT '',
+            secondary_genres TEXT,
+            release_date TEXT NOT NULL DEFAULT '',
+            timestamp INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    tx.execute("CREATE INDEX IF NOT EXISTS idx_song_ratings_keys ON song_ratings(title_key, artist_key)", [])?;
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS song_appearances (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            song_id INTEGER NOT NULL REFERENCES song_ratings(id) ON DELETE CASCADE,
+            position INTEGER NOT NULL,
+            title TEXT NOT NULL,
+            rym_url TEXT NOT NULL,
+            track_number TEXT,
+            UNIQUE(song_id, position)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// The new music feed. `release_ts` is the parsed release date, for ordering the feed.
+fn upcoming_releases_table(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS upcoming_releases (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            rym_url TEXT NOT NULL UNIQUE,
+            release_type TEXT NOT NULL,
+            title TEXT NOT NULL,
+            artist_name TEXT NOT NULL,
+            artist_urls TEXT NOT NULL DEFAULT '',
+            release_date TEXT NOT NULL DEFAULT '',
+            release_ts INTEGER,
+            genres TEXT NOT NULL DEFAULT '',
+            rating REAL,
+            count INTEGER,
+            wants INTEGER,
+            timestamp INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    tx.execute("CREATE INDEX IF NOT EXISTS idx_upcoming_releases_date ON upcoming_releases(release_ts)", [])?;
+    Ok(())
+}
+
+// Streaming links per release page: RYM's defaults first, then by service
+fn media_links_table(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS media_links (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            release_url TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            service TEXT NOT NULL,
+            url TEXT NOT NULL,
+            is_default INTEGER NOT NULL DEFAULT 0,
+            UNIQUE(release_url, position)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// RYM pages waiting to be fetched, one row per URL, taken most urgent first
+fn fetch_queue_table(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS fetch_queue (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            url TEXT NOT NULL UNIQUE,
+            priority INTEGER NOT NULL,
+            enqueued_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    tx.execute("CREATE INDEX IF NOT EXISTS idx_fetch_queue_order ON fetch_queue(priority DESC, enqueued_at)", [])?;
+    Ok(())
+}
+
+// Background refresh pages loaded per UTC day
+fn refresh_budget_table(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS refresh_budget (
+            day TEXT PRIMARY KEY,
+            pages INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// Lets similarity lookups gather every release by an artist without scanning album_keys
+fn artist_key_index(tx: &Transaction) -> Result<()> {
+    tx.execute("CREATE INDEX IF NOT EXISTS idx_album_keys_artist ON album_keys(artist_key)", [])?;
+    Ok(())
+}
+
+// The last day an upcoming release's date can stand for, so "December 2025" stays
+// upcoming all month
+fn upcoming_release_end_ts(tx: &Transaction) -> Result<()> {
+    add_column_if_missing(tx, "upcoming_releases", "release_end_ts", "INTEGER")?;
+    let rows = {
+        let mut stmt = tx.prepare("SELECT id, release_date FROM upcoming_releases")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+        rows.collect::<Result<Vec<_>>>()?
+    };
+    let mut stmt = tx.prepare("UPDATE upcoming_releases SET release_end_ts = ?1 WHERE id = ?2")?;
+    for (id, date) in &rows {
+        stmt.execute((ReleaseDate::parse(date).and_then(|d| d.latest_timestamp()), id))?;
+    }
+    Ok(())
+}
+
+// Index lookup keys for rows that have none yet. Callers own the transaction.
+pub fn index_missing_keys(conn: &Connection) -> Result<()> {
+    let rows = {
+        let mut stmt = conn.prepare(
+            "SELECT id, album_name, artist_name FROM album_ratings
+             WHERE id NOT IN (SELECT album_id FROM album_keys)",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?;
+        rows.collect::<Result<Vec<_>>>()?
+    };
+
+    if !rows.is_empty() {
+        println!("RYM-DATABASE: Indexing lookup keys for {} rows...", rows.len());
+        for (id, album, artist) in &rows {
+            index_album_keys(conn, *id, album, artist)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    // Schema written by the very first release (see README)
+    const FIXTURE_ORIGINAL: &str = "
+        CREATE TABLE album_ratings (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            album_name TEXT NOT NULL,
+            artist_name TEXT NOT NULL,
+            rym_rating REAL NOT NULL,
+            rating_count INTEGER NOT NULL,
+            rym_url TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            UNIQUE(album_name, artist_name)
+        );
+        INSERT INTO album_ratings (album_name, artist_name, rym_rating, rating_count, rym_url, timestamp)
+        VALUES ('Kid A', 'Radiohead', 4.2, 60000, 'https://rateyourmusic.com/release/album/radiohead/kid-a/', 1700000000);
+    ";
+
+    // Unversioned cache after the blind ALTER TABLE era, with JSON blobs for tracks and reviews
+    const FIXTURE_UNVERSIONED: &str = r#"
+        CREATE TABLE album_ratings (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            album_name TEXT NOT NULL,
+            artist_name TEXT NOT NULL,
+            rym_rating REAL NOT NULL,
+            rating_count INTEGER NOT NULL,
+            rym_url TEXT NOT NULL,
+            genres TEXT NOT NULL DEFAULT '',
+            secondary_genres TEXT DEFAULT '',
+            descriptors TEXT DEFAULT '',
+            language TEXT DEFAULT '',
+            rank TEXT DEFAULT '',
+            track_ratings TEXT DEFAULT '',
+            reviews TEXT DEFAULT '',
+            release_date TEXT NOT NULL DEFAULT '',
+            timestamp INTEGER NOT NULL,
+            UNIQUE(album_name, artist_name)
+        );
+        INSERT INTO album_ratings (album_name, artist_name, rym_rating, rating_count, rym_url, genres, track_ratings, reviews, release_date, timestamp)
+        VALUES ('Blue Lines (Remastered)', 'Massive Attack', 3.9, 20000, 'https://rateyourmusic.com/release/album/massive-attack/blue-lines/', 'Trip Hop',
+                '[{"number":"1","title":"Safe From Harm","duration":"5:18","rating":3.9,"count":900}]',
+                '[{"reviewer":"someone","date":"2020","rating":4.0,"text":"Great"}]',
+                '8 April 1991', 1700000000);
+    "#;
+
+    struct Fixture(PathBuf);
+
+    impl Fixture {
+        fn new(name: &str, sql: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("rym_migration_{}_{}.db", name, std::process::id()));
+            let _ = std::fs::remove_file(&path);
+            Connection::open(&path).unwrap().execute_batch(sql).unwrap();
+            Fixture(path)
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn fresh_database_reaches_latest_version() {
+        let fixture = Fixture::new("fresh", "");
+        Database::new(fixture.0.clone()).unwrap();
+        assert_eq!(schema_version(&Connection::open(&fixture.0).unwrap()).unwrap(), latest_version());
+    }
+
+    #[test]
+    fn upgrades_original_schema() {
+        let fixture = Fixture::new("original", FIXTURE_ORIGINAL);
+        let db = Database::new(fixture.0.clone()).unwrap();
+
+        let rating = db.get_rating("kid a", "RADIOHEAD").unwrap().unwrap();
+        assert_eq!(rating.rating_count, 60000);
+        assert_eq!(rating.genres, "");
+        assert_eq!(db.get_rating_history("Kid A", "Radiohead").unwrap().len(), 1);
+        assert_eq!(schema_version(&Connection::open(&fixture.0).unwrap()).unwrap(), latest_version());
+    }
+
+    #[test]
+    fn upgrades_unversioned_schema_with_legacy_json() {
+        let fixture = Fixture::new("unversioned", FIXTURE_UNVERSIONED);
+        let db = Database::new(fixture.0.clone()).unwrap();
+
+        // Found through the backfilled normalized keys
+        let rating = db.get_rating("Blue Lines", "Massive Attack").unwrap().unwrap();
+        assert_eq!(rating.track_ratings.len(), 1);
+        assert_eq!(rating.track_ratings[0].title, "Safe From Harm");
+        assert_eq!(rating.reviews.len(), 1);
+        assert_eq!(rating.reviews[0].body, "Great");
+    }
+
+    #[test]
+    fn migrations_run_once() {
+        let fixture = Fixture::new("rerun", FIXTURE_ORIGINAL);
+        Database::new(fixture.0.clone()).unwrap();
+        Database::new(fixture.0.clone()).unwrap();
+
+        let conn = Connection::open(&fixture.0).unwrap();
+        let snapshots: i64 = conn.query_row("SELECT COUNT(*) FROM rating_snapshots", [], |row| row.get(0)).unwrap();
+        assert_eq!(snapshots, 1);
+    }
+
+    #[test]
+    fn failed_migration_rolls_back_and_reports() {
+        // A hand-made snapshots table without rating_count breaks the seed in migration 4
+        let broken = format!("{} CREATE TABLE rating_snapshots (album_id INTEGER, timestamp INTEGER);", FIXTURE_ORIGINAL);
+        let fixture = Fixture::new("broken", &broken);
+        let mut conn = Connection::open(&fixture.0).unwrap();
+
+        let err = run_migrations(&mut conn).unwrap_err();
+        assert!(err.starts_with("Migration 4 (rating_snapshots table) failed"), "{}", err);
+        assert_eq!(schema_version(&conn).unwrap(), 3);
+        let index: i64 = conn
+            .query_row("SELECT COUNT(*) FROM sqlite_master WHERE name = 'idx_rating_snapshots_album'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(index, 0);
+    }
+
+    #[test]
+    fn refuses_newer_schema() {
+        let fixture = Fixture::new("future", "PRAGMA user_version = 999;");
+        let err = Database::new(fixture.0.clone()).err().unwrap();
+        assert!(err.contains("newer than this app supports"), "{}", err);
+    }
+}
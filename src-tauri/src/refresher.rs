@@ -0,0 +1,291 @@
+use crate::database::{CachedAlbum, Database, FetchPriority};
+use crate::fetch_queue::FetchQueue;
+use crate::release_date::{is_fresh, ReleaseDate};
+use crate::ttl_policy::{TtlInput, TtlPolicy};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const DEFAULT_DAILY_BUDGET: u32 = 50;
+// Background refreshes wait until the user has left RYM alone this long
+pub const IDLE_AFTER: Duration = Duration::from_secs(120);
+const CHECK_EVERY: Duration = Duration::from_secs(30);
+
+// Background pages per day, overridable with RYM_REFRESH_DAILY_BUDGET
+pub fn daily_budget_from_env() -> u32 {
+    std::env::var("RYM_REFRESH_DAILY_BUDGET")
+        .ok()
+        .and_then(|v| v.trim().parse::<u32>().ok())
+        .unwrap_or(DEFAULT_DAILY_BUDGET)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OverdueAlbum {
+    pub artist_name: String,
+    pub album_name: String,
+    pub rym_url: String,
+    // How long past its TTL the cached rating is
+    pub overdue_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RefreshStatus {
+    pub daily_budget: u32,
+    pub pages_today: u32,
+    // Whether the last check found the user away from RYM
+    pub idle: bool,
+    pub overdue: usize,
+    // Refreshes waiting in the fetch queue
+    pub queued: usize,
+    pub next: Option<OverdueAlbum>,
+    pub last_refresh: Option<String>,
+}
+
+// Cached albums past their TTL, most overdue first
+pub fn overdue_albums(albums: &[CachedAlbum], policy: &dyn TtlPolicy, now: i64) -> Vec<OverdueAlbum> {
+    let mut overdue: Vec<_> = albums
+        .iter()
+        .filter_map(|album| {
+            let release = ReleaseDate::parse(&album.release_date);
+            let ttl = policy.ttl_seconds(&TtlInput {
+                now,
+                release: release.as_ref(),
+                rating_count: album.rating_count,
+                score_change: album.score_change,
+            });
+            (!is_fresh(album.timestamp, ttl, now)).then(|| OverdueAlbum {
+                artist_name: album.artist_name.clone(),
+                album_name: album.album_name.clone(),
+                rym_url: album.rym_url.clone(),
+                overdue_seconds: now - album.timestamp - ttl,
+            })
+        })
+        .collect();
+    overdue.sort_by_key(|a| std::cmp::Reverse(a.overdue_seconds));
+    overdue
+}
+
+// UTC day the budget is counted against
+fn budget_day(now: i64) -> String {
+    chrono::DateTime::from_timestamp(now, 0).map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default()
+}
+
+#[derive(Default)]
+struct RefreshState {
+    idle: bool,
+    day: String,
+    // Pages queued today. One that didn't refresh isn't tried again until tomorrow.
+    tried: HashSet<String>,
+    last_refresh: Option<String>,
+}
+
+// Refreshes stale cache entries while the user isn't using RYM: one page at a time
+// through the fetch queue, most overdue first, up to a daily budget
+pub struct Refresher {
+    db: Arc<Mutex<Database>>,
+    queue: Arc<FetchQueue>,
+    policy: Arc<dyn TtlPolicy>,
+    daily_budget: u32,
+    state: Mutex<RefreshState>,
+}
+
+impl Refresher {
+    pub fn new(db: Arc<Mutex<Database>>, queue: Arc<FetchQueue>, policy: Arc<dyn TtlPolicy>, daily_budget: u32) -> Self {
+        Refresher { db, queue, policy, daily_budget, state: Mutex::new(RefreshState::default()) }
+    }
+
+    // Checks every half minute for as long as the app runs. `rym_in_use` says whether
+    // a refresh would navigate away a RYM window the user is looking at.
+    pub async fn run(&self, rym_in_use: impl Fn() -> bool) {
+        println!("RYM-REFRESH: Background refresher started (budget {} pages/day)", self.daily_budget);
+        loop {
+            tokio::time::sleep(CHECK_EVERY).await;
+            let idle = !rym_in_use() && self.queue.idle_for() >= IDLE_AFTER;
+            self.tick(chrono::Utc::now().timestamp(), idle);
+        }
+    }
+
+    // Queues the most overdue album if the user is idle, nothing else is waiting and
+    // today's budget allows it. Returns the queued URL. The budget caps requests to RYM,
+    // so a page is charged when it is queued and a fetch that fails still counts.
+    pub fn tick(&self, now: i64, idle: bool) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        state.idle = idle;
+        let day = budget_day(now);
+        if state.day != day {
+            state.day = day.clone();
+            state.tried.clear();
+        }
+        if !idle || !self.queue.pending().is_empty() {
+            return None;
+        }
+
+        let pages = match self.db.lock().unwrap().refresh_pages(&day) {
+            Ok(pages) => pages,
+            Err(e) => {
+                eprintln!("RYM-REFRESH: ❌ Failed to read today's budget: {}", e);
+                return None;
+            }
+        };
+        if pages >= self.daily_budget {
+            return None;
+        }
+
+        let album = self.overdue(now).into_iter().find(|a| !state.tried.contains(&a.rym_url))?;
+        if let Err(e) = self.queue.enqueue(&album.rym_url, FetchPriority::Refresh) {
+            eprintln!("RYM-REFRESH: ❌ {}", e);
+            return None;
+        }
+        let pages = self.db.lock().unwrap().count_refresh_page(&day).unwrap_or(pages + 1);
+        println!(
+            "RYM-REFRESH: Queued {} - {} ({}h overdue, {}/{} pages today)",
+            album.artist_name,
+            album.album_name,
+            album.overdue_seconds / 3600,
+            pages,
+            self.daily_budget
+        );
+
+        state.tried.insert(album.rym_url.clone());
+        state.last_refresh = Some(album.rym_url.clone());
+        Some(album.rym_url)
+    }
+
+    pub fn status(&self, now: i64) -> RefreshStatus {
+        let state = self.state.lock().unwrap();
+        let pages_today = self.db.lock().unwrap().refresh_pages(&budget_day(now)).unwrap_or(0);
+        let overdue = self.overdue(now);
+        let queued = self.queue.pending().iter().filter(|f| f.priority == FetchPriority::Refresh).count();
+        RefreshStatus {
+            daily_budget: self.daily_budget,
+            pages_today,
+            idle: state.idle,
+            overdue: overdue.len(),
+            queued,
+            next: overdue.into_iter().find(|a| !state.tried.contains(&a.rym_url)),
+            last_refresh: state.last_refresh.clone(),
+        }
+    }
+
+    fn overdue(&self, now: i64) -> Vec<OverdueAlbum> {
+        let albums = match self.db.lock().unwrap().cached_albums() {
+            Ok(albums) => albums,
+            Err(e) => {
+                eprintln!("RYM-REFRESH: ❌ Failed to read the cache: {}", e);
+                return Vec::new();
+            }
+        };
+        overdue_albums(&albums, self.policy.as_ref(), now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::AlbumRating;
+    use crate::fetch_queue::TokenBucket;
+    use crate::ttl_policy::AgePolicy;
+    use std::path::PathBuf;
+
+    const DAY: i64 = 86400;
+
+    fn now() -> i64 {
+        ReleaseDate::parse("15 December 2025").unwrap().timestamp().unwrap() + 12 * 3600
+    }
+
+    fn url(name: &str) -> String {
+        format!("https://rateyourmusic.com/release/album/{}/", name)
+    }
+
+    fn album(name: &str, release_date: &str, fetched_days_ago: i64) -> CachedAlbum {
+        CachedAlbum {
+            album_name: name.to_string(),
+            artist_name: "Artist".to_string(),
+            rym_url: url(name),
+            release_date: release_date.to_string(),
+            rating_count: 100,
+            timestamp: now() - fetched_days_ago * DAY,
+            score_change: None,
+        }
+    }
+
+    fn rating(album: &CachedAlbum) -> AlbumRating {
+        serde_json::from_value(serde_json::json!({
+            "album_name": album.album_name,
+            "artist_name": album.artist_name,
+            "rym_rating": 3.5,
+            "rating_count": album.rating_count,
+            "rym_url": album.rym_url,
+            "genres": "",
+            "release_date": album.release_date,
+            "timestamp": album.timestamp,
+        }))
+        .unwrap()
+    }
+
+    fn with_budget(db: &Arc<Mutex<Database>>, daily_budget: u32) -> Refresher {
+        let queue = Arc::new(FetchQueue::new(db.clone(), TokenBucket::new(1, 1.0)));
+        Refresher::new(db.clone(), queue, Arc::new(AgePolicy), daily_budget)
+    }
+
+    // The page the worker would load next
+    fn take_queued(db: &Arc<Mutex<Database>>) -> Option<String> {
+        db.lock().unwrap().pop_fetch().unwrap().map(|f| f.url)
+    }
+
+    #[test]
+    fn most_overdue_albums_come_first() {
+        let albums = [
+            album("fresh-classic", "1 June 1971", 10),
+            album("stale-classic", "1 June 1971", 200),
+            album("new-release", "10 December 2025", 3),
+            album("undated", "", 400),
+            album("fetched-today", "10 December 2025", 0),
+        ];
+
+        let overdue: Vec<_> = overdue_albums(&albums, &AgePolicy, now())
+            .into_iter()
+            .map(|a| (a.album_name, a.overdue_seconds / DAY))
+            .collect();
+        assert_eq!(overdue, vec![
+            ("undated".to_string(), 220),
+            ("stale-classic".to_string(), 20),
+            ("new-release".to_string(), 2),
+        ]);
+    }
+
+    #[test]
+    fn refreshes_one_page_at_a_time_within_the_budget() {
+        let db = Arc::new(Mutex::new(Database::new(PathBuf::from(":memory:")).unwrap()));
+        for a in [album("a", "", 300), album("b", "", 250), album("c", "", 200), album("fresh", "", 1)] {
+            db.lock().unwrap().save_rating(&rating(&a)).unwrap();
+        }
+        let refresher = with_budget(&db, 2);
+
+        // Nothing happens while the user is busy
+        assert_eq!(refresher.tick(now(), false), None);
+        assert_eq!(refresher.tick(now(), true), Some(url("a")));
+        // The next page waits until this one has been loaded
+        assert_eq!(refresher.tick(now(), true), None);
+        assert_eq!(take_queued(&db), Some(url("a")));
+
+        // "a" didn't get refreshed, but it used up budget and isn't retried today
+        assert_eq!(refresher.tick(now(), true), Some(url("b")));
+        assert_eq!(take_queued(&db), Some(url("b")));
+        db.lock().unwrap().save_rating(&rating(&album("b", "", 0))).unwrap();
+
+        // Budget spent
+        assert_eq!(refresher.tick(now(), true), None);
+        let status = refresher.status(now());
+        assert_eq!((status.pages_today, status.overdue, status.queued), (2, 2, 0));
+        assert_eq!(status.next.map(|a| a.rym_url), Some(url("c")));
+        assert_eq!(status.last_refresh, Some(url("b")));
+
+        // The budget survives a restart and resets the next day
+        let restarted = with_budget(&db, 2);
+        assert_eq!(restarted.tick(now(), true), None);
+        assert_eq!(restarted.tick(now() + DAY, true), Some(url("a")));
+        assert_eq!(restarted.status(now() + DAY).queued, 1);
+    }
+}
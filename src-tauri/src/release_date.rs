@@ -1,42 +1,101 @@
-use chrono::{Utc, TimeZone};
+use chrono::{Months, NaiveDate, TimeZone, Utc};
+use std::fmt;
 
-pub fn parse_release_date_to_timestamp(releasedate: &str) -> Option<i64> {
-    if releasedate.is_empty() {
+const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+// A release date only as precise as RYM gives it: "21 December 2015", "December 2025"
+// or just "2025". Within the same period the less precise date sorts first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReleaseDate {
+    pub year: i32,
+    pub month: Option<u32>,
+    pub day: Option<u32>,
+}
+
+// "December", "Dec" or "Dec." -> 12
+fn month_number(token: &str) -> Option<u32> {
+    let token = token.trim_end_matches('.').to_lowercase();
+    if token.len() < 3 {
         return None;
     }
+    MONTHS
+        .iter()
+        .position(|m| m.to_lowercase().starts_with(&token))
+        .map(|i| i as u32 + 1)
+}
+
+fn start_of_day(date: NaiveDate) -> Option<i64> {
+    Some(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?).timestamp())
+}
+
+impl ReleaseDate {
+    // Reads the first date in a RYM date cell, skipping labels around it:
+    // "Released 21 December 2015", "Recorded October 1958 - March 1959" (the start of
+    // the session), "Reissued 4 September 2009", "2009 reissue".
+    pub fn parse(s: &str) -> Option<ReleaseDate> {
+        let tokens: Vec<&str> = s
+            .split(|c: char| c.is_whitespace() || matches!(c, ',' | '-' | '–' | '/'))
+            .filter(|t| !t.is_empty())
+            .collect();
+        let year_at = tokens.iter().position(|t| t.len() == 4 && t.chars().all(|c| c.is_ascii_digit()))?;
 
-    let releasedate = releasedate.trim();
+        let year = tokens[year_at].parse().ok()?;
+        let month = year_at.checked_sub(1).and_then(|i| month_number(tokens[i]));
+        let day = match month {
+            Some(_) => year_at.checked_sub(2).and_then(|i| tokens[i].parse().ok()),
+            None => None,
+        };
 
-    // Try common formats
-    // 1. "24 December 2025" or "24 Dec 2025"
-    if let Ok(dt) = chrono::NaiveDate::parse_from_str(releasedate, "%d %B %Y") {
-        return Some(Utc.from_utc_datetime(&dt.and_hms_opt(0, 0, 0)?).timestamp());
+        let date = ReleaseDate { year, month, day };
+        // Rejects "31 February 2020"
+        date.first_day()?;
+        Some(date)
     }
-    if let Ok(dt) = chrono::NaiveDate::parse_from_str(releasedate, "%d %b %Y") {
-        return Some(Utc.from_utc_datetime(&dt.and_hms_opt(0, 0, 0)?).timestamp());
+
+    // The earliest and the latest day the date can stand for
+    pub fn first_day(&self) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(self.year, self.month.unwrap_or(1), self.day.unwrap_or(1))
     }
-    
-    // 2. "December 2025" or "Dec 2025" (Assume 1st of month)
-    if let Ok(dt) = chrono::NaiveDate::parse_from_str(&format!("1 {}", releasedate), "%d %B %Y") {
-        return Some(Utc.from_utc_datetime(&dt.and_hms_opt(0, 0, 0)?).timestamp());
+
+    pub fn last_day(&self) -> Option<NaiveDate> {
+        match (self.month, self.day) {
+            (Some(_), Some(_)) => self.first_day(),
+            (Some(_), None) => self.first_day()?.checked_add_months(Months::new(1))?.pred_opt(),
+            (None, _) => NaiveDate::from_ymd_opt(self.year, 12, 31),
+        }
     }
-    if let Ok(dt) = chrono::NaiveDate::parse_from_str(&format!("1 {}", releasedate), "%d %b %Y") {
-        return Some(Utc.from_utc_datetime(&dt.and_hms_opt(0, 0, 0)?).timestamp());
+
+    // Start of the first possible day, for ordering
+    pub fn timestamp(&self) -> Option<i64> {
+        start_of_day(self.first_day()?)
     }
 
-    // 3. "2025" (Year only - Assume Jan 1st)
-    if let Ok(year) = releasedate.parse::<i32>() {
-        if year > 1900 && year < 2100 {
-            let dt = chrono::NaiveDate::from_ymd_opt(year, 1, 1)?;
-            return Some(Utc.from_utc_datetime(&dt.and_hms_opt(0, 0, 0)?).timestamp());
-        }
+    // Start of the last possible day: an imprecise date is taken to be as recent as it can be
+    pub fn latest_timestamp(&self) -> Option<i64> {
+        start_of_day(self.last_day()?)
     }
+}
 
-    None
+impl fmt::Display for ReleaseDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(day) = self.day {
+            write!(f, "{} ", day)?;
+        }
+        if let Some(month) = self.month {
+            write!(f, "{} ", MONTHS[month as usize - 1])?;
+        }
+        write!(f, "{}", self.year)
+    }
 }
 
-pub fn compute_ttl_seconds(now_ts: i64, release_ts: Option<i64>) -> i64 {
-    let release_ts = match release_ts {
+// How long a cached rating stays fresh. New releases move fast, old ones barely at all.
+// Imprecise dates count from their last possible day, so a "2025" release isn't
+// treated as older than it might be.
+pub fn compute_ttl_seconds(now_ts: i64, release: Option<&ReleaseDate>) -> i64 {
+    let release_ts = match release.and_then(|r| r.latest_timestamp()) {
         Some(ts) => ts,
         None => return 180 * 86400, // Default to 180 days if unknown
     };
@@ -45,7 +104,7 @@ pub fn compute_ttl_seconds(now_ts: i64, release_ts: Option<i64>) -> i64 {
     let age_days = age_seconds / 86400;
 
     if age_days < 14 {
-        1 * 86400 // 1 day
+        86400 // 1 day
     } else if age_days < 30 {
         3 * 86400 // 3 days
     } else if age_days < 180 { // 6 months
@@ -62,3 +121,83 @@ pub fn compute_ttl_seconds(now_ts: i64, release_ts: Option<i64>) -> i64 {
 pub fn is_fresh(fetched_at: i64, ttl_seconds: i64, now_ts: i64) -> bool {
     (now_ts - fetched_at) < ttl_seconds
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: Option<u32>, day: Option<u32>) -> ReleaseDate {
+        ReleaseDate { year, month, day }
+    }
+
+    fn ts(s: &str) -> i64 {
+        ReleaseDate::parse(s).unwrap().timestamp().unwrap()
+    }
+
+    #[test]
+    fn parses_rym_date_cells() {
+        assert_eq!(ReleaseDate::parse("21 December 2015"), Some(date(2015, Some(12), Some(21))));
+        assert_eq!(ReleaseDate::parse("21 Dec 2015"), Some(date(2015, Some(12), Some(21))));
+        assert_eq!(ReleaseDate::parse("December 2025"), Some(date(2025, Some(12), None)));
+        assert_eq!(ReleaseDate::parse("2025"), Some(date(2025, None, None)));
+        assert_eq!(ReleaseDate::parse("Released 17 November 1958"), Some(date(1958, Some(11), Some(17))));
+
+        assert_eq!(ReleaseDate::parse(""), None);
+        assert_eq!(ReleaseDate::parse("Unknown"), None);
+        assert_eq!(ReleaseDate::parse("31 February 2020"), None);
+    }
+
+    #[test]
+    fn parses_recorded_and_reissue_dates() {
+        // Single and video samples: "Recorded 19 October 1958", "Recorded 22 November 2025"
+        assert_eq!(ReleaseDate::parse("Recorded 19 October 1958"), Some(date(1958, Some(10), Some(19))));
+        assert_eq!(ReleaseDate::parse("22 November 2025"), Some(date(2025, Some(11), Some(22))));
+        // Sessions spanning months or years start the range
+        assert_eq!(ReleaseDate::parse("Recorded October 1958 - March 1959"), Some(date(1958, Some(10), None)));
+        assert_eq!(ReleaseDate::parse("1975-1976"), Some(date(1975, None, None)));
+
+        assert_eq!(ReleaseDate::parse("Reissued 4 Sept. 2009"), Some(date(2009, Some(9), Some(4))));
+        assert_eq!(ReleaseDate::parse("2009 reissue"), Some(date(2009, None, None)));
+    }
+
+    #[test]
+    fn orders_and_displays_by_precision() {
+        let mut dates: Vec<_> = ["5 December 2025", "2025", "December 2025", "December 2024", "1 January 2025"]
+            .iter()
+            .map(|s| ReleaseDate::parse(s).unwrap())
+            .collect();
+        dates.sort();
+        let shown: Vec<_> = dates.iter().map(|d| d.to_string()).collect();
+        assert_eq!(shown, ["December 2024", "2025", "1 January 2025", "December 2025", "5 December 2025"]);
+    }
+
+    #[test]
+    fn spans_the_whole_period() {
+        let february = ReleaseDate::parse("February 2024").unwrap();
+        assert_eq!(february.first_day(), NaiveDate::from_ymd_opt(2024, 2, 1));
+        assert_eq!(february.last_day(), NaiveDate::from_ymd_opt(2024, 2, 29));
+        assert_eq!(ReleaseDate::parse("2025").unwrap().last_day(), NaiveDate::from_ymd_opt(2025, 12, 31));
+        assert_eq!(ReleaseDate::parse("December 2025").unwrap().last_day(), NaiveDate::from_ymd_opt(2025, 12, 31));
+    }
+
+    #[test]
+    fn ttl_treats_imprecise_dates_as_recent() {
+        let now = ts("15 December 2025");
+        let ttl = |s: &str| compute_ttl_seconds(now, ReleaseDate::parse(s).as_ref());
+
+        // Could have come out this week
+        assert_eq!(ttl("2025"), 86400);
+        assert_eq!(ttl("December 2025"), 86400);
+        assert_eq!(ttl("November 2025"), 3 * 86400);
+        assert_eq!(ttl("1 January 2025"), 30 * 86400);
+        assert_eq!(ttl("2023"), 90 * 86400);
+        assert_eq!(ttl("Recorded 19 October 1958"), 180 * 86400);
+        assert_eq!(ttl(""), 180 * 86400);
+    }
+
+    #[test]
+    fn freshness_window() {
+        assert!(is_fresh(1000, 86400, 1000 + 86399));
+        assert!(!is_fresh(1000, 86400, 1000 + 86400));
+    }
+}
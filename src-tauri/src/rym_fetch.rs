@@ -0,0 +1,182 @@
+use crate::database::{AlbumRating, MediaLink};
+use crate::media_links;
+use crate::rym_parse;
+use crate::sync::RYM_HOME_URL;
+use reqwest::cookie::Jar;
+use reqwest::{Client, Url};
+use std::sync::Arc;
+use std::time::Duration;
+
+// What the RYM webview sends, so pages come back the way the window sees them
+const USER_AGENT: &str =
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/18.0 Safari/605.1.15";
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+// Background pages are fetched over HTTP only with RYM_HEADLESS_FETCH=1 (or "true")
+pub fn headless_from_env() -> bool {
+    matches!(std::env::var("RYM_HEADLESS_FETCH").as_deref().map(str::trim), Ok("1") | Ok("true"))
+}
+
+// A release page fetched and parsed without the RYM window
+#[derive(Debug)]
+pub struct FetchedRelease {
+    pub rating: AlbumRating,
+    pub links: Vec<MediaLink>,
+}
+
+// Fetches RYM pages with reqwest, sending the cookies of the user's RYM session
+pub struct HttpFetcher {
+    client: Client,
+    jar: Arc<Jar>,
+    // Where rateyourmusic.com URLs are fetched from; a local server in tests
+    origin: String,
+}
+
+impl HttpFetcher {
+    pub fn new() -> Result<Self, String> {
+        HttpFetcher::with_origin(RYM_HOME_URL)
+    }
+
+    pub fn with_origin(origin: &str) -> Result<Self, String> {
+        let jar = Arc::new(Jar::default());
+        let client = Client::builder()
+            .cookie_provider(jar.clone())
+            .user_agent(USER_AGENT)
+            .timeout(TIMEOUT)
+            .build()
+            .map_err(|e| format!("Failed to build the HTTP client: {}", e))?;
+        Ok(HttpFetcher { client, jar, origin: origin.trim_end_matches('/').to_string() })
+    }
+
+    // Adds (name, value) cookies taken from the RYM window, replacing ones of the same
+    // name. Returns how many were imported.
+    pub fn import_cookies<'a>(&self, cookies: impl IntoIterator<Item = (&'a str, &'a str)>) -> usize {
+        let Ok(origin) = Url::parse(&self.origin) else {
+            return 0;
+        };
+        let mut imported = 0;
+        for (name, value) in cookies {
+            self.jar.add_cookie_str(&format!("{}={}; Path=/", name, value), &origin);
+            imported += 1;
+        }
+        imported
+    }
+
+    pub async fn fetch_page(&self, url: &str) -> Result<String, String> {
+        let target = match url.strip_prefix(RYM_HOME_URL) {
+            Some(path) => format!("{}{}", self.origin, path),
+            None => url.to_string(),
+        };
+        let response = self.client.get(&target).send().await.map_err(|e| format!("Request for {} failed: {}", url, e))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("HTTP {} for {}", status.as_u16(), url));
+        }
+        response.text().await.map_err(|e| format!("Failed to read {}: {}", url, e))
+    }
+
+    // The rating and streaming links of a release page, keyed by its rateyourmusic.com URL
+    pub async fn fetch_release(&self, url: &str) -> Result<FetchedRelease, String> {
+        let html = self.fetch_page(url).await?;
+        let rating = rym_parse::parse_release_page(&html, url)?;
+        let links = media_links::parse_media_links(&html);
+        Ok(FetchedRelease { rating, links })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    // Stands in for rateyourmusic.com: "/release/album/..." gets
+    // sample_pages/release_album_sample.html, "/search?..." search_sample.html. Records
+    // the Cookie header of every request.
+    struct SamplePages {
+        origin: String,
+        cookies: Arc<Mutex<Vec<String>>>,
+    }
+
+    fn sample_pages() -> SamplePages {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let origin = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let cookies = Arc::new(Mutex::new(Vec::new()));
+        let log = cookies.clone();
+        std::thread::spawn(move || {
+            let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../sample_pages");
+            for request in server.incoming_requests() {
+                let cookie = request.headers().iter().find(|h| h.field.equiv("Cookie")).map(|h| h.value.to_string());
+                log.lock().unwrap().push(cookie.unwrap_or_default());
+
+                let path = request.url().split('?').next().unwrap_or_default().to_string();
+                let kind: Vec<_> = path.split('/').filter(|s| !s.is_empty()).take(2).collect();
+                let page = match kind.as_slice() {
+                    ["release", kind] => format!("release_{}_sample.html", kind),
+                    [page, ..] => format!("{}_sample.html", page),
+                    [] => "homepage_sample.html".to_string(),
+                };
+                let _ = match std::fs::read(dir.join(page)) {
+                    Ok(html) => request.respond(tiny_http::Response::from_data(html)),
+                    Err(_) => request.respond(tiny_http::Response::empty(404)),
+                };
+            }
+        });
+        SamplePages { origin, cookies }
+    }
+
+    #[tokio::test]
+    async fn fetches_release_pages_with_the_session_cookies() {
+        let server = sample_pages();
+        let fetcher = HttpFetcher::with_origin(&server.origin).unwrap();
+        assert_eq!(fetcher.import_cookies([("ulv", "abc123"), ("sec_bs", "xyz")]), 2);
+
+        let url = "https://rateyourmusic.com/release/album/t-e-l-e-p-a-t-h-テレパシー能力者/星間性交/";
+        let page = fetcher.fetch_release(url).await.unwrap();
+        assert_eq!(page.rating.album_name, "星間性交");
+        assert_eq!(page.rating.rating_count, 3598);
+        // Stored under the real URL, not the server's
+        assert_eq!(page.rating.rym_url, url);
+        assert_eq!(page.links.len(), 4);
+
+        let cookies = server.cookies.lock().unwrap().clone();
+        assert_eq!(cookies.len(), 1);
+        assert!(cookies[0].contains("ulv=abc123") && cookies[0].contains("sec_bs=xyz"), "{}", cookies[0]);
+    }
+
+    #[tokio::test]
+    async fn parses_every_release_type() {
+        let server = sample_pages();
+        let fetcher = HttpFetcher::with_origin(&server.origin).unwrap();
+
+        // (type, title, rating, count) of each sample page
+        let samples = [
+            ("album", "星間性交", 3.87, 3598),
+            ("ep", "What Once Was... Liber III", 3.11, 373),
+            ("single", "Rockin' Around the Christmas Tree / Papa Noël", 3.48, 506),
+            ("comp", "Like Cats and Dogs", 3.38, 242),
+            ("video", "Camp Flog Gnaw 2025", 4.3, 12),
+        ];
+        for (kind, title, rating, count) in samples {
+            let url = format!("https://rateyourmusic.com/release/{}/artist/title/", kind);
+            let page = fetcher.fetch_release(&url).await.unwrap_or_else(|e| panic!("{}: {}", kind, e));
+            assert_eq!(page.rating.album_name, title, "{}", kind);
+            assert_eq!((page.rating.rym_rating, page.rating.rating_count), (rating, count), "{}", kind);
+            assert_eq!(page.rating.rym_url, url, "{}", kind);
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_missing_and_unparseable_pages() {
+        let server = sample_pages();
+        let fetcher = HttpFetcher::with_origin(&server.origin).unwrap();
+
+        let missing = "https://rateyourmusic.com/release/bootleg/artist/title/";
+        assert_eq!(fetcher.fetch_release(missing).await.unwrap_err(), format!("HTTP 404 for {}", missing));
+
+        // A search results page isn't a release
+        let search = "https://rateyourmusic.com/search?searchterm=kid+a&searchtype=l";
+        assert!(fetcher.fetch_page(search).await.unwrap().contains("Source URL: https://rateyourmusic.com/search"));
+        assert!(fetcher.fetch_release(search).await.is_err());
+    }
+}
@@ -0,0 +1,412 @@
+use crate::database::{AlbumRating, Review, TrackRating};
+use scraper::{ElementRef, Html, Selector};
+
+mod artist;
+mod chart;
+mod genre;
+mod list;
+mod new_music;
+mod search;
+mod song;
+
+pub use artist::parse_artist_page;
+pub use chart::parse_chart_page;
+pub use genre::{parse_genre_page, parse_genres_page};
+pub use list::{parse_list_page, parse_lists_page, ListPage, ListSummary};
+pub use new_music::parse_new_music_page;
+pub use search::{parse_search_page, rank_search_results, search_url, SearchResult};
+pub use song::parse_song_page;
+
+const RYM_BASE_URL: &str = "https://rateyourmusic.com";
+
+pub(crate) fn sel(selector: &str) -> Selector {
+    Selector::parse(selector).unwrap()
+}
+
+// Concatenate all text below an element and collapse runs of whitespace
+fn clean_text(el: ElementRef) -> String {
+    el.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Only the element's own text nodes (skips nested spans such as translated titles)
+fn own_text(el: ElementRef) -> String {
+    el.children()
+        .filter_map(|n| n.value().as_text().map(|t| t.to_string()))
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Prefer the original-script name over the transliteration shown next to it
+fn original_name(el: ElementRef) -> String {
+    el.select(&sel(".ui_name_locale_original"))
+        .next()
+        .map(clean_text)
+        .unwrap_or_else(|| clean_text(el))
+}
+
+fn parse_count(s: &str) -> Option<i32> {
+    s.trim().replace(',', "").parse::<i32>().ok()
+}
+
+// RYM links are site-relative ("/release/album/...")
+fn absolute_url(href: &str) -> String {
+    if href.starts_with("http") {
+        href.to_string()
+    } else if href.starts_with("//") {
+        format!("https:{}", href)
+    } else {
+        format!("{}{}", RYM_BASE_URL, href)
+    }
+}
+
+// "/charts/top/album/2025/3/" is page 3 of "/charts/top/album/2025/". Paged views stop
+// well before page 1000, so a four-digit segment is a year, not a page.
+pub fn first_page_url(url: &str) -> String {
+    let trimmed = url.trim_end_matches('/');
+    match trimmed.rsplit_once('/') {
+        Some((base, page)) if (1..=3).contains(&page.len()) && page.chars().all(|c| c.is_ascii_digit()) => {
+            format!("{}/", base)
+        }
+        _ => format!("{}/", trimmed),
+    }
+}
+
+fn join_links(root: ElementRef, selector: &Selector) -> String {
+    root.select(selector).map(clean_text).collect::<Vec<_>>().join(", ")
+}
+
+// Rows of the `album_info` table keyed by their header ("Released", "RYM Rating", ...)
+fn info_rows(doc: &Html) -> Vec<(String, ElementRef<'_>)> {
+    let row_sel = sel("table.album_info tr");
+    let hdr_sel = sel("th.info_hdr");
+    let td_sel = sel("td");
+
+    doc.select(&row_sel)
+        .filter_map(|row| {
+            let hdr = clean_text(row.select(&hdr_sel).next()?);
+            let td = row.select(&td_sel).next()?;
+            Some((hdr, td))
+        })
+        .collect()
+}
+
+fn info_row<'a>(rows: &[(String, ElementRef<'a>)], name: &str) -> Option<ElementRef<'a>> {
+    rows.iter().find(|(hdr, _)| hdr == name).map(|(_, td)| *td)
+}
+
+// Parse a RYM release page (album, EP, single, compilation, video...) into an AlbumRating.
+// `page_url` is used as `rym_url`; if empty, the canonical URL from the page metadata is used.
+pub fn parse_release_page(html: &str, page_url: &str) -> Result<AlbumRating, String> {
+    let doc = Html::parse_document(html);
+    let rows = info_rows(&doc);
+
+    let album_name = doc
+        .select(&sel(".album_title"))
+        .next()
+        .map(own_text)
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| "Release title not found".to_string())?;
+
+    let artist_name = info_row(&rows, "Artist")
+        .map(|td| join_links(td, &sel("a.artist")))
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| "Release artist not found".to_string())?;
+
+    let rating_td = info_row(&rows, "RYM Rating").ok_or_else(|| "RYM rating not found".to_string())?;
+    let rym_rating = rating_td
+        .select(&sel(".avg_rating"))
+        .next()
+        .and_then(|el| clean_text(el).parse::<f32>().ok())
+        .unwrap_or(0.0);
+    let rating_count = rating_td
+        .select(&sel(".num_ratings b"))
+        .next()
+        .and_then(|el| parse_count(&clean_text(el)))
+        .unwrap_or(0);
+
+    let rym_url = if page_url.is_empty() {
+        doc.select(&sel("meta[itemprop=\"url\"]"))
+            .next()
+            .and_then(|m| m.value().attr("content"))
+            .map(|path| format!("{}{}", RYM_BASE_URL, path))
+            .unwrap_or_default()
+    } else {
+        page_url.to_string()
+    };
+
+    let genres = doc
+        .select(&sel(".release_pri_genres"))
+        .next()
+        .map(|el| join_links(el, &sel("a.genre")))
+        .unwrap_or_default();
+    let secondary_genres = doc
+        .select(&sel(".release_sec_genres"))
+        .next()
+        .map(|el| join_links(el, &sel("a.genre")));
+    let descriptors = doc.select(&sel(".release_pri_descriptors")).next().map(|el| {
+        clean_text(el)
+            .split(',')
+            .map(|d| d.trim())
+            .filter(|d| !d.is_empty())
+            .collect::<Vec<_>>()
+            .join(", ")
+    });
+
+    let language = info_row(&rows, "Language").map(clean_text);
+    let rank = info_row(&rows, "Ranked").map(clean_text);
+    let release_date = info_row(&rows, "Released").map(clean_text).unwrap_or_default();
+
+    let tracks = parse_tracks(&doc);
+    let reviews = parse_reviews(&doc);
+
+    Ok(AlbumRating {
+        album_name,
+        artist_name,
+        rym_rating,
+        rating_count,
+        rym_url,
+        genres,
+        secondary_genres,
+        descriptors,
+        language,
+        rank,
+        track_ratings: tracks,
+        reviews,
+        release_date,
+        timestamp: chrono::Utc::now().timestamp(),
+        status: None,
+    })
+}
+
+fn parse_tracks(doc: &Html) -> Vec<TrackRating> {
+    // RYM renders the same tracklist for mobile and desktop; the first one is enough
+    let Some(list) = doc.select(&sel("ul.tracklisting")).next() else {
+        return Vec::new();
+    };
+
+    let num_sel = sel(".tracklist_num");
+    let song_sel = sel(".tracklist_title a.song");
+    let rendered_sel = sel(".tracklist_title .rendered_text");
+    let duration_sel = sel(".tracklist_duration");
+    let stats_sel = sel(".page_release_section_tracks_songs_song_stats");
+    let rating_sel = sel(".page_release_section_tracks_track_stats_rating");
+    let count_sel = sel(".page_release_section_tracks_track_stats_count");
+
+    list.select(&sel(".tracklist_line"))
+        .filter_map(|line| {
+            let title = line
+                .select(&song_sel)
+                .next()
+                .or_else(|| line.select(&rendered_sel).next())
+                .map(clean_text)
+                .filter(|t| !t.is_empty())?;
+            let number = line.select(&num_sel).next().map(clean_text).unwrap_or_default();
+            let duration = line.select(&duration_sel).next().map(clean_text).unwrap_or_default();
+
+            // The tooltip ("3.73 from 347 ratings") carries two decimals, the visible score only one
+            let tip = line
+                .select(&stats_sel)
+                .next()
+                .and_then(|el| el.value().attr("data-tiptip"))
+                .map(|t| t.split_whitespace().collect::<Vec<_>>())
+                .unwrap_or_default();
+            let rating = tip
+                .first()
+                .and_then(|r| r.parse::<f32>().ok())
+                .or_else(|| line.select(&rating_sel).next().and_then(|el| clean_text(el).parse().ok()));
+            let count = tip
+                .get(2)
+                .and_then(|c| parse_count(c))
+                .or_else(|| line.select(&count_sel).next().and_then(|el| parse_count(&clean_text(el))));
+
+            Some(TrackRating {
+                number,
+                title,
+                duration,
+                rating,
+                count,
+            })
+        })
+        .collect()
+}
+
+fn parse_reviews(doc: &Html) -> Vec<Review> {
+    let user_sel = sel(".review_header .user");
+    let date_sel = sel(".review_header .review_date");
+    let rating_sel = sel(".review_header .review_rating img");
+    let body_sel = sel(".review_body");
+
+    doc.select(&sel("div.review"))
+        .filter_map(|review| {
+            let reviewer = review.select(&user_sel).next().map(clean_text)?;
+            let body = review.select(&body_sel).next().map(clean_text).unwrap_or_default();
+            let date = review.select(&date_sel).next().map(clean_text).unwrap_or_default();
+            // Star images carry the score in their alt text, e.g. "4.50 stars"
+            let rating = review
+                .select(&rating_sel)
+                .next()
+                .and_then(|img| img.value().attr("alt"))
+                .and_then(|alt| alt.split_whitespace().next())
+                .and_then(|r| r.parse::<f32>().ok());
+            let id = review.value().id().map(|id| id.trim_start_matches("rvw_").to_string()).unwrap_or_default();
+
+            Some(Review {
+                id,
+                reviewer,
+                date,
+                rating,
+                body,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_album_page() {
+        let html = include_str!("../../sample_pages/release_album_sample.html");
+        let r = parse_release_page(html, "").unwrap();
+
+        assert_eq!(r.album_name, "星間性交");
+        assert_eq!(r.artist_name, "t e l e p a t h テレパシー能力者");
+        assert_eq!(r.rym_rating, 3.87);
+        assert_eq!(r.rating_count, 3598);
+        assert_eq!(r.rym_url, "https://rateyourmusic.com/release/album/t-e-l-e-p-a-t-h-テレパシー能力者/星間性交/");
+        assert_eq!(r.genres, "Slushwave, Ambient");
+        assert_eq!(r.secondary_genres.as_deref(), Some("Dreampunk, Ambient Dub"));
+        assert!(r.descriptors.as_deref().unwrap().starts_with("nocturnal, atmospheric, soothing"));
+        assert_eq!(r.language.as_deref(), Some("Japanese"));
+        assert_eq!(r.rank.as_deref(), Some("#10 for 2015, #1,330 overall"));
+        assert_eq!(r.release_date, "21 December 2015");
+
+        let tracks = &r.track_ratings;
+        assert_eq!(tracks.len(), 11);
+        assert_eq!(tracks[0], TrackRating {
+            number: "1".to_string(),
+            title: "心を入力して".to_string(),
+            duration: "4:44".to_string(),
+            rating: Some(3.73),
+            count: Some(347),
+        });
+        assert!(r.reviews.is_empty());
+    }
+
+    #[test]
+    fn parses_reviews_in_a_release_page() {
+        // The fixture was saved before any reviews loaded, so these blocks follow RYM's
+        // review markup rather than being captured from the page
+        let reviews = r#"<div class="review" id="rvw_1001"><div class="review_header">
+            <a class="user">dreamer</a> <span class="review_date">3 January 2016</span>
+            <span class="review_rating"><img alt="4.50 stars"></span></div>
+            <div class="review_body">Late night <b>drive</b> music.</div></div>
+            <div class="review"><div class="review_header"><a class="user">anon</a></div>
+            <div class="review_body">No id, no stars</div></div>"#;
+        let html = include_str!("../../sample_pages/release_album_sample.html").replace("</body>", &format!("{}</body>", reviews));
+
+        let r = parse_release_page(&html, "").unwrap();
+        assert_eq!(r.reviews, vec![
+            Review {
+                id: "1001".to_string(),
+                reviewer: "dreamer".to_string(),
+                date: "3 January 2016".to_string(),
+                rating: Some(4.5),
+                body: "Late night drive music.".to_string(),
+            },
+            Review {
+                id: String::new(),
+                reviewer: "anon".to_string(),
+                date: String::new(),
+                rating: None,
+                body: "No id, no stars".to_string(),
+            },
+        ]);
+        assert_eq!(r.reviews[1].stable_id(), "user:anon");
+    }
+
+    #[test]
+    fn parses_ep_page() {
+        let html = include_str!("../../sample_pages/release_ep_sample.html");
+        let r = parse_release_page(html, "https://rateyourmusic.com/release/ep/blut-aus-nord/what-once-was-liber-iii/").unwrap();
+
+        assert_eq!(r.artist_name, "Blut aus Nord");
+        assert_eq!(r.release_date, "18 October 2013");
+        assert_eq!(r.rym_url, "https://rateyourmusic.com/release/ep/blut-aus-nord/what-once-was-liber-iii/");
+        assert!(r.rym_rating > 0.0);
+        assert!(r.rating_count > 0);
+        assert!(!r.genres.is_empty());
+        assert_eq!(r.track_ratings.len(), 6);
+    }
+
+    #[test]
+    fn parses_single_page() {
+        let html = include_str!("../../sample_pages/release_single_sample.html");
+        let r = parse_release_page(html, "").unwrap();
+
+        assert_eq!(r.album_name, "Rockin' Around the Christmas Tree / Papa Noël");
+        assert_eq!(r.artist_name, "Brenda Lee");
+        assert_eq!(r.rym_rating, 3.48);
+        assert_eq!(r.rating_count, 506);
+        assert_eq!(r.genres, "Christmas Music, Rock & Roll, Pop Rock");
+        assert_eq!(r.secondary_genres.as_deref(), Some("Rockabilly, Standards"));
+        assert_eq!(r.descriptors.as_deref(), Some("Christmas, female vocalist, happy, uplifting, melodic, playful, party, rhythmic, soft"));
+        assert_eq!(r.rank.as_deref(), Some("#47 for 1958, #9,063 overall"));
+        assert_eq!(r.release_date, "17 November 1958");
+
+        let tracks = &r.track_ratings;
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[0].number, "A");
+        assert_eq!(tracks[0].title, "Rockin' Around the Christmas Tree");
+        assert_eq!(tracks[0].count, Some(79));
+    }
+
+    #[test]
+    fn parses_comp_page_with_unrated_subtracks() {
+        let html = include_str!("../../sample_pages/release_comp_sample.html");
+        let r = parse_release_page(html, "").unwrap();
+
+        assert_eq!(r.artist_name, "Catherine Wheel");
+        assert_eq!(r.release_date, "10 September 1996");
+        assert_eq!(r.language.as_deref(), Some("English"));
+
+        let tracks = &r.track_ratings;
+        assert_eq!(tracks[0].title, "Heal 2");
+        assert_eq!(tracks.iter().filter(|t| t.rating.is_some()).count(), 13);
+        assert!(tracks.iter().any(|t| t.title == "- [silence]" && t.rating.is_none()));
+    }
+
+    #[test]
+    fn parses_video_page_without_tracks() {
+        let html = include_str!("../../sample_pages/release_video_sample.html");
+        let r = parse_release_page(html, "").unwrap();
+
+        assert_eq!(r.album_name, "Camp Flog Gnaw 2025");
+        assert_eq!(r.artist_name, "Earl Sweatshirt");
+        assert_eq!(r.rym_rating, 4.30);
+        assert_eq!(r.rating_count, 12);
+        assert_eq!(r.genres, "Abstract Hip Hop");
+        assert_eq!(r.secondary_genres, None);
+        assert_eq!(r.descriptors.as_deref(), Some("male vocalist"));
+        assert_eq!(r.rank.as_deref(), Some("#5,746 overall, #2,815 for live"));
+        assert_eq!(r.release_date, "4 December 2025");
+        assert!(r.track_ratings.is_empty());
+    }
+
+    #[test]
+    fn strips_page_number_from_paged_urls() {
+        assert_eq!(first_page_url("https://rateyourmusic.com/charts/top/album/2025/3/"), "https://rateyourmusic.com/charts/top/album/2025/");
+        assert_eq!(first_page_url("https://rateyourmusic.com/charts/top/album/2025"), "https://rateyourmusic.com/charts/top/album/2025/");
+        assert_eq!(first_page_url("https://rateyourmusic.com/charts/top/album/1990s/"), "https://rateyourmusic.com/charts/top/album/1990s/");
+        assert_eq!(first_page_url("https://rateyourmusic.com/list/MarilynRoxie/rym25/2/"), "https://rateyourmusic.com/list/MarilynRoxie/rym25/");
+    }
+
+    #[test]
+    fn rejects_non_release_page() {
+        let html = include_str!("../../sample_pages/artist_sample.html");
+        assert!(parse_release_page(html, "").is_err());
+    }
+}
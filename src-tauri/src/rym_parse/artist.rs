@@ -0,0 +1,181 @@
+use super::{absolute_url, clean_text, info_row, join_links, parse_count, sel, RYM_BASE_URL};
+use crate::database::{Artist, ArtistRelease};
+use scraper::{ElementRef, Html};
+
+// Discography sections are `div#disco_type_<code>`
+const DISCO_TYPES: &[(&str, &str)] = &[
+    ("s", "album"),
+    ("l", "live"),
+    ("e", "ep"),
+    ("i", "single"),
+    ("c", "compilation"),
+    ("v", "video"),
+    ("b", "bootleg"),
+    ("a", "appears_on"),
+];
+
+// Header/content pairs of the artist info block ("Formed", "Born", "Genres", ...)
+fn artist_info(doc: &Html) -> Vec<(String, ElementRef<'_>)> {
+    doc.select(&sel(".artist_info_main .info_hdr"))
+        .filter_map(|hdr| {
+            let content = hdr.next_siblings().find_map(ElementRef::wrap)?;
+            content.value().classes().any(|c| c == "info_content").then(|| (clean_text(hdr), content))
+        })
+        .collect()
+}
+
+// Parse a RYM artist page into an Artist with its discography.
+// `page_url` is used as `rym_url`; if empty, the canonical URL from the page metadata is used.
+pub fn parse_artist_page(html: &str, page_url: &str) -> Result<Artist, String> {
+    let doc = Html::parse_document(html);
+    let info = artist_info(&doc);
+
+    let name = doc
+        .select(&sel("h1.artist_name_hdr"))
+        .next()
+        .map(clean_text)
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| "Artist name not found".to_string())?;
+
+    let rym_url = if page_url.is_empty() {
+        doc.select(&sel(".artist_page meta[itemprop=\"url\"]"))
+            .next()
+            .and_then(|m| m.value().attr("content"))
+            .map(|path| format!("{}{}", RYM_BASE_URL, path))
+            .unwrap_or_default()
+    } else {
+        page_url.to_string()
+    };
+
+    let genres = info_row(&info, "Genres")
+        .map(|el| join_links(el, &sel("a.genre")))
+        .unwrap_or_default();
+
+    Ok(Artist {
+        name,
+        rym_url,
+        formed: info_row(&info, "Formed").map(clean_text),
+        born: info_row(&info, "Born").map(clean_text),
+        genres,
+        releases: parse_discography(&doc),
+        timestamp: chrono::Utc::now().timestamp(),
+    })
+}
+
+fn parse_discography(doc: &Html) -> Vec<ArtistRelease> {
+    let release_sel = sel(".disco_release");
+    let title_sel = sel(".disco_mainline a.album");
+    let year_sel = sel(".disco_subline [class^=\"disco_year\"]");
+    let avg_sel = sel(".disco_avg_rating");
+    let count_sel = sel(".disco_ratings");
+
+    let mut releases = Vec::new();
+    for section in doc.select(&sel("div[id^=\"disco_type_\"]")) {
+        let code = section.value().id().unwrap_or_default().trim_start_matches("disco_type_");
+        let Some((_, release_type)) = DISCO_TYPES.iter().find(|(c, _)| *c == code) else {
+            continue;
+        };
+
+        for release in section.select(&release_sel) {
+            let Some(link) = release.select(&title_sel).next() else {
+                continue;
+            };
+            // "2013" with the full date ("11 June 2013") in the title attribute
+            let year = release
+                .select(&year_sel)
+                .next()
+                .and_then(|el| clean_text(el).get(..4).and_then(|y| y.parse::<i32>().ok()));
+
+            releases.push(ArtistRelease {
+                release_type: release_type.to_string(),
+                title: clean_text(link),
+                rym_url: link.value().attr("href").map(absolute_url).unwrap_or_default(),
+                year,
+                rating: release.select(&avg_sel).next().and_then(|el| clean_text(el).parse().ok()),
+                count: release.select(&count_sel).next().and_then(|el| parse_count(&clean_text(el))),
+            });
+        }
+    }
+    releases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_artist_page() {
+        let html = include_str!("../../../sample_pages/artist_sample.html");
+        let a = parse_artist_page(html, "").unwrap();
+
+        assert_eq!(a.name, "Deafheaven");
+        assert_eq!(a.rym_url, "https://rateyourmusic.com/artist/deafheaven/");
+        assert_eq!(a.formed.as_deref(), Some("February 2010, San Francisco, CA, United States"));
+        assert_eq!(a.born, None);
+        assert_eq!(a.genres, "Blackgaze, Post-Metal, Shoegaze, Atmospheric Black Metal, Dream Pop, Black Metal");
+        // The saved sample was captured before the discography loaded
+        assert!(a.releases.is_empty());
+    }
+
+    // Hand-written from a live artist page: none of the saved samples include discography
+    // markup, so this only checks the selectors against what RYM served when they were written
+    #[test]
+    fn parses_discography_by_type() {
+        let html = r#"
+            <div class="artist_page">
+              <h1 class="artist_name_hdr">Deafheaven</h1>
+              <div id="discography">
+                <div id="disco_type_s">
+                  <div class="disco_header_top"><h3>Album</h3></div>
+                  <div class="disco_release">
+                    <div class="disco_info">
+                      <div class="disco_mainline"><a title="[Album5181413]" href="/release/album/deafheaven/sunbather/" class="album">Sunbather</a></div>
+                      <div class="disco_subline"><span title="11 June 2013" class="disco_year_ymd">2013</span></div>
+                    </div>
+                    <div class="disco_avg_rating">3.72</div>
+                    <div class="disco_ratings">24,811</div>
+                  </div>
+                  <div class="disco_release">
+                    <div class="disco_info">
+                      <div class="disco_mainline"><a href="/release/album/deafheaven/lonely-people-with-power/" class="album">Lonely People With Power</a></div>
+                      <div class="disco_subline"><span class="disco_year_y">2025</span></div>
+                    </div>
+                    <div class="disco_avg_rating"></div>
+                    <div class="disco_ratings"></div>
+                  </div>
+                </div>
+                <div id="disco_type_e">
+                  <div class="disco_release">
+                    <div class="disco_info">
+                      <div class="disco_mainline"><a href="/release/ep/deafheaven/from-the-kettle-onto-the-coil/" class="album">From the Kettle Onto the Coil</a></div>
+                      <div class="disco_subline"><span title="2015" class="disco_year_y">2015</span></div>
+                    </div>
+                    <div class="disco_avg_rating">3.31</div>
+                    <div class="disco_ratings">1,204</div>
+                  </div>
+                </div>
+              </div>
+            </div>"#;
+        let a = parse_artist_page(html, "https://rateyourmusic.com/artist/deafheaven").unwrap();
+
+        assert_eq!(a.releases.len(), 3);
+        assert_eq!(a.releases[0], ArtistRelease {
+            release_type: "album".to_string(),
+            title: "Sunbather".to_string(),
+            rym_url: "https://rateyourmusic.com/release/album/deafheaven/sunbather/".to_string(),
+            year: Some(2013),
+            rating: Some(3.72),
+            count: Some(24811),
+        });
+        assert_eq!(a.releases[1].rating, None);
+        assert_eq!(a.releases[1].count, None);
+        assert_eq!(a.releases[2].release_type, "ep");
+        assert_eq!(a.releases[2].year, Some(2015));
+    }
+
+    #[test]
+    fn rejects_non_artist_page() {
+        let html = include_str!("../../../sample_pages/release_album_sample.html");
+        assert!(parse_artist_page(html, "").is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use super::{absolute_url, clean_text, first_page_url, join_links, parse_count, sel};
+use crate::database::{Chart, ChartEntry};
+use chrono::{Datelike, NaiveDate};
+use scraper::{ElementRef, Html};
+
+// The version selector shows "Weekly: 16 Dec" without a year; it is the most recent such day
+fn resolve_chart_date(label: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let day_month = label.rsplit(':').next()?.trim();
+    let this_year = NaiveDate::parse_from_str(&format!("{} {}", day_month, today.year()), "%d %b %Y").ok()?;
+    if this_year > today {
+        NaiveDate::parse_from_str(&format!("{} {}", day_month, today.year() - 1), "%d %b %Y").ok()
+    } else {
+        Some(this_year)
+    }
+}
+
+// Parse one page of a RYM chart. Entries from later pages of the same chart share
+// its base URL and date, so they land in the same snapshot.
+pub fn parse_chart_page(html: &str, page_url: &str) -> Result<Chart, String> {
+    let doc = Html::parse_document(html);
+
+    let title = doc
+        .select(&sel("#page_charts_section_charts_header_chart_name"))
+        .next()
+        .map(clean_text)
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| "Chart title not found".to_string())?;
+
+    let url = if page_url.is_empty() {
+        doc.select(&sel(".page_charts_section_charts_version_selector a.selected"))
+            .next()
+            .and_then(|a| a.value().attr("href"))
+            .map(absolute_url)
+            .unwrap_or_default()
+    } else {
+        page_url.to_string()
+    };
+
+    let today = chrono::Utc::now().date_naive();
+    let chart_date = doc
+        .select(&sel(".page_charts_section_charts_version_selector a.selected"))
+        .next()
+        .and_then(|a| resolve_chart_date(&clean_text(a), today))
+        .unwrap_or(today);
+
+    let entries = doc
+        .select(&sel(".page_charts_section_charts_item"))
+        .filter_map(parse_entry)
+        .collect();
+
+    Ok(Chart {
+        title,
+        rym_url: first_page_url(&url),
+        chart_date: chart_date.format("%Y-%m-%d").to_string(),
+        entries,
+        timestamp: chrono::Utc::now().timestamp(),
+    })
+}
+
+fn parse_entry(item: ElementRef) -> Option<ChartEntry> {
+    let text = |selector: &str| item.select(&sel(selector)).next().map(clean_text).filter(|s| !s.is_empty());
+
+    let position = text(".page_charts_section_charts_item_number")?.trim_end_matches('.').parse().ok()?;
+    let link = item.select(&sel(".page_charts_section_charts_item_title a")).next()?;
+    let release_date = text(".page_charts_section_charts_item_date").unwrap_or_default();
+    // "11 July 2025", "July 2025" or "2025"
+    let year = release_date.split_whitespace().last().and_then(|y| y.parse().ok());
+
+    Some(ChartEntry {
+        position,
+        artist_name: item
+            .select(&sel(".page_charts_section_charts_item_credited_links_primary"))
+            .next()
+            .map(|el| join_links(el, &sel("a.artist")))
+            .unwrap_or_default(),
+        release_name: clean_text(link),
+        rym_url: link.value().attr("href").map(absolute_url).unwrap_or_default(),
+        year,
+        release_date,
+        genres: item
+            .select(&sel(".page_charts_section_charts_item_genres_primary"))
+            .next()
+            .map(|el| join_links(el, &sel("a.genre")))
+            .unwrap_or_default(),
+        secondary_genres: item
+            .select(&sel(".page_charts_section_charts_item_genres_secondary"))
+            .next()
+            .map(|el| join_links(el, &sel("a.genre")))
+            .filter(|s| !s.is_empty()),
+        rating: text(".page_charts_section_charts_item_details_average_num").and_then(|r| r.parse().ok()),
+        // The abbreviated count ("12k") sits next to the exact one
+        count: text(".page_charts_section_charts_item_details_ratings .full").and_then(|c| parse_count(&c)),
+        reviews: text(".page_charts_section_charts_item_details_reviews .full").and_then(|c| parse_count(&c)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_chart_header_from_sample() {
+        let html = include_str!("../../../sample_pages/charts_sample.html");
+        let chart = parse_chart_page(html, "").unwrap();
+
+        assert_eq!(chart.title, "Top albums of 2025");
+        assert_eq!(chart.rym_url, "https://rateyourmusic.com/charts/top/album/2025/");
+        assert!(chart.chart_date.ends_with("-12-16"), "{}", chart.chart_date);
+        // The saved sample was captured before the chart items loaded
+        assert!(chart.entries.is_empty());
+    }
+
+    // Hand-written from a live chart page: none of the saved samples include chart items,
+    // so this only checks the selectors against what RYM served when they were written
+    #[test]
+    fn parses_chart_entries() {
+        let html = r#"
+            <h1 id="page_charts_section_charts_header_chart_name">Top albums of 2025</h1>
+            <div class="page_charts_section_charts_version_selector"><a href="/charts/top/album/2025/" class="selected">Weekly: 16 Dec</a></div>
+            <section id="page_charts_section_charts">
+              <div id="pos101" class="page_charts_section_charts_item object_release">
+                <div class="page_charts_section_charts_item_number">101</div>
+                <div class="page_charts_section_charts_item_info">
+                  <div class="page_charts_section_charts_item_title">
+                    <a class="page_charts_section_charts_item_link release" href="/release/album/deafheaven/lonely-people-with-power/"><span class="ui_name_locale_original">Lonely People With Power</span></a>
+                  </div>
+                  <div class="page_charts_section_charts_item_credited_links_primary">
+                    <a class="artist" href="/artist/deafheaven"><span class="ui_name_locale_original">Deafheaven</span></a>
+                  </div>
+                  <div class="page_charts_section_charts_item_date"><span>28 March 2025</span></div>
+                  <div class="page_charts_section_charts_item_genres_primary"><a class="genre comma_separated" href="/genre/blackgaze/">Blackgaze</a></div>
+                  <div class="page_charts_section_charts_item_genres_secondary"><a class="genre comma_separated" href="/genre/post-metal/">Post-Metal</a>, <a class="genre comma_separated" href="/genre/shoegaze/">Shoegaze</a></div>
+                  <div class="page_charts_section_charts_item_stats">
+                    <span class="page_charts_section_charts_item_details_average_num">3.76</span>
+                    <span class="page_charts_section_charts_item_details_ratings"><span class="abbr">9k</span><span class="full">9,102</span></span>
+                    <span class="page_charts_section_charts_item_details_reviews"><span class="abbr">86</span><span class="full">86</span></span>
+                  </div>
+                </div>
+              </div>
+            </section>"#;
+        let chart = parse_chart_page(html, "https://rateyourmusic.com/charts/top/album/2025/2/").unwrap();
+
+        assert_eq!(chart.rym_url, "https://rateyourmusic.com/charts/top/album/2025/");
+        assert_eq!(chart.entries, vec![ChartEntry {
+            position: 101,
+            artist_name: "Deafheaven".to_string(),
+            release_name: "Lonely People With Power".to_string(),
+            rym_url: "https://rateyourmusic.com/release/album/deafheaven/lonely-people-with-power/".to_string(),
+            year: Some(2025),
+            release_date: "28 March 2025".to_string(),
+            genres: "Blackgaze".to_string(),
+            secondary_genres: Some("Post-Metal, Shoegaze".to_string()),
+            rating: Some(3.76),
+            count: Some(9102),
+            reviews: Some(86),
+        }]);
+    }
+
+    #[test]
+    fn resolves_weekly_label_to_a_date() {
+        let ymd = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(resolve_chart_date("Weekly: 16 Dec", ymd(2025, 12, 22)), Some(ymd(2025, 12, 16)));
+        // Early January still shows the last chart of December
+        assert_eq!(resolve_chart_date("Weekly: 30 Dec", ymd(2026, 1, 2)), Some(ymd(2025, 12, 30)));
+        assert_eq!(resolve_chart_date("Daily", ymd(2026, 1, 2)), None);
+    }
+
+    #[test]
+    fn rejects_non_chart_page() {
+        let html = include_str!("../../../sample_pages/artist_sample.html");
+        assert!(parse_chart_page(html, "").is_err());
+    }
+}
@@ -0,0 +1,162 @@
+use super::{absolute_url, clean_text, original_name, sel};
+use crate::database::{Genre, GenreLink, GenreRelease};
+use scraper::Html;
+
+// Parse a RYM genre page. The breadcrumb gives the genre's parents (outermost first);
+// its children link back to it from their own pages.
+// `page_url` is used as `rym_url`; if empty, the URL from the share box is used.
+pub fn parse_genre_page(html: &str, page_url: &str) -> Result<Genre, String> {
+    let doc = Html::parse_document(html);
+    let text = |selector: &str| doc.select(&sel(selector)).next().map(clean_text).filter(|s| !s.is_empty());
+
+    let name = text("#page_genre_section_name h1").ok_or_else(|| "Genre name not found".to_string())?;
+
+    let rym_url = if page_url.is_empty() {
+        doc.select(&sel("#page_genre_section_share input[aria-label=\"URL\"]"))
+            .next()
+            .and_then(|i| i.value().attr("value"))
+            .unwrap_or_default()
+            .to_string()
+    } else {
+        page_url.to_string()
+    };
+
+    let parents = doc
+        .select(&sel("#page_genre_breadcrumb li.relationship_parent a"))
+        .map(|a| GenreLink {
+            name: clean_text(a),
+            rym_url: a.value().attr("href").map(absolute_url).unwrap_or_default(),
+        })
+        .collect();
+
+    // "AKA: Phaserwave, Dreamtone • 1,203 releases"
+    let release_count = text("#page_genre_section_name .page_genre_akas").and_then(|akas| {
+        let count = akas.rsplit('•').next()?.trim().strip_suffix("releases")?;
+        count.trim().replace(',', "").parse().ok()
+    });
+    let akas = doc
+        .select(&sel("#page_genre_section_name .page_genre_akas bdi"))
+        .map(clean_text)
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Ok(Genre {
+        name,
+        rym_url,
+        parents,
+        summary: text("#page_genre_description_short .rendered_text"),
+        description: text("#page_genre_description_full .rendered_text"),
+        akas,
+        release_count,
+        top_level: false,
+        top_releases: parse_top_releases(&doc),
+        timestamp: chrono::Utc::now().timestamp(),
+    })
+}
+
+// The "Top-ranked albums" carousel
+fn parse_top_releases(doc: &Html) -> Vec<GenreRelease> {
+    doc.select(&sel("#page_genre_section_charts .page_section_charts_carousel_item"))
+        .filter_map(|item| {
+            let link = item.select(&sel(".page_section_charts_carousel_title a.release")).next()?;
+            let artist = item.select(&sel(".page_section_charts_carousel_title a.artist")).next();
+            Some(GenreRelease {
+                // "#1"
+                position: item
+                    .select(&sel(".page_section_charts_carousel_number"))
+                    .next()
+                    .and_then(|n| clean_text(n).trim_start_matches('#').parse().ok())?,
+                title: clean_text(link),
+                artist_name: artist.map(original_name).unwrap_or_default(),
+                rym_url: link.value().attr("href").map(absolute_url).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+// Parse the genre index (/genres/): the top-level genres plus the uncategorized ones
+// that have no parent. Subgenres are loaded on demand and aren't on the page.
+pub fn parse_genres_page(html: &str) -> Vec<Genre> {
+    let doc = Html::parse_document(html);
+    let timestamp = chrono::Utc::now().timestamp();
+
+    doc.select(&sel(".page_genre_index_hierarchy .page_genre_index_hierarchy_item"))
+        .filter_map(|item| {
+            let link = item.select(&sel(".page_genre_index_hierarchy_item_main_inner h2 a")).next()?;
+            Some(Genre {
+                name: clean_text(link),
+                rym_url: link.value().attr("href").map(absolute_url).unwrap_or_default(),
+                parents: Vec::new(),
+                summary: item
+                    .select(&sel(".page_genre_index_hierarchy_item_description"))
+                    .next()
+                    .map(clean_text)
+                    .filter(|s| !s.is_empty()),
+                description: None,
+                akas: String::new(),
+                release_count: None,
+                top_level: !item.value().classes().any(|c| c == "parentless_non_top_level"),
+                top_releases: Vec::new(),
+                timestamp,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_genre_page() {
+        let html = include_str!("../../../sample_pages/genre_sample.html");
+        let g = parse_genre_page(html, "").unwrap();
+
+        assert_eq!(g.name, "Slushwave");
+        assert_eq!(g.rym_url, "https://rateyourmusic.com/genre/slushwave/");
+        assert_eq!(g.parents, vec![GenreLink {
+            name: "Vaporwave".to_string(),
+            rym_url: "https://rateyourmusic.com/genre/vaporwave/".to_string(),
+        }]);
+        assert_eq!(g.akas, "Phaserwave, Dreamtone");
+        assert_eq!(g.release_count, Some(1203));
+        assert!(g.summary.unwrap().starts_with("Utilizes a distinct form of sample manipulation"));
+        assert!(g.description.unwrap().starts_with("Slushwave is a subgenre of Vaporwave"));
+
+        assert_eq!(g.top_releases.len(), 10);
+        assert_eq!(g.top_releases[0], GenreRelease {
+            position: 1,
+            title: "星間性交".to_string(),
+            artist_name: "t e l e p a t h テレパシー能力者".to_string(),
+            rym_url: "https://rateyourmusic.com/release/album/t-e-l-e-p-a-t-h-テレパシー能力者/星間性交/".to_string(),
+        });
+        // Artists without a separate original-script span
+        assert_eq!(g.top_releases[1].artist_name, "虚拟梦想广场");
+        // Transliterated artists keep the original name
+        assert_eq!(g.top_releases[3].artist_name, "泰合志恒");
+    }
+
+    #[test]
+    fn parses_genre_index() {
+        let html = include_str!("../../../sample_pages/genres_sample.html");
+        let genres = parse_genres_page(html);
+
+        let top: Vec<_> = genres.iter().filter(|g| g.top_level).collect();
+        assert_eq!(top.len(), 25);
+        assert_eq!(top[0].name, "Ambient");
+        assert_eq!(top[0].rym_url, "https://rateyourmusic.com/genre/ambient/");
+        assert!(top[0].summary.as_deref().unwrap().starts_with("Emphasizes texture and tone"));
+        assert!(top.iter().any(|g| g.name == "Jazz"));
+        assert!(top.iter().any(|g| g.name == "R&B"));
+
+        let uncategorized: Vec<_> = genres.iter().filter(|g| !g.top_level).map(|g| g.name.as_str()).collect();
+        assert_eq!(uncategorized.len(), 24);
+        assert_eq!(uncategorized[0], "Ambient Pop");
+    }
+
+    #[test]
+    fn rejects_non_genre_page() {
+        let html = include_str!("../../../sample_pages/artist_sample.html");
+        assert!(parse_genre_page(html, "").is_err());
+    }
+}
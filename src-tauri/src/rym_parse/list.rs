@@ -0,0 +1,260 @@
+use super::{absolute_url, clean_text, first_page_url, join_links, sel};
+use crate::database::{Collection, CollectionEntry};
+use scraper::{ElementRef, Html};
+use serde::Serialize;
+
+// One page of a RYM list. Lists are paginated, so the links to the other pages come
+// along for importing the rest.
+#[derive(Debug, Clone, Serialize)]
+pub struct ListPage {
+    pub collection: Collection,
+    pub pages: Vec<String>,
+    pub next_page: Option<String>,
+}
+
+// A list as shown on the /lists/ portal
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ListSummary {
+    pub title: String,
+    pub author: String,
+    pub rym_url: String,
+    pub item_count: Option<i32>,
+    pub updated: String,
+    pub description: Option<String>,
+}
+
+// Parse one page of a RYM user list. `page_url` is used as the list URL (without
+// its page number); if empty, the canonical URL from the page is used.
+pub fn parse_list_page(html: &str, page_url: &str) -> Result<ListPage, String> {
+    let doc = Html::parse_document(html);
+
+    let url = if page_url.is_empty() {
+        doc.select(&sel("link[rel=\"canonical\"]"))
+            .next()
+            .and_then(|l| l.value().attr("href"))
+            .unwrap_or_default()
+            .to_string()
+    } else {
+        page_url.to_string()
+    };
+    if !url.contains("/list/") {
+        return Err("Not a list page".to_string());
+    }
+
+    let title = doc
+        .select(&sel("#content h1"))
+        .next()
+        .map(clean_text)
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| "List title not found".to_string())?;
+
+    // "A list by <a class="user">"
+    let author = doc.select(&sel("#content p > a.user")).next().map(clean_text).unwrap_or_default();
+    let description = doc
+        .select(&sel("#content .columns > span.rendered_text"))
+        .next()
+        .map(clean_text)
+        .filter(|s| !s.is_empty());
+
+    let entries: Vec<CollectionEntry> = doc
+        .select(&sel("#user_list tr"))
+        .filter_map(parse_entry)
+        .collect();
+
+    let pages = doc
+        .select(&sel(".navspan a.navlinknum"))
+        .filter_map(|a| a.value().attr("href"))
+        .map(absolute_url)
+        .collect();
+    let next_page = doc
+        .select(&sel(".navspan a.navlinknext"))
+        .next()
+        .and_then(|a| a.value().attr("href"))
+        .map(absolute_url);
+
+    Ok(ListPage {
+        collection: Collection {
+            id: 0,
+            title,
+            author,
+            rym_url: first_page_url(&url),
+            description,
+            entry_count: entries.len() as u32,
+            entries,
+            cursor: 0,
+            timestamp: chrono::Utc::now().timestamp(),
+        },
+        pages,
+        next_page,
+    })
+}
+
+fn parse_entry(row: ElementRef) -> Option<CollectionEntry> {
+    let main = row.select(&sel("td.main_entry")).next()?;
+    let link = main.select(&sel("a.list_album, a.list_song")).next()?;
+    let href = link.value().attr("href").unwrap_or_default();
+    let position = row
+        .select(&sel("td.number"))
+        .next()
+        .and_then(|td| clean_text(td).trim_end_matches('.').parse().ok())?;
+
+    // "/release/album/radiohead/kid-a/" -> "album", "/song/..." -> "song"
+    let mut segments = href.trim_start_matches('/').split('/');
+    let entry_type = match segments.next() {
+        Some("release") => segments.next().unwrap_or("album"),
+        Some(kind) => kind,
+        None => "",
+    };
+
+    // "(2000)" next to albums, "2005" next to songs
+    let year = main
+        .select(&sel(".rel_date, .list_song_title_date"))
+        .next()
+        .and_then(|el| clean_text(el).trim_matches(|c| c == '(' || c == ')').get(..4)?.parse().ok());
+
+    Some(CollectionEntry {
+        position,
+        entry_type: entry_type.to_string(),
+        artist_name: join_links(main, &sel("a.list_artist")),
+        title: clean_text(link),
+        rym_url: absolute_url(href),
+        year,
+        description: main
+            .select(&sel(".generic_item"))
+            .next()
+            .map(clean_text)
+            .filter(|s| !s.is_empty()),
+    })
+}
+
+// Parse the lists portal (/lists/, /lists/popular/, ...) into list summaries
+pub fn parse_lists_page(html: &str) -> Vec<ListSummary> {
+    let doc = Html::parse_document(html);
+    let text = |item: ElementRef, selector: &str| item.select(&sel(selector)).next().map(clean_text).filter(|s| !s.is_empty());
+
+    doc.select(&sel(".listportal_itembox"))
+        .filter_map(|item| {
+            let link = item.select(&sel("a.listportal_item_title")).next()?;
+            Some(ListSummary {
+                title: clean_text(link),
+                author: text(item, ".listportal_item_author").unwrap_or_default(),
+                rym_url: link.value().attr("href").map(absolute_url).unwrap_or_default(),
+                // "383 items"
+                item_count: text(item, ".listportal_item_numitems")
+                    .and_then(|n| n.split_whitespace().next()?.replace(',', "").parse().ok()),
+                updated: text(item, ".listportal_item_date").unwrap_or_default(),
+                description: text(item, ".listportal_item_description .rendered_text"),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_list_header_from_sample() {
+        let html = include_str!("../../../sample_pages/list_sample.html");
+        let page = parse_list_page(html, "").unwrap();
+        let list = page.collection;
+
+        assert_eq!(list.title, "RYM25");
+        assert_eq!(list.author, "MarilynRoxie");
+        assert_eq!(list.rym_url, "https://rateyourmusic.com/list/MarilynRoxie/rym25/");
+        assert!(list.description.unwrap().starts_with("We have just launched our Best of 2025 giveaway."));
+        // The saved sample was captured before the list items loaded
+        assert!(list.entries.is_empty());
+        assert!(page.next_page.is_none());
+    }
+
+    // Hand-written from a live list page: none of the saved samples include list rows,
+    // so this only checks the selectors against what RYM served when they were written
+    #[test]
+    fn parses_list_entries_and_pagination() {
+        let html = r#"
+            <link rel="canonical" href="https://rateyourmusic.com/list/someone/favourites/" />
+            <div id="content">
+              <div class="row"><div class="large-8 columns">
+                <h1>Favourites</h1>
+                <p>A list by <a class="user" href="/~someone">someone</a></p>
+              </div></div>
+              <table id="user_list">
+                <tr class="trodd">
+                  <td class="number">26</td>
+                  <td class="list_art"><a href="/release/album/radiohead/kid-a/"><img src="" /></a></td>
+                  <td class="main_entry">
+                    <h2><a class="list_artist" href="/artist/radiohead">Radiohead</a></h2>
+                    <h3><a class="list_album" href="/release/album/radiohead/kid-a/">Kid A</a> <span class="rel_date">(2000)</span></h3>
+                    <div class="generic_item"><span class="rendered_text">Where it all <b>changed</b>.</span></div>
+                  </td>
+                </tr>
+                <tr class="treven">
+                  <td class="number">27</td>
+                  <td class="main_entry">
+                    <h2><a class="list_song" href="/song/burial-kode9/ghost-hardware/">Ghost Hardware</a> <span class="list_song_title_date">2007</span></h2>
+                    <h3 class="list_song_artists"><a class="list_artist" href="/artist/burial">Burial</a> &amp; <a class="list_artist" href="/artist/kode9">Kode9</a></h3>
+                  </td>
+                </tr>
+                <tr><td colspan="3">Comments</td></tr>
+              </table>
+              <span class="navspan">
+                <a class="navlinkprev" href="/list/someone/favourites/1/">&lt;&lt;</a>
+                <a class="navlinknum" href="/list/someone/favourites/1/">1</a>
+                <span class="navlinkcurrent">2</span>
+                <a class="navlinknum" href="/list/someone/favourites/3/">3</a>
+                <a class="navlinknext" href="/list/someone/favourites/3/">&gt;&gt;</a>
+              </span>
+            </div>"#;
+        let page = parse_list_page(html, "https://rateyourmusic.com/list/someone/favourites/2/").unwrap();
+
+        assert_eq!(page.collection.rym_url, "https://rateyourmusic.com/list/someone/favourites/");
+        assert_eq!(page.collection.author, "someone");
+        assert_eq!(page.collection.entries, vec![
+            CollectionEntry {
+                position: 26,
+                entry_type: "album".to_string(),
+                artist_name: "Radiohead".to_string(),
+                title: "Kid A".to_string(),
+                rym_url: "https://rateyourmusic.com/release/album/radiohead/kid-a/".to_string(),
+                year: Some(2000),
+                description: Some("Where it all changed.".to_string()),
+            },
+            CollectionEntry {
+                position: 27,
+                entry_type: "song".to_string(),
+                artist_name: "Burial, Kode9".to_string(),
+                title: "Ghost Hardware".to_string(),
+                rym_url: "https://rateyourmusic.com/song/burial-kode9/ghost-hardware/".to_string(),
+                year: Some(2007),
+                description: None,
+            },
+        ]);
+        assert_eq!(page.pages.len(), 2);
+        assert_eq!(page.next_page.as_deref(), Some("https://rateyourmusic.com/list/someone/favourites/3/"));
+    }
+
+    #[test]
+    fn parses_lists_portal() {
+        let html = include_str!("../../../sample_pages/lists_sample.html");
+        let lists = parse_lists_page(html);
+
+        assert_eq!(lists.len(), 25);
+        assert_eq!(lists[1], ListSummary {
+            title: "Songs Featuring Bird Sounds".to_string(),
+            author: "MagicalRose".to_string(),
+            rym_url: "https://rateyourmusic.com/list/MagicalRose/songs-featuring-bird-sounds/".to_string(),
+            item_count: Some(383),
+            updated: "23 December, 2025".to_string(),
+            description: lists[1].description.clone(),
+        });
+        assert!(lists[1].description.as_deref().unwrap().starts_with("What can I say? I love birds."));
+        assert_eq!(lists[2].item_count, Some(1194));
+    }
+
+    #[test]
+    fn rejects_non_list_page() {
+        let html = include_str!("../../../sample_pages/release_album_sample.html");
+        assert!(parse_list_page(html, "").is_err());
+    }
+}
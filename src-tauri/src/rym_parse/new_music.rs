@@ -0,0 +1,100 @@
+use super::{absolute_url, clean_text, parse_count, sel};
+use crate::database::UpcomingRelease;
+use scraper::{ElementRef, Html};
+use std::collections::HashSet;
+
+// Parse the releases on RYM's new music page (/new-music/). "All New Releases" and
+// "My New Releases" overlap, so each release is kept once, in page order.
+pub fn parse_new_music_page(html: &str) -> Vec<UpcomingRelease> {
+    let doc = Html::parse_document(html);
+    let timestamp = chrono::Utc::now().timestamp();
+    let mut seen = HashSet::new();
+
+    doc.select(&sel("#newreleases_container .newreleases_itembox"))
+        .filter_map(|item| parse_item(item, timestamp))
+        .filter(|release| seen.insert(release.rym_url.clone()))
+        .collect()
+}
+
+fn parse_item(item: ElementRef, timestamp: i64) -> Option<UpcomingRelease> {
+    let link = item.select(&sel("a.newreleases_item_title")).next()?;
+    let href = link.value().attr("href")?;
+    // "/release/album/mac-demarco/seven-off-the-two/"
+    let release_type = href.split('/').nth(2).unwrap_or("album");
+
+    let artists: Vec<_> = item.select(&sel(".newreleases_item_artist a.artist")).collect();
+    // Unrated releases show "-"
+    let stat = |selector: &str| item.select(&sel(selector)).next().map(clean_text).filter(|s| s != "-");
+
+    Some(UpcomingRelease {
+        release_type: release_type.to_string(),
+        title: clean_text(link),
+        artist_name: artists.iter().map(|a| clean_text(*a)).collect::<Vec<_>>().join(" & "),
+        artist_urls: artists.iter().filter_map(|a| a.value().attr("href")).map(absolute_url).collect(),
+        rym_url: absolute_url(href),
+        release_date: item
+            .select(&sel(".newreleases_item_releasedate"))
+            .next()
+            .map(clean_text)
+            .unwrap_or_default(),
+        // Each genre sits in its own span with a trailing comma
+        genres: item
+            .select(&sel(".newreleases_item_genres"))
+            .map(|g| clean_text(g).trim_end_matches(',').to_string())
+            .filter(|g| !g.is_empty())
+            .collect::<Vec<_>>()
+            .join(", "),
+        rating: stat(".newreleases_avg_rating_stat").and_then(|r| r.parse().ok()),
+        count: stat(".newreleases_ratings_stat").and_then(|c| parse_count(&c)),
+        wants: stat(".newreleases_wishlist_stat").and_then(|w| parse_count(&w)),
+        timestamp,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_new_music_page() {
+        let html = include_str!("../../../sample_pages/new-music_sample.html");
+        let releases = parse_new_music_page(html);
+
+        // The 12 personal picks are all among the 25 new releases
+        assert_eq!(releases.len(), 25);
+        assert_eq!(releases[0], UpcomingRelease {
+            release_type: "album".to_string(),
+            title: "Tranquilizer".to_string(),
+            artist_name: "Oneohtrix Point Never".to_string(),
+            artist_urls: vec!["https://rateyourmusic.com/artist/oneohtrix-point-never".to_string()],
+            rym_url: "https://rateyourmusic.com/release/album/oneohtrix-point-never/tranquilizer/".to_string(),
+            release_date: "21 November 2025".to_string(),
+            genres: "Progressive Electronic, Ambient, Sound Collage".to_string(),
+            rating: Some(3.84),
+            count: Some(8396),
+            wants: Some(1153),
+            timestamp: releases[0].timestamp,
+        });
+        assert_eq!(releases[1].release_type, "additional");
+        assert_eq!(releases[1].title, "Tentative Decisions: Demos & Live");
+
+        // Not rated yet, and only the month is known
+        let frog = &releases[3];
+        assert_eq!(frog.release_date, "December 2025");
+        assert_eq!((frog.rating, frog.count, frog.wants), (None, None, Some(9)));
+        assert_eq!(frog.genres, "");
+
+        let collab = &releases[4];
+        assert_eq!(collab.artist_name, "TOBi & Real Bad Man");
+        assert_eq!(collab.artist_urls.len(), 2);
+
+        let urls: HashSet<_> = releases.iter().map(|r| &r.rym_url).collect();
+        assert_eq!(urls.len(), releases.len());
+    }
+
+    #[test]
+    fn ignores_pages_without_new_releases() {
+        let html = include_str!("../../../sample_pages/release_album_sample.html");
+        assert!(parse_new_music_page(html).is_empty());
+    }
+}
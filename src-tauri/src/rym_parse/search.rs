@@ -0,0 +1,165 @@
+use super::{absolute_url, clean_text, sel};
+use crate::matcher::{self, Candidate};
+use scraper::Html;
+use serde::Serialize;
+
+// A release found on a RYM search results page
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchResult {
+    pub rym_url: String,
+    pub artist_name: String,
+    pub title: String,
+    pub year: Option<i32>,
+    pub release_type: String, // "album", "ep", "single", ... taken from the URL
+}
+
+// RYM's release search, shared by every lookup that has to find a release page
+pub fn search_url(artist: &str, album: &str) -> String {
+    format!(
+        "https://rateyourmusic.com/search?searchterm={}&searchtype=l",
+        urlencoding::encode(&format!("{} {}", artist, album))
+    )
+}
+
+// Parse the releases on a RYM search page, in the order RYM listed them.
+// Artists, songs and labels in a mixed search are skipped.
+pub fn parse_search_page(html: &str) -> Vec<SearchResult> {
+    let doc = Html::parse_document(html);
+
+    doc.select(&sel("tr.infobox"))
+        .filter_map(|row| {
+            let link = row
+                .select(&sel("a.searchpage"))
+                .find(|a| a.value().attr("href").is_some_and(|h| h.starts_with("/release/")))?;
+            let href = link.value().attr("href")?;
+            // "/release/album/radiohead/kid-a/"
+            let release_type = href.split('/').nth(2).unwrap_or("album");
+
+            // First cell of the details table: "2020"
+            let year = row
+                .select(&sel("table.mbgen td"))
+                .next()
+                .and_then(|td| clean_text(td).get(..4)?.parse().ok());
+
+            Some(SearchResult {
+                rym_url: absolute_url(href),
+                artist_name: row.select(&sel("a.artist")).map(clean_text).collect::<Vec<_>>().join(" & "),
+                title: clean_text(link),
+                year,
+                release_type: release_type.to_string(),
+            })
+        })
+        .collect()
+}
+
+// Apple Music marks non-albums in the title ("Query - Single")
+fn split_type_suffix(album: &str) -> (&str, Option<&str>) {
+    for (suffix, release_type) in [(" - Single", "single"), (" - EP", "ep")] {
+        if let Some(title) = album.strip_suffix(suffix) {
+            return (title, Some(release_type));
+        }
+    }
+    (album, None)
+}
+
+// Rank search results against an Apple Music album, best first. Equal scores go to
+// the release type Apple Music hinted at, then albums before EPs, singles and the
+// rest; remaining ties keep RYM's order.
+pub fn rank_search_results(results: Vec<SearchResult>, artist: &str, album: &str) -> Vec<Candidate<SearchResult>> {
+    let (title, hinted_type) = split_type_suffix(album);
+    let priority = |release_type: &str| {
+        if Some(release_type) == hinted_type {
+            return 0;
+        }
+        match release_type {
+            "album" => 1,
+            "ep" => 2,
+            "comp" => 3,
+            "single" => 4,
+            _ => 5,
+        }
+    };
+
+    let mut ranked = matcher::rank(
+        title,
+        artist,
+        results.into_iter().map(|r| {
+            let (t, a) = (r.title.clone(), r.artist_name.clone());
+            (r, t, a)
+        }),
+    );
+    ranked.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| priority(&a.item.release_type).cmp(&priority(&b.item.release_type)))
+    });
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_release_results() {
+        let html = include_str!("../../../sample_pages/search_sample.html");
+        let results = parse_search_page(html);
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0], SearchResult {
+            rym_url: "https://rateyourmusic.com/release/album/mais-uma/quero-quero-quero-quero/".to_string(),
+            artist_name: "Mais Uma".to_string(),
+            title: "QUERO QUERO QUERO QUERO".to_string(),
+            year: Some(2020),
+            release_type: "album".to_string(),
+        });
+        assert_eq!(results[1].release_type, "single");
+        assert_eq!(results[1].title, "Query:");
+        assert_eq!(results[3].release_type, "ep");
+        assert_eq!(results[3].year, Some(2016));
+    }
+
+    #[test]
+    fn ranks_the_matching_release_first() {
+        let html = include_str!("../../../sample_pages/search_sample.html");
+
+        let ranked = rank_search_results(parse_search_page(html), "Privacy", "Default Query - EP");
+        assert_eq!(ranked[0].item.rym_url, "https://rateyourmusic.com/release/ep/privacy/default-query/");
+        assert!(ranked[0].score >= matcher::DEFAULT_THRESHOLD);
+
+        let ranked = rank_search_results(parse_search_page(html), "Lamp DX", "Query - Single");
+        assert_eq!(ranked[0].item.rym_url, "https://rateyourmusic.com/release/single/lamp-dx/query/");
+
+        // Two releases with the same name: RYM's order decides
+        let ranked = rank_search_results(parse_search_page(html), "Mais Uma", "Quero Quero Quero Quero");
+        assert_eq!(ranked[0].item.year, Some(2020));
+        assert_eq!(ranked[1].item.year, Some(2022));
+
+        let ranked = rank_search_results(parse_search_page(html), "Radiohead", "Kid A");
+        assert!(ranked[0].score < matcher::DEFAULT_THRESHOLD);
+    }
+
+    #[test]
+    fn prefers_albums_on_equal_scores() {
+        let result = |release_type: &str| SearchResult {
+            rym_url: format!("https://rateyourmusic.com/release/{}/radiohead/creep/", release_type),
+            artist_name: "Radiohead".to_string(),
+            title: "Creep".to_string(),
+            year: None,
+            release_type: release_type.to_string(),
+        };
+
+        let ranked = rank_search_results(vec![result("single"), result("album")], "Radiohead", "Creep");
+        assert_eq!(ranked[0].item.release_type, "album");
+        let ranked = rank_search_results(vec![result("album"), result("single")], "Radiohead", "Creep - Single");
+        assert_eq!(ranked[0].item.release_type, "single");
+    }
+
+    #[test]
+    fn builds_release_search_url() {
+        assert_eq!(
+            search_url("Sigur Rós", "( )"),
+            "https://rateyourmusic.com/search?searchterm=Sigur%20R%C3%B3s%20%28%20%29&searchtype=l"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use super::{absolute_url, clean_text, join_links, original_name, parse_count, sel};
+use crate::database::{SongAppearance, SongRating};
+use scraper::Html;
+
+// Parse a RYM song page (/song/<artist>/<title>/) into a SongRating.
+// `page_url` is used as `rym_url`; if empty, the URL from the share box is used.
+pub fn parse_song_page(html: &str, page_url: &str) -> Result<SongRating, String> {
+    let doc = Html::parse_document(html);
+    let header = doc
+        .select(&sel("#page_song_temp_section_header"))
+        .next()
+        .ok_or_else(|| "Song header not found".to_string())?;
+
+    let title = header
+        .select(&sel("h1"))
+        .next()
+        .map(original_name)
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| "Song title not found".to_string())?;
+
+    let artist_name = header
+        .select(&sel(".page_song_header_info_artist a.artist"))
+        .map(original_name)
+        .collect::<Vec<_>>()
+        .join(", ");
+    if artist_name.is_empty() {
+        return Err("Song artist not found".to_string());
+    }
+
+    let rym_url = if page_url.is_empty() {
+        doc.select(&sel("input[aria-label=\"URL\"]"))
+            .next()
+            .and_then(|i| i.value().attr("value"))
+            .unwrap_or_default()
+            .to_string()
+    } else {
+        page_url.to_string()
+    };
+
+    let rating = header
+        .select(&sel(".page_section_main_info_music_rating_value_rating"))
+        .next()
+        .and_then(|el| clean_text(el).parse::<f32>().ok());
+    // "32 ratings"
+    let rating_count = header
+        .select(&sel(".page_section_main_info_music_rating_value_number"))
+        .next()
+        .and_then(|el| parse_count(clean_text(el).split_whitespace().next()?))
+        .unwrap_or(0);
+
+    let genres = header
+        .select(&sel(".page_song_header_info_genre_item_primary"))
+        .next()
+        .map(|el| join_links(el, &sel("a.genre")))
+        .unwrap_or_default();
+    let secondary_genres = header
+        .select(&sel(".page_song_header_info_genre_item_secondary"))
+        .next()
+        .map(|el| join_links(el, &sel("a.genre")))
+        .filter(|s| !s.is_empty());
+
+    // "Song | Released 23 November 2013"
+    let release_date = header
+        .select(&sel(".page_song_header_info_rest .pipe_separated"))
+        .find_map(|el| clean_text(el).strip_prefix("Released ").map(str::to_string))
+        .unwrap_or_default();
+
+    Ok(SongRating {
+        title,
+        artist_name,
+        rym_url,
+        rating,
+        rating_count,
+        genres,
+        secondary_genres,
+        release_date,
+        appearances: parse_appearances(&doc),
+        timestamp: chrono::Utc::now().timestamp(),
+    })
+}
+
+// The release named in the header ("Track 1 on <album>") first, then the rest of the
+// "Appears on" section. The section only links cover art, so its titles come from
+// the aria-label and may be empty.
+fn parse_appearances(doc: &Html) -> Vec<SongAppearance> {
+    let mut appearances: Vec<SongAppearance> = doc
+        .select(&sel(".page_song_header_info_rest"))
+        .filter_map(|row| {
+            let link = row.select(&sel("a.album")).next()?;
+            let text = clean_text(row);
+            let track_number = text
+                .strip_prefix("Track ")
+                .and_then(|rest| rest.split_whitespace().next())
+                .map(str::to_string);
+            Some(SongAppearance {
+                title: clean_text(link),
+                rym_url: absolute_url(link.value().attr("href")?),
+                track_number,
+            })
+        })
+        .collect();
+
+    for link in doc.select(&sel("#page_song_temp_section_appears_on a[href^=\"/release/\"]")) {
+        let rym_url = absolute_url(link.value().attr("href").unwrap_or_default());
+        if appearances.iter().any(|a| a.rym_url == rym_url) {
+            continue;
+        }
+        appearances.push(SongAppearance {
+            title: link.value().attr("aria-label").unwrap_or_default().trim().to_string(),
+            rym_url,
+            track_number: None,
+        });
+    }
+    appearances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_song_page() {
+        let html = include_str!("../../../sample_pages/song_sample.html");
+        let song = parse_song_page(html, "").unwrap();
+
+        assert_eq!(song.title, "Enterprise");
+        assert_eq!(song.artist_name, "t e l e p a t h テレパシー能力者");
+        assert_eq!(song.rym_url, "https://rateyourmusic.com/song/t-e-l-e-p-a-t-h-テレパシー能力者/enterprise/");
+        assert_eq!(song.rating, Some(3.51));
+        assert_eq!(song.rating_count, 32);
+        assert_eq!(song.genres, "Vaporwave");
+        assert_eq!(song.secondary_genres, None);
+        assert_eq!(song.release_date, "23 November 2013");
+        // The header and the "Appears on" section name the same release
+        assert_eq!(song.appearances, vec![SongAppearance {
+            title: "末永く [Suenagaku]".to_string(),
+            rym_url: "https://rateyourmusic.com/release/album/t-e-l-e-p-a-t-h-テレパシー能力者/末永く/".to_string(),
+            track_number: Some("1".to_string()),
+        }]);
+    }
+
+    #[test]
+    fn lists_every_release_a_song_appears_on() {
+        let html = r#"
+            <section id="page_song_temp_section_header">
+              <h1><span class="ui_name_locale"><span class="ui_name_locale_original">Windowlicker</span></span></h1>
+              <h2 class="page_song_header_info_artist"><a class="artist" href="/artist/aphex-twin">Aphex Twin</a></h2>
+              <div class="page_song_header_info_genre">
+                <div class="page_song_header_info_genre_item_primary"><a class="genre" href="/genre/idm/">IDM</a></div>
+                <div class="page_song_header_info_genre_item_secondary"><a class="genre" href="/genre/drill-and-bass/">Drill and Bass</a></div>
+              </div>
+              <div class="page_song_header_info_rest">Track 1 on <a href="/release/single/aphex-twin/windowlicker/" class="album">Windowlicker</a></div>
+            </section>
+            <section id="page_song_temp_section_appears_on">
+              <a aria-label="Windowlicker" class="page_song_section_appears_on_image" href="/release/single/aphex-twin/windowlicker/"></a>
+              <a aria-label="Classics" class="page_song_section_appears_on_image" href="/release/comp/aphex-twin/classics/"></a>
+            </section>"#;
+        let song = parse_song_page(html, "https://rateyourmusic.com/song/aphex-twin/windowlicker/").unwrap();
+
+        assert_eq!(song.rym_url, "https://rateyourmusic.com/song/aphex-twin/windowlicker/");
+        // Unrated songs have no score
+        assert_eq!(song.rating, None);
+        assert_eq!(song.rating_count, 0);
+        assert_eq!(song.secondary_genres.as_deref(), Some("Drill and Bass"));
+        assert_eq!(song.appearances.len(), 2);
+        assert_eq!(song.appearances[0].track_number.as_deref(), Some("1"));
+        assert_eq!(song.appearances[1].title, "Classics");
+        assert_eq!(song.appearances[1].track_number, None);
+    }
+
+    #[test]
+    fn rejects_non_song_page() {
+        let html = include_str!("../../../sample_pages/release_album_sample.html");
+        assert!(parse_song_page(html, "").is_err());
+    }
+}
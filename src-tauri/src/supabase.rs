@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use crate::database::AlbumRating;
+use crate::database::{parse_json_list, AlbumRating};
 use reqwest::Client;
 use std::env;
 
@@ -83,8 +83,8 @@ impl SupabaseClient {
             descriptors: r.descriptors,
             language: r.language,
             rank: r.rank,
-            track_ratings: r.track_ratings,
-            reviews: r.reviews,
+            track_ratings: r.track_ratings.map(|json| parse_json_list(&json)).unwrap_or_default(),
+            reviews: r.reviews.map(|json| parse_json_list(&json)).unwrap_or_default(),
             release_date: r.release_date,
             timestamp: chrono::Utc::now().timestamp(),
             status: None,
@@ -106,8 +106,8 @@ impl SupabaseClient {
             descriptors: rating.descriptors.clone(),
             language: rating.language.clone(),
             rank: rating.rank.clone(),
-            track_ratings: rating.track_ratings.clone(),
-            reviews: rating.reviews.clone(),
+            track_ratings: serde_json::to_string(&rating.track_ratings).ok(),
+            reviews: serde_json::to_string(&rating.reviews).ok(),
             release_date: rating.release_date.clone(),
         };
 
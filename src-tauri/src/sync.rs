@@ -0,0 +1,390 @@
+use crate::database::normalize_key;
+use std::fmt;
+
+mod controller;
+
+pub use controller::{MusicSync, SyncController, RYM_HOME_URL};
+
+// Keeps the Apple Music and RYM windows on the same album without ping-pong. Every
+// navigation we start makes the other window report the album it landed on, and that
+// report must not be synced back. Each window's reports go through one transition here
+// (`rym_reported` for RYM, `music_reported` for Apple Music) that decides what to do.
+
+// How long after a navigation a report of the awaited album is taken as its echo.
+// RYM searches can take two page loads plus the rate limit before the release shows.
+pub const ECHO_TIMEOUT_SECS: i64 = 60;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncAlbum {
+    pub artist: String,
+    pub album: String,
+}
+
+impl SyncAlbum {
+    pub fn new(artist: &str, album: &str) -> Self {
+        SyncAlbum { artist: artist.to_string(), album: album.to_string() }
+    }
+
+    // Both windows name albums their own way: "Kid A" on RYM can be
+    // "Kid A (Remastered)" or "Kid A Mnesia" on Apple Music
+    pub fn matches(&self, other: &SyncAlbum) -> bool {
+        loosely_equal(&self.artist, &other.artist) && loosely_equal(&self.album, &other.album)
+    }
+}
+
+impl fmt::Display for SyncAlbum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} - {}", self.artist, self.album)
+    }
+}
+
+fn loosely_equal(a: &str, b: &str) -> bool {
+    let (a, b) = (normalize_key(a), normalize_key(b));
+    !a.is_empty() && !b.is_empty() && (a.contains(&b) || b.contains(&a))
+}
+
+// "https://music.apple.com/us/album/kid-a/1097861387?i=1097861390" -> "1097861387"
+fn music_album_id(url: &str) -> Option<&str> {
+    let path = url.split(['?', '#']).next()?.trim_end_matches('/');
+    let id = path.rsplit('/').next()?;
+    (path.contains("/album/") && !id.is_empty() && id.chars().all(|c| c.is_ascii_digit())).then_some(id)
+}
+
+// RYM links to geo.music.apple.com in whatever storefront it was submitted from, which
+// redirects to the user's own; the album id is the same in all of them
+pub fn same_music_url(a: &str, b: &str) -> bool {
+    match (music_album_id(a), music_album_id(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a.replace("geo.music.apple.com", "music.apple.com") == b.replace("geo.music.apple.com", "music.apple.com"),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncState {
+    Idle,
+    // Apple Music was sent to `url` for the album open on RYM
+    AwaitingMusic { album: SyncAlbum, url: String, since: i64 },
+    // RYM was sent to a release page, for the album open on Apple Music if it knows it
+    AwaitingRym { album: Option<SyncAlbum>, since: i64 },
+}
+
+// What to do with the Apple Music window after RYM reported an album
+#[derive(Debug, Clone, PartialEq)]
+pub enum MusicAction {
+    Navigate(String),
+    // RYM loading the page we sent it to
+    Echo,
+    AlreadyShowing,
+    // The release has no Apple Music link
+    NoLink,
+}
+
+// What to do with the RYM window after Apple Music reported an album
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RymAction {
+    // Apple Music loading the album we sent it to: refresh the rating UI, don't navigate RYM
+    Echo,
+    AlreadyShowing,
+    Proceed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct MusicPage {
+    album: SyncAlbum,
+    url: Option<String>,
+}
+
+pub struct SyncMachine {
+    state: SyncState,
+    music: Option<MusicPage>,
+    rym: Option<SyncAlbum>,
+}
+
+impl SyncMachine {
+    pub fn new() -> Self {
+        SyncMachine { state: SyncState::Idle, music: None, rym: None }
+    }
+
+    #[cfg(test)]
+    pub fn state(&self) -> &SyncState {
+        &self.state
+    }
+
+    // The RYM window shows a release, with its Apple Music link if it has one. Only the
+    // album RYM was sent to is its echo; a navigation without one takes any release.
+    pub fn rym_reported(&mut self, album: SyncAlbum, url: Option<&str>, now: i64) -> MusicAction {
+        let echo = match &self.state {
+            SyncState::AwaitingRym { album: awaited, since } if now - since < ECHO_TIMEOUT_SECS => {
+                awaited.as_ref().is_none_or(|awaited| awaited.matches(&album))
+            }
+            _ => false,
+        };
+        if matches!(self.state, SyncState::AwaitingRym { .. }) {
+            self.state = SyncState::Idle;
+        }
+        self.rym = Some(album.clone());
+        if echo {
+            return MusicAction::Echo;
+        }
+
+        let Some(url) = url else {
+            return MusicAction::NoLink;
+        };
+        if let Some(music) = &self.music {
+            if music.album.matches(&album) || music.url.as_deref().is_some_and(|u| same_music_url(u, url)) {
+                return MusicAction::AlreadyShowing;
+            }
+        }
+
+        self.state = SyncState::AwaitingMusic { album: album.clone(), url: url.to_string(), since: now };
+        self.music = Some(MusicPage { album, url: Some(url.to_string()) });
+        MusicAction::Navigate(url.to_string())
+    }
+
+    // The Apple Music window shows an album. It is the echo when it is the album or URL
+    // Apple Music was sent to; a forced sync is never an echo.
+    pub fn music_reported(&mut self, album: SyncAlbum, url: Option<&str>, force: bool, now: i64) -> RymAction {
+        let echo = !force
+            && match &self.state {
+                SyncState::AwaitingMusic { album: awaited, url: awaited_url, since } if now - since < ECHO_TIMEOUT_SECS => {
+                    awaited.matches(&album) || url.is_some_and(|url| same_music_url(awaited_url, url))
+                }
+                _ => false,
+            };
+        if matches!(self.state, SyncState::AwaitingMusic { .. }) {
+            self.state = SyncState::Idle;
+        }
+        self.music = Some(MusicPage { album: album.clone(), url: url.map(str::to_string) });
+        if echo {
+            return RymAction::Echo;
+        }
+
+        if self.rym.as_ref().is_some_and(|rym| rym.matches(&album)) {
+            return RymAction::AlreadyShowing;
+        }
+        RymAction::Proceed
+    }
+
+    // We are sending the RYM window to a release; the next release it reports is that page
+    pub fn rym_navigation_started(&mut self, album: Option<SyncAlbum>, now: i64) {
+        self.state = SyncState::AwaitingRym { album: album.clone(), since: now };
+        if album.is_some() {
+            self.rym = album;
+        }
+    }
+
+    // A rating was scraped from the RYM window
+    pub fn rym_loaded(&mut self, album: SyncAlbum) {
+        self.rym = Some(album);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KID_A_RYM: &str = "https://geo.music.apple.com/gb/album/kid-a/1097861387";
+    const KID_A_AM: &str = "https://music.apple.com/us/album/kid-a-mnesia/1097861387?l=en";
+    const OK_COMPUTER: &str = "https://geo.music.apple.com/us/album/ok-computer/1097861837";
+
+    fn kid_a() -> SyncAlbum {
+        SyncAlbum::new("Radiohead", "Kid A")
+    }
+
+    fn ok_computer() -> SyncAlbum {
+        SyncAlbum::new("Radiohead", "OK Computer")
+    }
+
+    #[test]
+    fn rym_to_music_echo_is_not_synced_back() {
+        let mut sync = SyncMachine::new();
+
+        assert_eq!(sync.rym_reported(kid_a(), Some(KID_A_RYM), 0), MusicAction::Navigate(KID_A_RYM.to_string()));
+        assert!(matches!(sync.state(), SyncState::AwaitingMusic { .. }));
+
+        // Apple Music lands on its own storefront, naming the album differently
+        let landed = SyncAlbum::new("Radiohead", "KID A MNESIA");
+        assert_eq!(sync.music_reported(landed, Some(KID_A_AM), false, 5), RymAction::Echo);
+        assert_eq!(sync.state(), &SyncState::Idle);
+
+        // The user moving on in Apple Music is synced again
+        assert_eq!(sync.music_reported(ok_computer(), Some(OK_COMPUTER), false, 30), RymAction::Proceed);
+    }
+
+    #[test]
+    fn music_to_rym_echo_is_not_synced_back() {
+        let mut sync = SyncMachine::new();
+
+        assert_eq!(sync.music_reported(kid_a(), Some(KID_A_AM), false, 0), RymAction::Proceed);
+        sync.rym_navigation_started(Some(kid_a()), 0);
+
+        // The search landed on a release RYM names differently, linking another storefront
+        let landed = SyncAlbum::new("Radiohead", "Kid A Mnesia");
+        assert_eq!(sync.rym_reported(landed, Some(KID_A_RYM), 12), MusicAction::Echo);
+        assert_eq!(sync.state(), &SyncState::Idle);
+
+        // Browsing to another release on RYM takes Apple Music along
+        assert_eq!(sync.rym_reported(ok_computer(), Some(OK_COMPUTER), 40), MusicAction::Navigate(OK_COMPUTER.to_string()));
+    }
+
+    // Each navigation makes the other window report; a whole exchange must settle after
+    // the one navigation the user asked for, whichever window it started in
+    #[test]
+    fn round_trips_settle_after_one_navigation() {
+        // RYM -> AM -> RYM -> AM
+        let mut sync = SyncMachine::new();
+        let mut navigations = 0;
+        if let MusicAction::Navigate(_) = sync.rym_reported(kid_a(), Some(KID_A_RYM), 0) {
+            navigations += 1;
+        }
+        match sync.music_reported(SyncAlbum::new("Radiohead", "Kid A (Remastered)"), Some(KID_A_AM), false, 3) {
+            RymAction::Proceed => navigations += 1,
+            action => assert_eq!(action, RymAction::Echo),
+        }
+        // RYM polls the same page again
+        assert_eq!(sync.rym_reported(kid_a(), Some(KID_A_RYM), 6), MusicAction::AlreadyShowing);
+        assert_eq!(navigations, 1);
+
+        // AM -> RYM -> AM -> RYM
+        let mut sync = SyncMachine::new();
+        assert_eq!(sync.music_reported(kid_a(), Some(KID_A_AM), false, 0), RymAction::Proceed);
+        sync.rym_navigation_started(Some(kid_a()), 0);
+        assert_eq!(sync.rym_reported(kid_a(), Some(KID_A_RYM), 8), MusicAction::Echo);
+        // Apple Music polls the same album again
+        assert_eq!(sync.music_reported(kid_a(), Some(KID_A_AM), false, 9), RymAction::AlreadyShowing);
+        assert_eq!(sync.rym_reported(kid_a(), Some(KID_A_RYM), 12), MusicAction::AlreadyShowing);
+    }
+
+    #[test]
+    fn same_album_is_not_navigated_again() {
+        let mut sync = SyncMachine::new();
+        sync.music_reported(kid_a(), Some(KID_A_AM), false, 0);
+
+        // Names that differ but share the album id
+        let renamed = SyncAlbum::new("Radiohead", "Kid A Mnesia Edition");
+        assert_eq!(sync.rym_reported(renamed, Some(KID_A_RYM), 1), MusicAction::AlreadyShowing);
+        assert_eq!(sync.state(), &SyncState::Idle);
+
+        // RYM already showing the album Apple Music reports
+        sync.rym_loaded(ok_computer());
+        assert_eq!(sync.music_reported(ok_computer(), Some(OK_COMPUTER), false, 2), RymAction::AlreadyShowing);
+    }
+
+    #[test]
+    fn late_reports_are_not_echoes() {
+        let mut sync = SyncMachine::new();
+        sync.rym_reported(kid_a(), Some(KID_A_RYM), 0);
+        assert_eq!(sync.music_reported(ok_computer(), Some(OK_COMPUTER), false, ECHO_TIMEOUT_SECS), RymAction::Proceed);
+        assert_eq!(sync.state(), &SyncState::Idle);
+
+        let mut sync = SyncMachine::new();
+        sync.rym_navigation_started(Some(kid_a()), 0);
+        assert_eq!(sync.rym_reported(ok_computer(), Some(OK_COMPUTER), ECHO_TIMEOUT_SECS), MusicAction::Navigate(OK_COMPUTER.to_string()));
+    }
+
+    #[test]
+    fn forced_sync_is_never_an_echo() {
+        let mut sync = SyncMachine::new();
+        sync.rym_reported(kid_a(), Some(KID_A_RYM), 0);
+        assert_eq!(sync.music_reported(ok_computer(), Some(OK_COMPUTER), true, 1), RymAction::Proceed);
+        assert_eq!(sync.state(), &SyncState::Idle);
+        // ...and it doesn't leave an echo behind for the next report
+        let amnesiac = SyncAlbum::new("Radiohead", "Amnesiac");
+        assert_eq!(sync.music_reported(amnesiac, None, false, 2), RymAction::Proceed);
+    }
+
+    #[test]
+    fn release_without_apple_music_link_consumes_the_echo() {
+        let mut sync = SyncMachine::new();
+        sync.music_reported(kid_a(), Some(KID_A_AM), false, 0);
+        sync.rym_navigation_started(Some(kid_a()), 0);
+        assert_eq!(sync.rym_reported(kid_a(), None, 5), MusicAction::Echo);
+
+        assert_eq!(sync.rym_reported(ok_computer(), None, 10), MusicAction::NoLink);
+        assert_eq!(sync.state(), &SyncState::Idle);
+        // RYM is on OK Computer now
+        assert_eq!(sync.music_reported(ok_computer(), None, false, 11), RymAction::AlreadyShowing);
+    }
+
+    #[test]
+    fn a_newer_navigation_replaces_the_pending_one() {
+        let mut sync = SyncMachine::new();
+        sync.rym_reported(kid_a(), Some(KID_A_RYM), 0);
+        // The user clicks through to another release before Apple Music reported
+        assert_eq!(sync.rym_reported(ok_computer(), Some(OK_COMPUTER), 2), MusicAction::Navigate(OK_COMPUTER.to_string()));
+        assert!(matches!(sync.state(), SyncState::AwaitingMusic { url, .. } if url == OK_COMPUTER));
+        assert_eq!(sync.music_reported(ok_computer(), Some(OK_COMPUTER), false, 4), RymAction::Echo);
+
+        let mut sync = SyncMachine::new();
+        sync.rym_navigation_started(Some(kid_a()), 0);
+        sync.rym_navigation_started(Some(ok_computer()), 2);
+        assert!(matches!(sync.state(), SyncState::AwaitingRym { album, .. } if album.as_ref() == Some(&ok_computer())));
+    }
+
+    // Every report in every state: (case, state, report, expected action, state after)
+    #[test]
+    fn transition_table() {
+        let awaiting_music = SyncState::AwaitingMusic { album: kid_a(), url: KID_A_RYM.to_string(), since: 0 };
+        let awaiting_rym = SyncState::AwaitingRym { album: Some(kid_a()), since: 0 };
+
+        // (case, state, reported album and URL, forced, expected)
+        type MusicCase<'a> = (&'a str, &'a SyncState, (SyncAlbum, &'a str), bool, RymAction);
+        let music_cases: &[MusicCase] = &[
+            ("idle", &SyncState::Idle, (kid_a(), KID_A_AM), false, RymAction::Proceed),
+            ("idle, forced", &SyncState::Idle, (kid_a(), KID_A_AM), true, RymAction::Proceed),
+            ("awaiting music", &awaiting_music, (kid_a(), KID_A_AM), false, RymAction::Echo),
+            ("awaiting music, forced", &awaiting_music, (kid_a(), KID_A_AM), true, RymAction::Proceed),
+            ("awaiting music, other album", &awaiting_music, (ok_computer(), OK_COMPUTER), false, RymAction::Proceed),
+            ("awaiting rym", &awaiting_rym, (kid_a(), KID_A_AM), false, RymAction::AlreadyShowing),
+            ("awaiting rym, forced", &awaiting_rym, (kid_a(), KID_A_AM), true, RymAction::AlreadyShowing),
+            ("awaiting rym, other album", &awaiting_rym, (ok_computer(), OK_COMPUTER), false, RymAction::Proceed),
+        ];
+        for (case, state, (album, url), force, expected) in music_cases {
+            let (state, force, expected) = (*state, *force, *expected);
+            let mut sync = SyncMachine::new();
+            if let SyncState::AwaitingRym { album, since } = state {
+                sync.rym_navigation_started(album.clone(), *since);
+            }
+            sync.state = state.clone();
+            assert_eq!(sync.music_reported(album.clone(), Some(url), force, 1), expected, "{}", case);
+            let after = if matches!(state, SyncState::AwaitingRym { .. }) { state } else { &SyncState::Idle };
+            assert_eq!(sync.state(), after, "{}", case);
+        }
+
+        let awaiting_any_rym = SyncState::AwaitingRym { album: None, since: 0 };
+        let rym_cases: &[(&str, &SyncState, SyncAlbum, Option<&str>, MusicAction)] = &[
+            ("idle", &SyncState::Idle, ok_computer(), Some(OK_COMPUTER), MusicAction::Navigate(OK_COMPUTER.to_string())),
+            ("idle, no link", &SyncState::Idle, ok_computer(), None, MusicAction::NoLink),
+            ("awaiting music", &awaiting_music, ok_computer(), Some(OK_COMPUTER), MusicAction::Navigate(OK_COMPUTER.to_string())),
+            ("awaiting music, no link", &awaiting_music, ok_computer(), None, MusicAction::NoLink),
+            ("awaiting rym", &awaiting_rym, kid_a(), Some(KID_A_RYM), MusicAction::Echo),
+            ("awaiting rym, no link", &awaiting_rym, kid_a(), None, MusicAction::Echo),
+            ("awaiting rym, other album", &awaiting_rym, ok_computer(), Some(OK_COMPUTER), MusicAction::Navigate(OK_COMPUTER.to_string())),
+            ("awaiting rym, other album, no link", &awaiting_rym, ok_computer(), None, MusicAction::NoLink),
+            ("awaiting any release", &awaiting_any_rym, ok_computer(), Some(OK_COMPUTER), MusicAction::Echo),
+        ];
+        for (case, state, album, url, expected) in rym_cases {
+            let mut sync = SyncMachine::new();
+            sync.state = (*state).clone();
+            assert_eq!(&sync.rym_reported(album.clone(), *url, 1), expected, "{}", case);
+            let after = match (state, expected) {
+                (_, MusicAction::Navigate(url)) => SyncState::AwaitingMusic { album: album.clone(), url: url.clone(), since: 1 },
+                (SyncState::AwaitingMusic { .. }, MusicAction::NoLink) => (*state).clone(),
+                _ => SyncState::Idle,
+            };
+            assert_eq!(sync.state(), &after, "{}", case);
+        }
+    }
+
+    #[test]
+    fn matches_albums_and_urls_loosely() {
+        assert!(kid_a().matches(&SyncAlbum::new("RADIOHEAD", "Kid A (Remastered)")));
+        assert!(SyncAlbum::new("Björk", "Homogenic").matches(&SyncAlbum::new("Bjork", "homogenic")));
+        assert!(!kid_a().matches(&ok_computer()));
+        assert!(!kid_a().matches(&SyncAlbum::new("", "")));
+
+        assert!(same_music_url(KID_A_RYM, KID_A_AM));
+        assert!(!same_music_url(KID_A_RYM, OK_COMPUTER));
+        assert!(same_music_url("https://geo.music.apple.com/us/browse", "https://music.apple.com/us/browse"));
+    }
+}
@@ -0,0 +1,668 @@
+use super::{MusicAction, RymAction, SyncAlbum, SyncMachine};
+use crate::browser::{BrowserSurface, Window};
+use crate::database::{AlbumRating, FetchPriority, QueuedFetch};
+use crate::fetch_queue::FetchQueue;
+use crate::rym_parse;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+pub const RYM_HOME_URL: &str = "https://rateyourmusic.com";
+
+// An album the Apple Music window reported
+pub struct MusicSync {
+    pub artist: String,
+    pub album: String,
+    // Sent by the music window on its own rather than by the user
+    pub background: bool,
+    // Skip the cache and reload the RYM page
+    pub force: bool,
+    pub music_url: Option<String>,
+}
+
+// Moves the windows for a sync: decides with the SyncMachine, loads RYM pages through
+// the fetch queue and remembers the search the RYM window is resolving
+pub struct SyncController {
+    machine: Mutex<SyncMachine>,
+    queue: Arc<FetchQueue>,
+    // Pages the sync flow wants in the RYM window, with the album a release page was
+    // requested for. Anything else in the queue may be fetched off screen.
+    requested: Mutex<HashMap<String, Option<SyncAlbum>>>,
+    rym_initialized: Mutex<bool>, // Track if RYM window has been loaded at least once
+    pending_searches: Mutex<HashMap<String, (String, String)>>, // (artist, album) per search URL sent to the RYM window
+}
+
+impl SyncController {
+    pub fn new(queue: Arc<FetchQueue>) -> Self {
+        SyncController {
+            machine: Mutex::new(SyncMachine::new()),
+            queue,
+            requested: Mutex::new(HashMap::new()),
+            rym_initialized: Mutex::new(false),
+            pending_searches: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // A rating was scraped from the RYM window
+    pub fn rym_loaded(&self, album: SyncAlbum) {
+        self.machine.lock().unwrap().rym_loaded(album);
+    }
+
+    // The album a search was started for, once its results page loads
+    pub fn take_pending_search(&self, url: &str) -> Option<(String, String)> {
+        self.pending_searches.lock().unwrap().remove(url)
+    }
+
+    // Works through the fetch queue for as long as the app runs. Pages nobody asked to
+    // see go to `offscreen` first, which may fetch them without the window; the rest
+    // are loaded into the RYM window.
+    pub async fn run_rym_queue<F, Fut>(&self, browser: &dyn BrowserSurface, offscreen: F)
+    where
+        F: Fn(&QueuedFetch) -> Option<Fut>,
+        Fut: Future<Output = Result<(), String>>,
+    {
+        let offscreen = &offscreen;
+        self.queue
+            .run(move |page| async move {
+                if !self.is_requested(&page.url) {
+                    if let Some(fetch) = offscreen(&page) {
+                        let result = fetch.await;
+                        // Unless the user asked for it meanwhile
+                        if !self.is_requested(&page.url) {
+                            return result;
+                        }
+                    }
+                }
+                self.load_rym_page(browser, &page.url)
+            })
+            .await
+    }
+
+    fn is_requested(&self, url: &str) -> bool {
+        self.requested.lock().unwrap().contains_key(url)
+    }
+
+    // Every release page we load reports back through `sync_to_music`, so loading one
+    // starts the wait for that echo
+    fn load_rym_page(&self, browser: &dyn BrowserSurface, url: &str) -> Result<(), String> {
+        let album = self.requested.lock().unwrap().remove(url).flatten();
+        if url.contains("/release/") {
+            self.machine.lock().unwrap().rym_navigation_started(album, now());
+        }
+        let result = browser.navigate(Window::Rym, url);
+        if result.is_ok() {
+            *self.rym_initialized.lock().unwrap() = true;
+        }
+        result
+    }
+
+    // Queues a RYM page and waits until the window has been sent there
+    pub async fn navigate_rym(&self, url: &str, priority: FetchPriority) -> Result<(), String> {
+        self.requested.lock().unwrap().entry(url.to_string()).or_insert(None);
+        self.queue.fetch(url, priority).await
+    }
+
+    // Opens a release page for an album shown in Apple Music
+    pub async fn open_rym_release(&self, url: &str, album: SyncAlbum, priority: FetchPriority) -> Result<(), String> {
+        self.requested.lock().unwrap().insert(url.to_string(), Some(album));
+        self.navigate_rym(url, priority).await
+    }
+
+    // Searches RYM for an album; the results page picks the release
+    pub async fn search_rym(&self, artist: &str, album: &str, priority: FetchPriority) -> Result<(), String> {
+        let url = rym_parse::search_url(artist, album);
+        self.pending_searches.lock().unwrap().insert(url.clone(), (artist.to_string(), album.to_string()));
+        self.navigate_rym(&url, priority).await
+    }
+
+    pub fn show_music(&self, browser: &dyn BrowserSurface) {
+        browser.show(Window::Music);
+        browser.hide(Window::Rym);
+    }
+
+    pub async fn show_rym(&self, browser: &dyn BrowserSurface) -> Result<(), String> {
+        println!("RYM-SHOW: ========================================");
+        println!("RYM-SHOW: show_rym command called");
+
+        if !browser.has_window(Window::Rym) {
+            println!("RYM-SHOW: ❌ ERROR - RYM window not found!");
+            println!("RYM-SHOW: ========================================");
+            return Err("RYM window not found".to_string());
+        }
+
+        let needs_init = !*self.rym_initialized.lock().unwrap();
+        if needs_init {
+            println!("RYM-INIT: First time showing RYM window (or previous init failed), loading homepage...");
+            match self.navigate_rym(RYM_HOME_URL, FetchPriority::Foreground).await {
+                Ok(_) => println!("RYM-SHOW: ✓ Navigation completed successfully"),
+                // Stays uninitialized, so it tries again next time
+                Err(e) => println!("RYM-SHOW: ❌ Navigation failed: {}", e),
+            }
+        } else {
+            println!("RYM-SHOW: RYM already initialized, skipping navigation");
+        }
+
+        browser.show(Window::Rym);
+        browser.hide(Window::Music);
+        println!("RYM-SHOW: ✓ RYM window shown and focused, Music window hidden");
+        println!("RYM-SHOW: ========================================");
+        Ok(())
+    }
+
+    // The RYM window reported the release it shows: sends Apple Music to the same album
+    // unless this is RYM loading a page we sent it to
+    pub fn sync_to_music(&self, browser: &dyn BrowserSurface, shown: SyncAlbum, url: Option<&str>) {
+        println!("RYM-APPLE-MUSIC: RYM is showing: {}", shown);
+        let action = self.machine.lock().unwrap().rym_reported(shown, url, now());
+
+        let url = match action {
+            MusicAction::Navigate(url) => url,
+            MusicAction::Echo => {
+                println!("RYM-APPLE-MUSIC: ❌ Ignoring sync request - RYM loaded the album we sent it to");
+                return;
+            }
+            MusicAction::AlreadyShowing => {
+                println!("RYM-APPLE-MUSIC: ❌ Ignoring sync request - AM is already on this album");
+                return;
+            }
+            MusicAction::NoLink => {
+                println!("RYM-APPLE-MUSIC: No Apple Music link for this release");
+                return;
+            }
+        };
+
+        // Navigate immediately so it's ready when the user switches
+        println!("RYM-APPLE-MUSIC: Navigating Apple Music to {}", url);
+        if let Err(e) = browser.navigate(Window::Music, &url) {
+            println!("RYM-APPLE-MUSIC: ❌ {}", e);
+            return;
+        }
+        // Let the user know in whichever window they are looking at
+        browser.toast(Window::Music, "Synced from RYM");
+        browser.toast(Window::Rym, "Synced to Apple Music");
+    }
+
+    // The Apple Music window reported an album: broadcasts its cached rating and brings
+    // the RYM window to the release when it needs refreshing or the user asked for it.
+    // `cached` is the best cached rating with its status; `alias_url` a manual match.
+    pub async fn sync_to_rym<C, Fut, A>(
+        &self,
+        browser: &dyn BrowserSurface,
+        request: &MusicSync,
+        cached: C,
+        alias_url: A,
+    ) -> Result<(), String>
+    where
+        C: FnOnce() -> Fut,
+        Fut: Future<Output = Option<AlbumRating>>,
+        A: FnOnce() -> Option<String>,
+    {
+        let MusicSync { artist, album, background, force, music_url } = request;
+        let (background, force) = (*background, *force);
+        let album_key = format!("{} - {}", artist, album);
+
+        println!("RYM-SYNC: ========================================");
+        println!("RYM-SYNC: Starting sync to RYM for: {}", album_key);
+        println!("RYM-SYNC: Background mode: {}, Force: {}", background, force);
+        if let Some(url) = music_url {
+            println!("RYM-SYNC: Source Music URL: {}", url);
+        }
+
+        let action = self.machine.lock().unwrap().music_reported(
+            SyncAlbum::new(artist, album),
+            music_url.as_deref(),
+            force,
+            now(),
+        );
+        // Apple Music loading the album RYM sent it to: update the UI but don't navigate RYM
+        let skip_rym_navigation = action == RymAction::Echo;
+        if skip_rym_navigation {
+            println!("RYM-SYNC: 🛑 Apple Music loaded the album we sent it to - Will skip RYM navigation but allow UI update");
+        }
+
+        // LOOP PREVENTION: If RYM window already has this album, skip.
+        let match_found = action == RymAction::AlreadyShowing;
+        if match_found && !force && background {
+            println!("RYM-SYNC: ❌ Skipping sync - RYM window already has {}", album_key);
+            println!("RYM-SYNC: ========================================");
+            return Ok(());
+        }
+
+        if !browser.has_window(Window::Rym) {
+            println!("RYM-SYNC: ❌ ERROR - RYM window not found!");
+            return Err("RYM window not found".to_string());
+        }
+
+        let mut best_candidate: Option<AlbumRating> = None;
+        let mut broadcast = false;
+
+        // STEP 1/2: Check local SQLite, then Supabase (Skip if force)
+        if !force {
+            println!("RYM-SYNC: Checking local and Supabase caches...");
+            match cached().await {
+                Some(rating) if rating.status.as_deref() == Some("fresh") => {
+                    println!("RYM-SYNC: ✓ CACHE HIT (FRESH)");
+                    println!("RYM-SYNC: Broadcasting fresh cached data...");
+                    browser.emit_rating(&rating);
+                    broadcast = true;
+
+                    // If foreground, we still need to show the window
+                    if !background {
+                        if match_found {
+                            println!("RYM-SYNC: Match already loaded. Showing window.");
+                            self.show_rym_window(browser);
+                            return Ok(());
+                        }
+                    } else {
+                        return Ok(());
+                    }
+                    // If foreground but not match_found, continue to navigation using the cached URL
+                    best_candidate = Some(rating);
+                }
+                Some(rating) => {
+                    println!("RYM-SYNC: ⚠️ CACHE HIT (STALE)");
+                    best_candidate = Some(rating);
+                }
+                None => println!("RYM-SYNC: ❌ Cache miss"),
+            }
+        }
+
+        // STEP 3: Fallback / Miss Handling
+
+        // Always emit current best status so UI can show buttons (Refresh/Open),
+        // unless the fresh cache hit above already did
+        match &best_candidate {
+            Some(_) if broadcast => {}
+            Some(rating) => {
+                println!("RYM-SYNC: Broadcasting current candidate status: {:?}", rating.status);
+                browser.emit_rating(rating);
+            }
+            None => {
+                println!("RYM-SYNC: Broadcasting MISSING status to UI...");
+                browser.emit_rating(&missing_rating(artist, album));
+            }
+        }
+
+        // DECIDE TO NAVIGATE
+        let is_rym_visible = browser.is_visible(Window::Rym);
+
+        // Rule: If background (auto-sync) AND not on RYM tab AND NOT force -> NO NAVIGATION
+        // EXCEPTION: If data is MISSING (no best_candidate), we allow one background auto-nav to fetch it.
+        if background && !is_rym_visible && !force && best_candidate.is_some() {
+            println!("RYM-SYNC: Auto background sync & User NOT on RYM tab. Data exists but stale. Skipping navigation.");
+            return Ok(());
+        }
+
+        // If we have a match already and not forcing, just show window and return
+        // BUT only if we aren't already on the RYM tab (if we are, we might want to refresh)
+        if !force && match_found && !background && !is_rym_visible {
+            println!("RYM-SYNC: Match already loaded. Showing window (No Nav).");
+            self.show_rym_window(browser);
+            return Ok(());
+        }
+
+        if skip_rym_navigation {
+            println!("RYM-SYNC: 🛑 Skipping RYM navigation (echo of a RYM sync)");
+            return Ok(());
+        }
+
+        // User actions first, then refreshing what is on screen, then fetching ahead
+        let priority = if force || !background {
+            FetchPriority::Foreground
+        } else if is_rym_visible {
+            FetchPriority::Refresh
+        } else {
+            FetchPriority::Prefetch
+        };
+
+        // DETERMINE TARGET URL
+        let target_url = match best_candidate {
+            Some(rating) => Some(rating.rym_url),
+            None => alias_url().inspect(|url| println!("RYM-SYNC: Using manual alias: {}", url)),
+        };
+
+        match target_url {
+            Some(url) => {
+                println!("RYM-SYNC: Navigating to refresh/find data: {}", url);
+                self.open_rym_release(&url, SyncAlbum::new(artist, album), priority).await?;
+            }
+            None => {
+                // The results page picks the release
+                println!("RYM-SYNC: Searching RYM for {}", album_key);
+                self.search_rym(artist, album, priority).await?;
+            }
+        }
+        println!("RYM-SYNC: ✓ Navigation initiated");
+
+        // Show toast notifications
+        if background {
+            let message = format!("Synced: {}", album);
+            browser.toast(Window::Rym, &message);
+            browser.toast(Window::Music, &message);
+        } else {
+            self.show_rym_window(browser);
+        }
+
+        println!("RYM-SYNC: ========================================");
+        Ok(())
+    }
+
+    fn show_rym_window(&self, browser: &dyn BrowserSurface) {
+        browser.show(Window::Rym);
+        browser.hide(Window::Music);
+    }
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+// Lets the UI offer a search when nothing is cached
+fn missing_rating(artist: &str, album: &str) -> AlbumRating {
+    AlbumRating {
+        album_name: album.to_string(),
+        artist_name: artist.to_string(),
+        rym_rating: 0.0,
+        rating_count: 0,
+        rym_url: "NO_MATCH".to_string(),
+        genres: "".to_string(),
+        secondary_genres: None,
+        descriptors: None,
+        language: None,
+        rank: None,
+        track_ratings: Vec::new(),
+        reviews: Vec::new(),
+        release_date: "".to_string(),
+        timestamp: now(),
+        status: Some("missing".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser::{toast_js, BrowserEvent, FakeBrowser};
+    use crate::database::Database;
+    use crate::fetch_queue::TokenBucket;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    const KID_A_APPLE: &str = "https://geo.music.apple.com/gb/album/kid-a/1097861387";
+    const KID_A_MUSIC: &str = "https://music.apple.com/us/album/kid-a-mnesia/1097861387";
+    const KID_A_RYM: &str = "https://rateyourmusic.com/release/album/radiohead/kid-a/";
+
+    fn rating(status: &str) -> AlbumRating {
+        AlbumRating {
+            rym_url: KID_A_RYM.to_string(),
+            rym_rating: 4.2,
+            rating_count: 70000,
+            status: Some(status.to_string()),
+            ..missing_rating("Radiohead", "Kid A")
+        }
+    }
+
+    fn report(album: &str, background: bool, force: bool) -> MusicSync {
+        MusicSync {
+            artist: "Radiohead".to_string(),
+            album: album.to_string(),
+            background,
+            force,
+            music_url: Some(KID_A_MUSIC.to_string()),
+        }
+    }
+
+    // A controller whose queue loads pages into `browser` without rate limiting
+    fn controller(browser: &FakeBrowser) -> Arc<SyncController> {
+        controller_with(browser, None)
+    }
+
+    // Same, fetching pages off screen into `offscreen` when given
+    fn controller_with(browser: &FakeBrowser, offscreen: Option<Arc<Mutex<Vec<String>>>>) -> Arc<SyncController> {
+        let db = Arc::new(Mutex::new(Database::new(PathBuf::from(":memory:")).unwrap()));
+        let queue = Arc::new(FetchQueue::new(db, TokenBucket::new(100, 1000.0)));
+        let sync = Arc::new(SyncController::new(queue));
+        let (worker, browser) = (sync.clone(), browser.clone());
+        tokio::spawn(async move {
+            let fetch = |page: &QueuedFetch| {
+                let fetched = offscreen.clone()?;
+                fetched.lock().unwrap().push(page.url.clone());
+                Some(std::future::ready(Ok(())))
+            };
+            worker.run_rym_queue(&browser, fetch).await
+        });
+        sync
+    }
+
+    fn no_alias() -> Option<String> {
+        None
+    }
+
+    fn navigated(window: Window, url: &str) -> BrowserEvent {
+        BrowserEvent::Navigate(window, url.to_string())
+    }
+
+    fn toast(window: Window, message: &str) -> BrowserEvent {
+        BrowserEvent::Eval(window, toast_js(message))
+    }
+
+    fn emitted(url: &str, status: &str) -> BrowserEvent {
+        BrowserEvent::Rating(url.to_string(), Some(status.to_string()))
+    }
+
+    #[tokio::test]
+    async fn rym_to_music_round_trip() {
+        let browser = FakeBrowser::new();
+        let sync = controller(&browser);
+
+        sync.sync_to_music(&browser, SyncAlbum::new("Radiohead", "Kid A"), Some(KID_A_APPLE));
+        assert_eq!(browser.take_events(), vec![
+            navigated(Window::Music, KID_A_APPLE),
+            toast(Window::Music, "Synced from RYM"),
+            toast(Window::Rym, "Synced to Apple Music"),
+        ]);
+
+        // Apple Music loads it and reports back: the rating UI updates, RYM stays put
+        sync.sync_to_rym(&browser, &report("Kid A Mnesia", true, false), || async { Some(rating("stale")) }, no_alias)
+            .await
+            .unwrap();
+        assert_eq!(browser.take_events(), vec![emitted(KID_A_RYM, "stale")]);
+
+        // RYM polling the same page doesn't send Apple Music anywhere
+        sync.sync_to_music(&browser, SyncAlbum::new("Radiohead", "Kid A"), Some(KID_A_APPLE));
+        assert!(browser.take_events().is_empty());
+    }
+
+    #[tokio::test]
+    async fn music_to_rym_round_trip() {
+        let browser = FakeBrowser::new();
+        let sync = controller(&browser);
+
+        // Nothing cached: one background search to fetch it
+        sync.sync_to_rym(&browser, &report("Kid A", true, false), || async { None }, no_alias).await.unwrap();
+        let search_url = rym_parse::search_url("Radiohead", "Kid A");
+        assert_eq!(browser.take_events(), vec![
+            emitted("NO_MATCH", "missing"),
+            navigated(Window::Rym, &search_url),
+            toast(Window::Rym, "Synced: Kid A"),
+            toast(Window::Music, "Synced: Kid A"),
+        ]);
+        assert_eq!(sync.take_pending_search(&search_url), Some(("Radiohead".to_string(), "Kid A".to_string())));
+
+        // The results page opens the best match, which reports back without moving Apple Music
+        sync.open_rym_release(KID_A_RYM, SyncAlbum::new("Radiohead", "Kid A"), FetchPriority::Foreground).await.unwrap();
+        assert_eq!(browser.take_events(), vec![navigated(Window::Rym, KID_A_RYM)]);
+        sync.sync_to_music(&browser, SyncAlbum::new("Radiohead", "Kid A"), Some(KID_A_APPLE));
+        assert!(browser.take_events().is_empty());
+
+        // Apple Music polling the same album again
+        sync.sync_to_rym(&browser, &report("Kid A", true, false), || async { None }, no_alias).await.unwrap();
+        assert!(browser.take_events().is_empty());
+    }
+
+    #[tokio::test]
+    async fn background_sync_uses_the_cache() {
+        // (case, cached status, RYM visible, RYM navigated to the release)
+        let cases: &[(&str, Option<&str>, bool, bool)] = &[
+            ("fresh", Some("fresh"), false, false),
+            ("fresh, on RYM", Some("fresh"), true, false),
+            ("stale", Some("stale"), false, false),
+            ("stale, on RYM", Some("stale"), true, true),
+        ];
+        for &(case, status, rym_visible, navigates) in cases {
+            let browser = FakeBrowser::new();
+            if rym_visible {
+                browser.show(Window::Rym);
+                browser.take_events();
+            }
+            let sync = controller(&browser);
+            let cached = status.map(rating);
+            sync.sync_to_rym(&browser, &report("Kid A", true, false), || async { cached }, no_alias).await.unwrap();
+
+            let events = browser.take_events();
+            assert_eq!(events[0], emitted(KID_A_RYM, status.unwrap()), "{}", case);
+            assert_eq!(events.contains(&navigated(Window::Rym, KID_A_RYM)), navigates, "{}", case);
+        }
+    }
+
+    #[tokio::test]
+    async fn foreground_sync_brings_rym_forward() {
+        let browser = FakeBrowser::new();
+        let sync = controller(&browser);
+
+        sync.sync_to_rym(&browser, &report("Kid A", false, false), || async { Some(rating("fresh")) }, no_alias)
+            .await
+            .unwrap();
+        assert_eq!(browser.take_events(), vec![
+            emitted(KID_A_RYM, "fresh"),
+            navigated(Window::Rym, KID_A_RYM),
+            BrowserEvent::Show(Window::Rym),
+            BrowserEvent::Hide(Window::Music),
+        ]);
+        assert!(browser.is_visible(Window::Rym) && !browser.is_visible(Window::Music));
+
+        // Back in Apple Music, asking again only switches windows
+        sync.show_music(&browser);
+        browser.take_events();
+        sync.sync_to_rym(&browser, &report("Kid A", false, false), || async { Some(rating("fresh")) }, no_alias)
+            .await
+            .unwrap();
+        assert_eq!(browser.take_events(), vec![
+            emitted(KID_A_RYM, "fresh"),
+            BrowserEvent::Show(Window::Rym),
+            BrowserEvent::Hide(Window::Music),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn each_results_page_resolves_its_own_search() {
+        let browser = FakeBrowser::new();
+        let sync = controller(&browser);
+
+        sync.search_rym("Radiohead", "Kid A", FetchPriority::Foreground).await.unwrap();
+        sync.search_rym("Radiohead", "Amnesiac", FetchPriority::Foreground).await.unwrap();
+
+        let amnesiac = rym_parse::search_url("Radiohead", "Amnesiac");
+        assert_eq!(sync.take_pending_search(&amnesiac), Some(("Radiohead".to_string(), "Amnesiac".to_string())));
+        assert_eq!(sync.take_pending_search(&amnesiac), None);
+        let kid_a = rym_parse::search_url("Radiohead", "Kid A");
+        assert_eq!(sync.take_pending_search(&kid_a), Some(("Radiohead".to_string(), "Kid A".to_string())));
+    }
+
+    #[tokio::test]
+    async fn forced_sync_skips_the_cache() {
+        let browser = FakeBrowser::new();
+        let sync = controller(&browser);
+        let alias = || Some("https://rateyourmusic.com/release/album/radiohead/kid-a-mnesia/".to_string());
+
+        let cached = || async { panic!("a forced sync must not read the cache") };
+        sync.sync_to_rym(&browser, &report("Kid A", true, true), cached, alias).await.unwrap();
+        let events = browser.take_events();
+        assert_eq!(events[..2], [
+            emitted("NO_MATCH", "missing"),
+            navigated(Window::Rym, "https://rateyourmusic.com/release/album/radiohead/kid-a-mnesia/"),
+        ]);
+        assert_eq!(sync.take_pending_search(&rym_parse::search_url("Radiohead", "Kid A")), None);
+    }
+
+    #[tokio::test]
+    async fn only_release_pages_are_echoes() {
+        let browser = FakeBrowser::new();
+        let sync = controller(&browser);
+
+        // A background refresh loads a release; its report must not move Apple Music
+        sync.queue.enqueue(KID_A_RYM, FetchPriority::Refresh).unwrap();
+        while browser.navigations(Window::Rym).is_empty() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        sync.sync_to_music(&browser, SyncAlbum::new("Radiohead", "Kid A"), Some(KID_A_APPLE));
+        assert_eq!(browser.take_events(), vec![navigated(Window::Rym, KID_A_RYM)]);
+
+        // The homepage reports nothing, so the next release is the user's own click
+        sync.navigate_rym(RYM_HOME_URL, FetchPriority::Foreground).await.unwrap();
+        browser.take_events();
+        let ok_computer = "https://geo.music.apple.com/us/album/ok-computer/1097861837";
+        sync.sync_to_music(&browser, SyncAlbum::new("Radiohead", "OK Computer"), Some(ok_computer));
+        assert_eq!(browser.take_events()[0], navigated(Window::Music, ok_computer));
+    }
+
+    #[tokio::test]
+    async fn unrequested_pages_are_fetched_off_screen() {
+        let browser = FakeBrowser::new();
+        let fetched = Arc::new(Mutex::new(Vec::new()));
+        let sync = controller_with(&browser, Some(fetched.clone()));
+
+        // A background refresh never moves the RYM window
+        sync.queue.enqueue(KID_A_RYM, FetchPriority::Refresh).unwrap();
+        while fetched.lock().unwrap().is_empty() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert_eq!(browser.take_events(), vec![]);
+
+        // The same page asked for by the sync flow is shown
+        sync.open_rym_release(KID_A_RYM, SyncAlbum::new("Radiohead", "Kid A"), FetchPriority::Refresh).await.unwrap();
+        assert_eq!(browser.take_events(), vec![navigated(Window::Rym, KID_A_RYM)]);
+        assert_eq!(*fetched.lock().unwrap(), vec![KID_A_RYM.to_string()]);
+    }
+
+    #[tokio::test]
+    async fn show_rym_loads_the_homepage_once() {
+        let browser = FakeBrowser::new();
+        let sync = controller(&browser);
+
+        sync.show_rym(&browser).await.unwrap();
+        assert_eq!(browser.take_events(), vec![
+            navigated(Window::Rym, RYM_HOME_URL),
+            BrowserEvent::Show(Window::Rym),
+            BrowserEvent::Hide(Window::Music),
+        ]);
+
+        sync.show_music(&browser);
+        sync.show_rym(&browser).await.unwrap();
+        assert_eq!(browser.navigations(Window::Rym).len(), 1);
+        assert!(browser.is_visible(Window::Rym));
+    }
+
+    #[tokio::test]
+    async fn missing_rym_window_is_an_error() {
+        let browser = FakeBrowser::without(Window::Rym);
+        let sync = controller(&browser);
+
+        assert_eq!(sync.show_rym(&browser).await, Err("RYM window not found".to_string()));
+        let result = sync.sync_to_rym(&browser, &report("Kid A", false, false), || async { None }, no_alias).await;
+        assert_eq!(result, Err("RYM window not found".to_string()));
+        assert!(sync.navigate_rym(RYM_HOME_URL, FetchPriority::Foreground).await.is_err());
+
+        // Apple Music can still follow RYM links
+        let browser = FakeBrowser::without(Window::Rym);
+        sync.sync_to_music(&browser, SyncAlbum::new("Radiohead", "OK Computer"), Some("https://music.apple.com/us/album/ok-computer/1097861837"));
+        assert_eq!(browser.navigations(Window::Music).len(), 1);
+    }
+
+    #[test]
+    fn toasts_are_escaped() {
+        assert_eq!(
+            toast_js("Synced: \"Heroes\" `live`"),
+            r#"if (window.showSyncToast) window.showSyncToast("Synced: \"Heroes\" `live`")"#
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use crate::database::RatingSnapshot;
+use crate::release_date::{compute_ttl_seconds, ReleaseDate};
+use serde::Deserialize;
+use std::path::Path;
+
+// What a policy knows about a cached rating when deciding how long it stays fresh
+#[derive(Debug, Clone, Copy)]
+pub struct TtlInput<'a> {
+    pub now: i64,
+    pub release: Option<&'a ReleaseDate>,
+    pub rating_count: i32,
+    // Score change between the album's last two snapshots, if it has two
+    pub score_change: Option<f32>,
+}
+
+pub trait TtlPolicy: Send + Sync {
+    fn ttl_seconds(&self, input: &TtlInput) -> i64;
+}
+
+// Release age only
+pub struct AgePolicy;
+
+impl TtlPolicy for AgePolicy {
+    fn ttl_seconds(&self, input: &TtlInput) -> i64 {
+        compute_ttl_seconds(input.now, input.release)
+    }
+}
+
+// Thresholds for PopularityPolicy, read from `ttl_settings.json` in the app data dir.
+// Missing fields keep their defaults.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct TtlSettings {
+    // With this many ratings the score hardly moves any more
+    pub popular_count: i32,
+    pub popular_factor: f32,
+    // Below this many ratings every new one can shift the score
+    pub obscure_count: i32,
+    pub obscure_factor: f32,
+    // A score that moved this much since the previous snapshot is still settling
+    pub volatile_delta: f32,
+    pub volatile_factor: f32,
+    pub min_ttl_seconds: i64,
+    pub max_ttl_seconds: i64,
+}
+
+impl Default for TtlSettings {
+    fn default() -> Self {
+        TtlSettings {
+            popular_count: 1000,
+            popular_factor: 2.0,
+            obscure_count: 20,
+            obscure_factor: 0.5,
+            volatile_delta: 0.05,
+            volatile_factor: 0.5,
+            min_ttl_seconds: 12 * 3600,
+            max_ttl_seconds: 365 * 86400,
+        }
+    }
+}
+
+impl TtlSettings {
+    // Defaults when the file is missing; a broken file is reported and ignored
+    pub fn load(path: &Path) -> TtlSettings {
+        match std::fs::read_to_string(path) {
+            Ok(json) => match serde_json::from_str(&json) {
+                Ok(settings) => {
+                    println!("RYM-TTL: ✓ Loaded TTL settings from {}", path.display());
+                    settings
+                }
+                Err(e) => {
+                    eprintln!("RYM-TTL: ❌ Ignoring invalid {}: {}", path.display(), e);
+                    TtlSettings::default()
+                }
+            },
+            Err(_) => TtlSettings::default(),
+        }
+    }
+}
+
+// The release-age schedule, stretched for popular albums and shortened for obscure
+// ones and for scores that are still moving
+pub struct PopularityPolicy {
+    settings: TtlSettings,
+}
+
+impl PopularityPolicy {
+    pub fn new(settings: TtlSettings) -> Self {
+        PopularityPolicy { settings }
+    }
+}
+
+impl TtlPolicy for PopularityPolicy {
+    fn ttl_seconds(&self, input: &TtlInput) -> i64 {
+        let s = &self.settings;
+        let mut factor = 1.0;
+        if input.rating_count >= s.popular_count {
+            factor *= s.popular_factor;
+        } else if input.rating_count < s.obscure_count {
+            factor *= s.obscure_factor;
+        }
+        if input.score_change.is_some_and(|change| change.abs() >= s.volatile_delta) {
+            factor *= s.volatile_factor;
+        }
+
+        let ttl = (AgePolicy.ttl_seconds(input) as f64 * factor as f64) as i64;
+        ttl.clamp(s.min_ttl_seconds, s.max_ttl_seconds.max(s.min_ttl_seconds))
+    }
+}
+
+// Change between the last two distinct snapshots of a history in timestamp order.
+// Every save appends a snapshot, so re-saving an unchanged page (or copying it down
+// from Supabase) must not read as "the score stopped moving".
+pub fn score_change(history: &[RatingSnapshot]) -> Option<f32> {
+    let mut distinct: Vec<&RatingSnapshot> = Vec::new();
+    for snapshot in history.iter().rev() {
+        let repeated = distinct
+            .last()
+            .is_some_and(|last| last.rym_rating == snapshot.rym_rating && last.rating_count == snapshot.rating_count);
+        if !repeated {
+            distinct.push(snapshot);
+        }
+        if distinct.len() == 2 {
+            break;
+        }
+    }
+    match distinct[..] {
+        [current, previous] => Some(current.rym_rating - previous.rym_rating),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 86400;
+
+    fn snapshot(rym_rating: f32) -> RatingSnapshot {
+        RatingSnapshot { rym_rating, rating_count: 0, timestamp: 0 }
+    }
+
+    #[test]
+    fn popularity_policy_table() {
+        let now = ReleaseDate::parse("15 December 2025").unwrap().timestamp().unwrap();
+        let policy = PopularityPolicy::new(TtlSettings::default());
+
+        // (case, release date, rating count, score change, expected TTL)
+        let cases: &[(&str, &str, i32, Option<f32>, i64)] = &[
+            ("classic", "1 June 1971", 20000, None, 360 * DAY),
+            ("classic, still moving", "1 June 1971", 20000, Some(-0.06), 180 * DAY),
+            ("classic, steady", "1 June 1971", 20000, Some(0.01), 360 * DAY),
+            ("mid-size", "1 June 1971", 500, None, 180 * DAY),
+            ("obscure", "1 June 1971", 3, None, 90 * DAY),
+            ("obscure and moving", "1 June 1971", 3, Some(0.4), 45 * DAY),
+            ("popular new release", "10 December 2025", 5000, None, 2 * DAY),
+            ("obscure new release", "10 December 2025", 3, None, 12 * 3600),
+            ("obscure new release, moving", "10 December 2025", 3, Some(0.5), 12 * 3600),
+            ("popular, last year", "1 March 2024", 1500, None, 180 * DAY),
+            ("unknown date", "", 500, None, 180 * DAY),
+            ("unknown date, popular", "", 5000, None, 360 * DAY),
+        ];
+
+        for &(case, date, rating_count, score_change, expected) in cases {
+            let release = ReleaseDate::parse(date);
+            let input = TtlInput { now, release: release.as_ref(), rating_count, score_change };
+            assert_eq!(policy.ttl_seconds(&input), expected, "{}", case);
+        }
+    }
+
+    #[test]
+    fn settings_bound_and_tune_the_policy() {
+        let now = ReleaseDate::parse("15 December 2025").unwrap().timestamp().unwrap();
+        let old = ReleaseDate::parse("1971").unwrap();
+        let input = |rating_count| TtlInput { now, release: Some(&old), rating_count, score_change: None };
+
+        // (case, settings JSON, rating count, expected TTL)
+        let cases: &[(&str, &str, i32, i64)] = &[
+            ("defaults", "{}", 20000, 360 * DAY),
+            ("capped", r#"{"max_ttl_seconds": 2592000}"#, 20000, 30 * DAY),
+            ("higher popular bar", r#"{"popular_count": 50000}"#, 20000, 180 * DAY),
+            ("no obscure discount", r#"{"obscure_factor": 1.0}"#, 3, 180 * DAY),
+            ("floor wins over cap", r#"{"min_ttl_seconds": 864000, "max_ttl_seconds": 86400}"#, 3, 10 * DAY),
+        ];
+
+        for &(case, json, rating_count, expected) in cases {
+            let settings: TtlSettings = serde_json::from_str(json).unwrap();
+            let policy = PopularityPolicy::new(settings);
+            assert_eq!(policy.ttl_seconds(&input(rating_count)), expected, "{}", case);
+        }
+    }
+
+    #[test]
+    fn loads_settings_file() {
+        let path = std::env::temp_dir().join(format!("rym_ttl_settings_{}.json", std::process::id()));
+
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(TtlSettings::load(&path), TtlSettings::default());
+
+        std::fs::write(&path, r#"{"popular_count": 250, "volatile_delta": 0.1}"#).unwrap();
+        let settings = TtlSettings::load(&path);
+        assert_eq!((settings.popular_count, settings.volatile_delta), (250, 0.1));
+        assert_eq!(settings.obscure_count, TtlSettings::default().obscure_count);
+
+        std::fs::write(&path, "popular_count = 250").unwrap();
+        assert_eq!(TtlSettings::load(&path), TtlSettings::default());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn score_change_uses_last_two_distinct_snapshots() {
+        let cases: &[(&[f32], Option<f32>)] = &[
+            (&[], None),
+            (&[3.5], None),
+            (&[3.5, 3.75], Some(0.25)),
+            (&[3.0, 3.5, 3.25], Some(-0.25)),
+            (&[3.5, 3.5], None),
+            (&[3.5, 3.75, 3.75, 3.75], Some(0.25)),
+            (&[3.0, 3.5, 3.5, 3.25, 3.25], Some(-0.25)),
+        ];
+        for &(ratings, expected) in cases {
+            let history: Vec<_> = ratings.iter().map(|&r| snapshot(r)).collect();
+            assert_eq!(score_change(&history), expected, "{:?}", ratings);
+        }
+
+        // More ratings at the same score is still a new snapshot
+        let history = [snapshot(3.5), RatingSnapshot { rating_count: 10, ..snapshot(3.5) }];
+        assert_eq!(score_change(&history), Some(0.0));
+    }
+}